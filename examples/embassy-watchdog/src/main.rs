@@ -11,45 +11,35 @@
 #![no_std]
 #![no_main]
 
-use defmt::{info, warn};
+use core::future::Future;
+use defmt::{error, info, warn};
 use embassy_executor::Spawner;
-use embassy_stm32::gpio::{Input, Level, Output, Pull, Speed};
-use embassy_time::{Duration, Instant, Timer};
-use heartbeat_watchdog::{io::WatchdogIoAsync, WatchdogAsync, WatchdogConfig};
+use embassy_stm32::{
+    exti::ExtiInput,
+    gpio::{Level, Output, Pull, Speed},
+};
+use embassy_time::{Duration, Timer};
+use heartbeat_watchdog::{
+    io::embassy::{EdgeWait, ExtiIo},
+    WatchdogAsync, WatchdogConfig,
+};
 use static_cell::StaticCell;
 use {defmt_rtt as _, panic_probe as _};
 
-struct WatchB14 {
-    input: Input<'static>,
-    timeout: Duration,
-}
+/// The heartbeat input, edges are detected with EXTI interrupts
+struct WatchB14(ExtiInput<'static>);
 
-impl WatchdogIoAsync for WatchB14 {
-    async fn get(
-        &self,
-        expected: heartbeat_watchdog::Edge,
-    ) -> heartbeat_watchdog::Result<heartbeat_watchdog::Edge> {
-        let now = Instant::now();
-        loop {
-            if now.elapsed() > self.timeout {
-                break;
-            }
-            let edge: heartbeat_watchdog::Edge = bool::from(self.input.get_level()).into();
-            if edge == expected {
-                return Ok(edge);
-            }
-            embassy_time::Timer::after(Duration::from_micros(100)).await;
-        }
-        Err(heartbeat_watchdog::Error::Timeout)
+impl EdgeWait for WatchB14 {
+    fn wait_for_rising_edge(&mut self) -> impl Future<Output = ()> + Send {
+        self.0.wait_for_rising_edge()
     }
-
-    async fn clear(&self) -> heartbeat_watchdog::Result<()> {
-        Ok(())
+    fn wait_for_falling_edge(&mut self) -> impl Future<Output = ()> + Send {
+        self.0.wait_for_falling_edge()
     }
 }
 
 #[embassy_executor::task]
-async fn run_watchdog(watchdog: WatchdogAsync<WatchB14>) {
+async fn run_watchdog(watchdog: WatchdogAsync<ExtiIo<WatchB14>>) {
     watchdog.run().await.unwrap();
 }
 
@@ -62,17 +52,14 @@ async fn main(spawner: Spawner) {
     let mut fault_led = Output::new(p.PB13, Level::Low, Speed::Low);
     fault_led.set_high();
     info!("Watchdog started");
-    let w_input = Input::new(p.PB14, Pull::Down);
+    let w_input = ExtiInput::new(p.PB14, p.EXTI14, Pull::Down);
     let watchdog_config = WatchdogConfig::new(Duration::from_millis(10).into())
         .with_range(heartbeat_watchdog::Range::Window(
             Duration::from_millis(1).into(),
         ))
         .with_warmup(Duration::from_secs(2).into())
         .with_min_beats(200);
-    let watchdog_io = WatchB14 {
-        input: w_input,
-        timeout: watchdog_config.io_timeout().try_into().unwrap(),
-    };
+    let watchdog_io = ExtiIo::new(WatchB14(w_input), watchdog_config.io_timeout());
     let watchdog_channel = WATCHDOG_CHANNEL.init(heartbeat_watchdog::EmbassyStateChannel::new());
     let mut watchdog = WatchdogAsync::new(watchdog_config, watchdog_io);
     watchdog.set_state_tx(watchdog_channel.sender());
@@ -89,6 +76,24 @@ async fn main(spawner: Spawner) {
                     info!("Watchdog state OK");
                     fault_led.set_low();
                 }
+                heartbeat_watchdog::StateEvent::Warning => {
+                    warn!("Watchdog state WARNING");
+                    fault_led.set_low();
+                }
+                heartbeat_watchdog::StateEvent::Degraded { lost } => {
+                    warn!("Watchdog degraded, {} beats lost", lost);
+                }
+                heartbeat_watchdog::StateEvent::HighLatency { latency } => {
+                    warn!("Watchdog high latency: {} us", latency.as_micros());
+                }
+                heartbeat_watchdog::StateEvent::Paused => {
+                    info!("Watchdog paused");
+                    fault_led.set_low();
+                }
+                heartbeat_watchdog::StateEvent::Terminated => {
+                    error!("Watchdog terminated");
+                    fault_led.set_high();
+                }
             }
         }
         Timer::after_millis(1).await;
@@ -0,0 +1,206 @@
+//! Watchdog example for ESP32-C3
+//!
+//! This example demonstrates the usage of the `heartbeat_watchdog` on an ESP32 board which
+//! supervises two heartbeat sources at once: a local GPIO signal and a remote one, received over
+//! Wi-Fi with UDP (e.g. from a PLC or a Linux host which uses `UdpHeart`).
+//!
+//! Set Wi-Fi credentials in `.cargo/config.toml` (or with `SSID`/`PASSWORD` environment
+//! variables).
+//!
+//! Pins:
+//!
+//! - GPIO8: Fault LED (on-board for most dev kits), lights up when any watchdog detects a fault
+//! - GPIO4: Input, the external heartbeat signal
+//!
+//! UDP: port 9999
+#![no_std]
+#![no_main]
+
+use embassy_executor::Spawner;
+use embassy_net::{
+    udp::{PacketMetadata, UdpSocket},
+    Runner, StackResources,
+};
+use embassy_time::{Duration, Timer};
+use esp_backtrace as _;
+use esp_hal::{
+    clock::CpuClock,
+    gpio::{Input, Level, Output, Pull},
+    rng::Rng,
+    timer::{systimer::SystemTimer, timg::TimerGroup},
+};
+use esp_wifi::{
+    wifi::{
+        ClientConfiguration, Configuration, WifiController, WifiDevice, WifiEvent, WifiStaDevice,
+        WifiState,
+    },
+    EspWifiController,
+};
+use heartbeat_watchdog::{
+    io::embassy::{udp::UdpIo, WaitIo},
+    EmbassyStateChannel, StateEvent, WatchdogAsync, WatchdogConfig,
+};
+use log::{error, info, warn};
+use static_cell::StaticCell;
+
+const SSID: &str = env!("SSID");
+const PASSWORD: &str = env!("PASSWORD");
+const WATCHDOG_PORT: u16 = 9999;
+
+static WIFI: StaticCell<EspWifiController<'static>> = StaticCell::new();
+static NET_RESOURCES: StaticCell<StackResources<3>> = StaticCell::new();
+static GPIO_CHANNEL: StaticCell<EmbassyStateChannel> = StaticCell::new();
+static UDP_CHANNEL: StaticCell<EmbassyStateChannel> = StaticCell::new();
+static UDP_RX_META: StaticCell<[PacketMetadata; 16]> = StaticCell::new();
+static UDP_RX_BUFFER: StaticCell<[u8; 256]> = StaticCell::new();
+static UDP_TX_META: StaticCell<[PacketMetadata; 1]> = StaticCell::new();
+static UDP_TX_BUFFER: StaticCell<[u8; 16]> = StaticCell::new();
+
+fn watchdog_config() -> WatchdogConfig {
+    WatchdogConfig::new(Duration::from_millis(100).into())
+        .with_range(heartbeat_watchdog::Range::Timeout(
+            Duration::from_millis(100).into(),
+        ))
+        .with_warmup(Duration::from_secs(2).into())
+        .with_min_beats(20)
+}
+
+#[embassy_executor::task]
+async fn run_gpio_watchdog(watchdog: WatchdogAsync<WaitIo<Input<'static>>>) {
+    watchdog.run().await.unwrap();
+}
+
+#[embassy_executor::task]
+async fn run_udp_watchdog(watchdog: WatchdogAsync<UdpIo<'static>>) {
+    watchdog.run().await.unwrap();
+}
+
+#[embassy_executor::task]
+async fn wifi_connection(mut controller: WifiController<'static>) {
+    loop {
+        if esp_wifi::wifi::wifi_state() == WifiState::StaConnected {
+            controller.wait_for_event(WifiEvent::StaDisconnected).await;
+            warn!("Wi-Fi disconnected");
+            Timer::after_secs(1).await;
+        }
+        if !matches!(controller.is_started(), Ok(true)) {
+            let config = Configuration::Client(ClientConfiguration {
+                ssid: SSID.try_into().unwrap(),
+                password: PASSWORD.try_into().unwrap(),
+                ..Default::default()
+            });
+            controller.set_configuration(&config).unwrap();
+            controller.start_async().await.unwrap();
+        }
+        match controller.connect_async().await {
+            Ok(()) => info!("Wi-Fi connected"),
+            Err(e) => {
+                warn!("Wi-Fi connection failed: {:?}", e);
+                Timer::after_secs(5).await;
+            }
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn net_task(mut runner: Runner<'static, WifiDevice<'static, WifiStaDevice>>) {
+    runner.run().await;
+}
+
+#[esp_hal_embassy::main]
+async fn main(spawner: Spawner) {
+    esp_println::logger::init_logger_from_env();
+    let p = esp_hal::init(esp_hal::Config::default().with_cpu_clock(CpuClock::max()));
+    esp_alloc::heap_allocator!(72 * 1024);
+    esp_hal_embassy::init(SystemTimer::new(p.SYSTIMER).alarm0);
+
+    let mut fault_led = Output::new(p.GPIO8, Level::High);
+    info!("Watchdog started");
+
+    // GPIO watchdog, edges are detected with interrupts
+    let config = watchdog_config();
+    let gpio_io = WaitIo::new(Input::new(p.GPIO4, Pull::Down), config.io_timeout());
+    let gpio_channel = GPIO_CHANNEL.init(EmbassyStateChannel::new());
+    let mut gpio_watchdog = WatchdogAsync::new(config, gpio_io);
+    gpio_watchdog.set_state_tx(gpio_channel.sender());
+    spawner.spawn(run_gpio_watchdog(gpio_watchdog)).unwrap();
+
+    // Wi-Fi station and the network stack
+    let mut rng = Rng::new(p.RNG);
+    let wifi = WIFI.init(
+        esp_wifi::init(TimerGroup::new(p.TIMG0).timer0, rng, p.RADIO_CLK).unwrap(),
+    );
+    let (wifi_interface, controller) =
+        esp_wifi::wifi::new_with_mode(wifi, p.WIFI, WifiStaDevice).unwrap();
+    let seed = u64::from(rng.random()) << 32 | u64::from(rng.random());
+    let (stack, runner) = embassy_net::new(
+        wifi_interface,
+        embassy_net::Config::dhcpv4(Default::default()),
+        NET_RESOURCES.init(StackResources::new()),
+        seed,
+    );
+    spawner.spawn(wifi_connection(controller)).unwrap();
+    spawner.spawn(net_task(runner)).unwrap();
+    stack.wait_config_up().await;
+    if let Some(config) = stack.config_v4() {
+        info!("Listening on {}:{}", config.address.address(), WATCHDOG_PORT);
+    }
+
+    // UDP watchdog, the link loss is reported as a timeout
+    let mut socket = UdpSocket::new(
+        stack,
+        UDP_RX_META.init([PacketMetadata::EMPTY; 16]),
+        UDP_RX_BUFFER.init([0; 256]),
+        UDP_TX_META.init([PacketMetadata::EMPTY; 1]),
+        UDP_TX_BUFFER.init([0; 16]),
+    );
+    socket.bind(WATCHDOG_PORT).unwrap();
+    let config = watchdog_config();
+    let udp_io = UdpIo::new(socket, config.io_timeout());
+    let udp_channel = UDP_CHANNEL.init(EmbassyStateChannel::new());
+    let mut udp_watchdog = WatchdogAsync::new(config, udp_io);
+    udp_watchdog.set_state_tx(udp_channel.sender());
+    spawner.spawn(run_udp_watchdog(udp_watchdog)).unwrap();
+
+    let mut gpio_ok = false;
+    let mut udp_ok = false;
+    loop {
+        for (name, channel, ok) in [
+            ("GPIO", &*gpio_channel, &mut gpio_ok),
+            ("UDP", &*udp_channel, &mut udp_ok),
+        ] {
+            if let Ok(event) = channel.try_receive() {
+                match event {
+                    StateEvent::Fault(kind) => {
+                        warn!("{} watchdog state FAULT: {:?}", name, kind);
+                        *ok = false;
+                    }
+                    StateEvent::Ok => {
+                        info!("{} watchdog state OK", name);
+                        *ok = true;
+                    }
+                    StateEvent::Warning => {
+                        warn!("{} watchdog state WARNING", name);
+                        *ok = true;
+                    }
+                    StateEvent::Degraded { lost } => {
+                        warn!("{} watchdog degraded, {} beats lost", name, lost);
+                    }
+                    StateEvent::HighLatency { latency } => {
+                        warn!("{} watchdog high latency: {:?}", name, latency);
+                    }
+                    StateEvent::Paused => {
+                        info!("{} watchdog paused", name);
+                        *ok = true;
+                    }
+                    StateEvent::Terminated => {
+                        error!("{} watchdog terminated", name);
+                        *ok = false;
+                    }
+                }
+            }
+        }
+        fault_led.set_level(Level::from(!(gpio_ok && udp_ok)));
+        Timer::after_millis(1).await;
+    }
+}
@@ -14,16 +14,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &GpioConfig::new("/dev/gpiochip0", 27, Duration::from_millis(2)),
         watchdog_config.io_timeout(),
     )?;
-    let watchdog = Watchdog::new(watchdog_config, watchdog_io);
+    let watchdog = Watchdog::new(watchdog_config, watchdog_io).spawn()?;
     let state_rx = watchdog.state_rx();
     thread::spawn(move || {
         for e in state_rx {
             println!("{:?}", e);
         }
     });
-    thread::spawn(move || {
-        watchdog.run().unwrap();
-    });
     for (i, _) in interval(Duration::from_millis(100)).enumerate() {
         heart.beat().unwrap();
         if i > 0 && i % 10 == 0 {
@@ -2,7 +2,7 @@ use std::{thread, time::Duration};
 
 use heartbeat_watchdog::{
     io::udp::{UdpHeart, UdpIo},
-    Heart, Range, Watchdog, WatchdogConfig,
+    Heart, Range, WatchdogBuilder, WatchdogConfig,
 };
 use rtsc::time::interval;
 
@@ -11,16 +11,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let watchdog_config = WatchdogConfig::new(Duration::from_millis(100))
         .with_range(Range::Window(Duration::from_millis(10)));
     let watchdog_io = UdpIo::create("127.0.0.1:9999", watchdog_config.io_timeout())?;
-    let watchdog = Watchdog::new(watchdog_config, watchdog_io);
-    let state_rx = watchdog.state_rx();
-    thread::spawn(move || {
-        for e in state_rx {
-            println!("{:?}", e);
-        }
-    });
-    thread::spawn(move || {
-        watchdog.run().unwrap();
-    });
+    let _watchdog = WatchdogBuilder::new()
+        .config(watchdog_config)
+        .io(watchdog_io)
+        .name("udp-watchdog")
+        .on_state_change(|e| println!("{:?}", e))
+        .build()?
+        .spawn()?;
     for (i, _) in interval(Duration::from_millis(100)).enumerate() {
         heart.beat()?;
         if i > 0 && i % 100 == 0 {
@@ -0,0 +1,208 @@
+use std::process::{self, Stdio};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::{Error, FaultKind, Result, State, StateEvent, StateSource};
+
+/// External command, the arguments are templated
+///
+/// The following placeholders are replaced in the arguments: `{name}` - the watchdog name
+/// (empty if not set), `{state}` - the watchdog state (`Ok`/`Fault`), `{fault}` - the fault
+/// kind (e.g. `Timeout`, empty on recovery). The same values are set for the command in the
+/// `WATCHDOG_NAME`, `WATCHDOG_STATE` and `WATCHDOG_FAULT` environment variables.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Command {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Command {
+    /// Create a new command without arguments
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+    /// Parse a command line, the program and the arguments are separated with whitespaces (no
+    /// quoting, use e.g. `sh -c` for shell features), e.g. `systemctl restart {name}`
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parts = s.split_whitespace();
+        let program = parts.next().ok_or_else(|| Error::failed("empty command"))?;
+        Ok(Self {
+            program: program.to_owned(),
+            args: parts.map(ToOwned::to_owned).collect(),
+        })
+    }
+    /// Add an argument
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+    /// Get the program
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+    /// Get the arguments (templates)
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+    /// Spawn the command with the templated arguments, the command is not waited for (the exit
+    /// status is collected in a background thread)
+    fn spawn(&self, vars: &[(&str, &str)]) -> Result<()> {
+        let mut cmd = process::Command::new(&self.program);
+        for arg in &self.args {
+            let mut arg = arg.clone();
+            for (name, value) in vars {
+                arg = arg.replace(&format!("{{{}}}", name), value);
+            }
+            cmd.arg(arg);
+        }
+        for (name, value) in vars {
+            cmd.env(format!("WATCHDOG_{}", name.to_uppercase()), value);
+        }
+        let mut child = cmd.stdin(Stdio::null()).spawn()?;
+        std::thread::Builder::new()
+            .name("watchdog-action".to_owned())
+            .spawn(move || child.wait())?;
+        Ok(())
+    }
+}
+
+/// Command execution on faults and recoveries
+///
+/// The fault command is spawned when the watchdog switches to Fault state (except the initial
+/// fault, when the watchdog is started), the recovery command is spawned when the watchdog
+/// switches from Fault to OK state:
+///
+/// ```rust,ignore
+/// CommandAction::new()
+///     .on_fault(Command::parse("systemctl restart {name}")?)
+///     .attach(&watchdog);
+/// ```
+///
+/// The commands are spawned from the watchdog run loop and are not waited for, spawn errors are
+/// ignored.
+#[derive(Default)]
+pub struct CommandAction {
+    on_fault: Option<Command>,
+    on_recovery: Option<Command>,
+}
+
+impl CommandAction {
+    /// Create a new command action without commands
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the command which is spawned on faults
+    pub fn on_fault(mut self, cmd: Command) -> Self {
+        self.on_fault = Some(cmd);
+        self
+    }
+    /// Set the command which is spawned on recoveries
+    pub fn on_recovery(mut self, cmd: Command) -> Self {
+        self.on_recovery = Some(cmd);
+        self
+    }
+    /// Attach the action to the watchdog (or any other state source)
+    pub fn attach<S: StateSource>(self, source: &S) {
+        let name = source.name().unwrap_or_default().to_owned();
+        let faulted = AtomicBool::new(false);
+        source.on_state_change(Box::new(move |event| {
+            let (cmd, fault) = match event {
+                StateEvent::Fault(FaultKind::Initial) => return,
+                StateEvent::Fault(kind) => {
+                    faulted.store(true, Ordering::Relaxed);
+                    (&self.on_fault, format!("{:?}", kind))
+                }
+                StateEvent::Ok if faulted.swap(false, Ordering::Relaxed) => {
+                    (&self.on_recovery, String::new())
+                }
+                _ => return,
+            };
+            let state = format!("{:?}", State::from(event.clone()));
+            if let Some(cmd) = cmd {
+                let _ = cmd.spawn(&[("name", &name), ("state", &state), ("fault", &fault)]);
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateCallback;
+    use core::time::Duration;
+    use rtsc::pi::Mutex;
+    use std::{path::Path, time::Instant};
+
+    /// A state source which emits the events on demand
+    #[derive(Default)]
+    struct EventSource(Mutex<Vec<StateCallback>>);
+
+    impl EventSource {
+        fn emit(&self, event: StateEvent) {
+            for f in self.0.lock().iter() {
+                f(&event);
+            }
+        }
+    }
+
+    impl StateSource for EventSource {
+        fn state(&self) -> State {
+            State::Fault
+        }
+        fn on_state_change(&self, f: StateCallback) {
+            self.0.lock().push(f);
+        }
+    }
+
+    /// Waits until the spawned commands have written the number of lines to the file
+    fn wait_lines(path: &Path, n: usize) -> Vec<String> {
+        let start = Instant::now();
+        loop {
+            let lines: Vec<String> = std::fs::read_to_string(path)
+                .unwrap_or_default()
+                .lines()
+                .map(ToOwned::to_owned)
+                .collect();
+            if lines.len() >= n || start.elapsed() > Duration::from_secs(5) {
+                return lines;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_command_action() {
+        let path = std::env::temp_dir().join(format!("watchdog-action-{}", process::id()));
+        let _ = std::fs::remove_file(&path);
+        let cmd = |what: &str| {
+            Command::new("sh").arg("-c").arg(format!(
+                "echo {} {{state}} {{fault}} >> {}",
+                what,
+                path.display()
+            ))
+        };
+        let source = EventSource::default();
+        CommandAction::new()
+            .on_fault(cmd("fault"))
+            .on_recovery(cmd("recovery"))
+            .attach(&source);
+        source.emit(StateEvent::Fault(FaultKind::Initial));
+        source.emit(StateEvent::Ok);
+        source.emit(StateEvent::Degraded { lost: 1 });
+        source.emit(StateEvent::HighLatency {
+            latency: Duration::from_secs(1),
+        });
+        source.emit(StateEvent::Fault(FaultKind::Timeout));
+        assert_eq!(wait_lines(&path, 1), ["fault Fault Timeout"]);
+        source.emit(StateEvent::Degraded { lost: 1 });
+        source.emit(StateEvent::Ok);
+        assert_eq!(wait_lines(&path, 2), ["fault Fault Timeout", "recovery Ok"]);
+        // no more commands are spawned
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(wait_lines(&path, 0).len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
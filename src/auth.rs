@@ -0,0 +1,38 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Authentication tag size (truncated HMAC-SHA256)
+pub const TAG_SIZE: usize = 16;
+
+/// Shared-key beat authenticator (HMAC-SHA256)
+///
+/// The heart and the watchdog must use the same key. Beats with invalid tags (forged or
+/// corrupted) are ignored by the watchdog.
+#[derive(Clone)]
+pub struct Authenticator {
+    mac: Hmac<Sha256>,
+}
+
+impl Authenticator {
+    /// Create a new authenticator with the shared key (recommended size is 32 bytes)
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            // HMAC accepts keys of any size
+            mac: Hmac::new_from_slice(key).unwrap_or_else(|_| unreachable!()),
+        }
+    }
+    /// Get the authentication tag of the data
+    pub fn sign(&self, data: &[u8]) -> [u8; TAG_SIZE] {
+        let mut mac = self.mac.clone();
+        mac.update(data);
+        let mut tag = [0; TAG_SIZE];
+        tag.copy_from_slice(&mac.finalize().into_bytes()[..TAG_SIZE]);
+        tag
+    }
+    /// Verify the authentication tag of the data (in constant time)
+    pub fn verify(&self, data: &[u8], tag: &[u8]) -> bool {
+        let mut mac = self.mac.clone();
+        mac.update(data);
+        tag.len() == TAG_SIZE && mac.verify_truncated_left(tag).is_ok()
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{
+    io::{WatchdogIo, WatchdogIoAsync},
+    Clock, Error, Result, SystemClock, Watchdog, WatchdogAsync, WatchdogConfig,
+};
+#[cfg(feature = "std")]
+use crate::{ErrorCallback, EscalationPolicy, Escalator, StateCallback, StateEvent};
+#[cfg(all(feature = "embassy", not(feature = "std")))]
+use crate::{NoopMutex, StateEvent};
+
+/// Watchdog builder
+///
+/// Assembles the configuration, the I/O and the state notifiers in a single chain, the
+/// configuration is validated when the watchdog is built:
+///
+/// ```rust,ignore
+/// let watchdog = WatchdogBuilder::new()
+///     .config(WatchdogConfig::new(Duration::from_millis(100)))
+///     .io(io)
+///     .name("plc")
+///     .on_state_change(|event| println!("{:?}", event))
+///     .build()?;
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct WatchdogBuilder<I = (), C = SystemClock> {
+    config: Option<WatchdogConfig>,
+    io: I,
+    clock: C,
+    #[cfg(feature = "std")]
+    name: Option<String>,
+    #[cfg(feature = "std")]
+    callbacks: Vec<StateCallback>,
+    #[cfg(feature = "std")]
+    error_callbacks: Vec<ErrorCallback>,
+    #[cfg(feature = "std")]
+    escalation: Option<EscalationPolicy>,
+    #[cfg(all(feature = "embassy", not(feature = "std")))]
+    state_tx: Option<embassy_sync::channel::Sender<'static, NoopMutex, StateEvent, 32>>,
+}
+
+impl Default for WatchdogBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WatchdogBuilder {
+    /// Create a new watchdog builder
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            io: (),
+            clock: SystemClock,
+            #[cfg(feature = "std")]
+            name: None,
+            #[cfg(feature = "std")]
+            callbacks: Vec::new(),
+            #[cfg(feature = "std")]
+            error_callbacks: Vec::new(),
+            #[cfg(feature = "std")]
+            escalation: None,
+            #[cfg(all(feature = "embassy", not(feature = "std")))]
+            state_tx: None,
+        }
+    }
+}
+
+impl<I, C> WatchdogBuilder<I, C> {
+    /// Set the configuration (required)
+    pub fn config(mut self, config: WatchdogConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+    /// Set the watchdog I/O (required)
+    pub fn io<J>(self, io: J) -> WatchdogBuilder<J, C> {
+        WatchdogBuilder {
+            config: self.config,
+            io,
+            clock: self.clock,
+            #[cfg(feature = "std")]
+            name: self.name,
+            #[cfg(feature = "std")]
+            callbacks: self.callbacks,
+            #[cfg(feature = "std")]
+            error_callbacks: self.error_callbacks,
+            #[cfg(feature = "std")]
+            escalation: self.escalation,
+            #[cfg(all(feature = "embassy", not(feature = "std")))]
+            state_tx: self.state_tx,
+        }
+    }
+    /// Set a custom clock (the default is [`SystemClock`])
+    pub fn clock<D: Clock>(self, clock: D) -> WatchdogBuilder<I, D> {
+        WatchdogBuilder {
+            config: self.config,
+            io: self.io,
+            clock,
+            #[cfg(feature = "std")]
+            name: self.name,
+            #[cfg(feature = "std")]
+            callbacks: self.callbacks,
+            #[cfg(feature = "std")]
+            error_callbacks: self.error_callbacks,
+            #[cfg(feature = "std")]
+            escalation: self.escalation,
+            #[cfg(all(feature = "embassy", not(feature = "std")))]
+            state_tx: self.state_tx,
+        }
+    }
+    /// Set the watchdog name (used as the thread name by [`Watchdog::spawn`])
+    #[cfg(feature = "std")]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+    /// Register a state change callback, see [`Watchdog::on_state_change`]
+    #[cfg(feature = "std")]
+    pub fn on_state_change(mut self, f: impl Fn(&StateEvent) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Box::new(f));
+        self
+    }
+    /// Register a terminal error callback, see [`Watchdog::on_error`]
+    #[cfg(feature = "std")]
+    pub fn on_error(mut self, f: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.error_callbacks.push(Box::new(f));
+        self
+    }
+    /// Set the fault escalation policy, see [`Watchdog::set_escalation`]
+    #[cfg(feature = "std")]
+    pub fn escalation(mut self, policy: EscalationPolicy) -> Self {
+        self.escalation = Some(policy);
+        self
+    }
+    /// Set the state sender channel, see [`WatchdogAsync::set_state_tx`]
+    #[cfg(all(feature = "embassy", not(feature = "std")))]
+    pub fn state_tx(
+        mut self,
+        tx: embassy_sync::channel::Sender<'static, NoopMutex, StateEvent, 32>,
+    ) -> Self {
+        self.state_tx = Some(tx);
+        self
+    }
+    fn validated_config(&mut self) -> Result<WatchdogConfig> {
+        let config = self
+            .config
+            .take()
+            .ok_or(Error::Config("configuration is not set"))?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl<I: WatchdogIo, C: Clock> WatchdogBuilder<I, C> {
+    /// Build the watchdog
+    pub fn build(mut self) -> Result<Watchdog<I, C>> {
+        let config = self.validated_config()?;
+        #[allow(unused_mut)]
+        let mut inner = Watchdog::new_inner(config, self.io, self.clock);
+        #[cfg(feature = "std")]
+        {
+            inner.name = self.name;
+            inner.callbacks.lock().extend(self.callbacks);
+            inner.error_callbacks.lock().extend(self.error_callbacks);
+            *inner.escalation.lock() = self.escalation.map(Escalator::new);
+        }
+        #[allow(clippy::useless_conversion)]
+        Ok(Watchdog {
+            inner: inner.into(),
+        })
+    }
+}
+
+impl<I: WatchdogIoAsync, C: Clock> WatchdogBuilder<I, C> {
+    /// Build the async watchdog
+    pub fn build_async(mut self) -> Result<WatchdogAsync<I, C>> {
+        let config = self.validated_config()?;
+        #[allow(unused_mut)]
+        let mut inner = WatchdogAsync::new_inner(config, self.io, self.clock);
+        #[cfg(feature = "std")]
+        {
+            inner.name = self.name;
+            inner.callbacks.lock().extend(self.callbacks);
+            inner.error_callbacks.lock().extend(self.error_callbacks);
+            *inner.escalation.lock() = self.escalation.map(Escalator::new);
+        }
+        #[cfg(all(feature = "embassy", not(feature = "std")))]
+        {
+            inner.embassy_state_tx = self.state_tx;
+        }
+        #[allow(clippy::useless_conversion)]
+        Ok(WatchdogAsync {
+            inner: inner.into(),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{io::mock::MockIo, FaultKind, MockClock, RetryPolicy};
+    use core::time::Duration;
+    use rtsc::pi::Mutex;
+    use std::sync::Arc;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_no_config() {
+        assert!(matches!(
+            WatchdogBuilder::new().io(MockIo::new()).build(),
+            Err(Error::Config("configuration is not set"))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(matches!(
+            WatchdogBuilder::new()
+                .config(WatchdogConfig::new(INTERVAL).with_fault_tolerance(0))
+                .io(MockIo::new())
+                .build(),
+            Err(Error::Config("fault_tolerance must be greater than zero"))
+        ));
+    }
+
+    #[test]
+    fn test_build() {
+        let config = WatchdogConfig::new(INTERVAL)
+            .with_io_retry(RetryPolicy::new(Duration::from_millis(1)).with_max_retries(1));
+        let clock = Arc::new(MockClock::new());
+        let io = MockIo::new()
+            .with_clock(clock.clone())
+            .with_timeout(config.io_timeout());
+        io.beats(10, INTERVAL).error("end").error("end");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let (e, r) = (events.clone(), errors.clone());
+        let watchdog = WatchdogBuilder::new()
+            .config(config)
+            .io(io)
+            .clock(clock)
+            .name("plc")
+            .on_state_change(move |event| e.lock().push(event.clone()))
+            .on_error(move |error| r.lock().push(error.to_string()))
+            .build()
+            .unwrap();
+        assert_eq!(watchdog.name(), Some("plc"));
+        assert!(watchdog.run().is_err());
+        assert_eq!(
+            *events.lock(),
+            [
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ]
+        );
+        assert_eq!(errors.lock().len(), 1);
+    }
+}
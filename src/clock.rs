@@ -0,0 +1,112 @@
+use core::time::Duration;
+
+use portable_atomic::{AtomicU64, Ordering};
+
+/// Monotonic clock used to evaluate heartbeats
+///
+/// Can be implemented for platforms with custom timebases (FPGA counters, PTP clocks etc.). Note
+/// that the I/O timeouts are still provided by the I/O.
+pub trait Clock {
+    /// Get the current time (since an arbitrary epoch)
+    fn now(&self) -> Duration;
+}
+
+/// System monotonic clock (`std::time::Instant` or `embassy_time::Instant`)
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        #[cfg(feature = "std")]
+        {
+            static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+            EPOCH.get_or_init(std::time::Instant::now).elapsed()
+        }
+        #[cfg(not(feature = "std"))]
+        Duration::from_micros(embassy_time::Instant::now().as_micros())
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock + ?Sized> Clock for std::sync::Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// Virtual clock for deterministic tests, the time is advanced manually
+///
+/// The clock is shared with the watchdog by reference (or with `Arc` for `std`):
+///
+/// ```rust,ignore
+/// let clock = Arc::new(MockClock::new());
+/// let watchdog = Watchdog::with_clock(config, io, clock.clone());
+/// clock.advance(Duration::from_millis(100));
+/// ```
+#[derive(Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct MockClock {
+    nanos: AtomicU64,
+}
+
+impl MockClock {
+    /// Create a new virtual clock, the time starts at zero
+    pub const fn new() -> Self {
+        Self {
+            nanos: AtomicU64::new(0),
+        }
+    }
+    /// Advance the time
+    pub fn advance(&self, d: Duration) {
+        self.nanos.fetch_add(nanos(d), Ordering::SeqCst);
+    }
+    /// Set the time
+    pub fn set(&self, t: Duration) {
+        self.nanos.store(nanos(t), Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+/// Converts the clock time to an instant, counting back the time elapsed on the clock
+#[cfg(feature = "std")]
+pub(crate) fn to_instant<C: Clock + ?Sized>(clock: &C, time: Duration) -> std::time::Instant {
+    let now = std::time::Instant::now();
+    now.checked_sub(clock.now().saturating_sub(time))
+        .unwrap_or(now)
+}
+
+fn nanos(d: Duration) -> u64 {
+    u64::try_from(d.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_to_instant() {
+        let clock = MockClock::new();
+        clock.set(Duration::from_secs(10));
+        let before = Instant::now();
+        let instant = to_instant(&clock, Duration::from_secs(4));
+        let after = Instant::now();
+        assert!(instant + Duration::from_secs(6) >= before);
+        assert!(instant + Duration::from_secs(6) <= after);
+        // the clock time can not be in the future
+        assert!(to_instant(&clock, Duration::from_secs(20)) >= after);
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::{Arc, Weak};
+
+use portable_atomic::{AtomicU8, Ordering};
+use rtsc::policy_channel;
+
+use crate::{
+    io::{WatchdogIo, WatchdogIoAsync},
+    Ack, Clock, Condvar, Error, FaultKind, Heart, HeartAsync, RawMutex, Result, State,
+    StateCallback, StateEvent, Watchdog, WatchdogAsync, SUBSCRIBER_QUEUE_SIZE,
+};
+
+/// Watchdog state source, which can be combined with others (see [`CompositeWatchdog`])
+pub trait StateSource: Send + Sync {
+    /// Get the current state
+    fn state(&self) -> State;
+    /// Register a state change callback
+    fn on_state_change(&self, f: StateCallback);
+    /// Get the source name
+    fn name(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<I: WatchdogIo + Send + Sync, C: Clock + Send + Sync> StateSource for Watchdog<I, C> {
+    fn state(&self) -> State {
+        Watchdog::state(self)
+    }
+    fn on_state_change(&self, f: StateCallback) {
+        Watchdog::on_state_change(self, f);
+    }
+    fn name(&self) -> Option<&str> {
+        Watchdog::name(self)
+    }
+}
+
+impl<I: WatchdogIoAsync + Send + Sync, C: Clock + Send + Sync> StateSource for WatchdogAsync<I, C> {
+    fn state(&self) -> State {
+        WatchdogAsync::state(self)
+    }
+    fn on_state_change(&self, f: StateCallback) {
+        WatchdogAsync::on_state_change(self, f);
+    }
+    fn name(&self) -> Option<&str> {
+        WatchdogAsync::name(self)
+    }
+}
+
+/// Composite watchdog logic
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Logic {
+    /// OK while all sources are OK (the worst state of the sources)
+    All,
+    /// OK while any source is OK (the best state of the sources)
+    Any,
+    /// OK while at least N sources are OK (N-of-M), e.g. redundant controller pairs/triples where
+    /// losing a single node is acceptable. Warning if N sources are alive but some of them are in
+    /// Warning state. N must be between 1 and the number of sources
+    Quorum(usize),
+}
+
+impl Logic {
+    fn evaluate(self, states: &[State]) -> State {
+        if states.is_empty() {
+            return State::Fault;
+        }
+        let ok = states.iter().filter(|s| **s == State::Ok).count();
+        let alive = states.iter().filter(|s| **s != State::Fault).count();
+        let (ok, alive) = match self {
+            Logic::All => (ok == states.len(), alive == states.len()),
+            Logic::Any => (ok > 0, alive > 0),
+            Logic::Quorum(n) => (ok >= n, alive >= n),
+        };
+        if ok {
+            State::Ok
+        } else if alive {
+            State::Warning
+        } else {
+            State::Fault
+        }
+    }
+}
+
+struct CompositeInner {
+    logic: Logic,
+    sources: rtsc::pi::Mutex<Vec<Arc<dyn StateSource>>>,
+    state: AtomicU8,
+    // transitions are serialized, so the events are emitted in order
+    transition: rtsc::pi::Mutex<()>,
+    subscribers: rtsc::pi::Mutex<Vec<policy_channel::Sender<StateEvent, RawMutex, Condvar>>>,
+    callbacks: rtsc::pi::Mutex<Vec<StateCallback>>,
+}
+
+impl CompositeInner {
+    fn evaluate(&self) -> State {
+        let states: Vec<State> = self.sources.lock().iter().map(|s| s.state()).collect();
+        self.logic.evaluate(&states)
+    }
+    /// Re-evaluates the state after a source event
+    fn update(&self, trigger: &StateEvent) {
+        let _transition = self.transition.lock();
+        let new_state = self.evaluate();
+        if self.state.swap(new_state as u8, Ordering::Relaxed) == new_state as u8 {
+            return;
+        }
+        let event = match new_state {
+            State::Ok => StateEvent::Ok,
+            State::Warning => StateEvent::Warning,
+            State::Fault => StateEvent::Fault(match trigger {
+                StateEvent::Fault(kind) => *kind,
+                // the source run has failed
+                _ => FaultKind::Io,
+            }),
+        };
+        for f in self.callbacks.lock().iter() {
+            f(&event);
+        }
+        // the events never block as a full queue drops the oldest one, the subscribers which have
+        // dropped their receivers are removed
+        self.subscribers
+            .lock()
+            .retain(|tx| !matches!(tx.try_send(event.clone()), Err(rtsc::Error::ChannelClosed)));
+    }
+}
+
+/// Composite watchdog
+///
+/// Derives its state from several sources (watchdogs or other composite watchdogs) with the
+/// given logic and emits its own state events, e.g. a machine which needs both its PLC and its
+/// HMI alive can be supervised as a single logical watchdog:
+///
+/// ```rust,ignore
+/// let machine = CompositeWatchdog::builder(Logic::All)
+///     .source(plc.clone())
+///     .source(hmi.clone())
+///     .build()?;
+/// machine.on_state_change(|event| println!("machine: {:?}", event));
+/// ```
+///
+/// The state is re-evaluated on the state events of the sources (from their run loops). A
+/// fault event carries the fault kind of the source which has caused it.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct CompositeWatchdog {
+    inner: Arc<CompositeInner>,
+}
+
+impl CompositeWatchdog {
+    /// Create a new composite watchdog builder
+    pub fn builder(logic: Logic) -> CompositeBuilder {
+        CompositeBuilder {
+            logic,
+            sources: Vec::new(),
+        }
+    }
+    /// Get the combination logic
+    pub fn logic(&self) -> Logic {
+        self.inner.logic
+    }
+    /// Get the current state
+    pub fn state(&self) -> State {
+        State::from(self.inner.state.load(Ordering::Relaxed))
+    }
+    /// Subscribe to state events, every subscriber receives all transitions
+    pub fn subscribe(&self) -> policy_channel::Receiver<StateEvent, RawMutex, Condvar> {
+        let (tx, rx) = policy_channel::bounded(SUBSCRIBER_QUEUE_SIZE);
+        self.inner.subscribers.lock().push(tx);
+        rx
+    }
+    /// Register a state change callback. Callbacks are called from the run loops of the sources,
+    /// so they must not block and must not register other callbacks
+    pub fn on_state_change(&self, f: impl Fn(&StateEvent) + Send + Sync + 'static) {
+        self.inner.callbacks.lock().push(Box::new(f));
+    }
+}
+
+/// Composite watchdog builder, see [`CompositeWatchdog::builder`]
+#[allow(clippy::module_name_repetitions)]
+pub struct CompositeBuilder {
+    logic: Logic,
+    sources: Vec<Arc<dyn StateSource>>,
+}
+
+impl CompositeBuilder {
+    /// Add a state source
+    pub fn source<S: StateSource + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+    /// Build the composite watchdog, the quorum of [`Logic::Quorum`] is validated against the
+    /// number of sources. A composite watchdog without sources is in Fault state
+    pub fn build(self) -> Result<CompositeWatchdog> {
+        if let Logic::Quorum(n) = self.logic {
+            if n == 0 {
+                return Err(Error::Config("quorum must be greater than zero"));
+            }
+            if n > self.sources.len() {
+                return Err(Error::Config(
+                    "quorum must not exceed the number of sources",
+                ));
+            }
+        }
+        let sources = self.sources.clone();
+        let inner = Arc::new(CompositeInner {
+            logic: self.logic,
+            sources: rtsc::pi::Mutex::new(self.sources),
+            state: AtomicU8::new(State::Fault as u8),
+            transition: <_>::default(),
+            subscribers: <_>::default(),
+            callbacks: <_>::default(),
+        });
+        for source in sources {
+            // the sources hold weak references only, so the composite can be dropped
+            let weak: Weak<CompositeInner> = Arc::downgrade(&inner);
+            source.on_state_change(Box::new(move |event| {
+                if let Some(inner) = weak.upgrade() {
+                    inner.update(event);
+                }
+            }));
+        }
+        inner.state.store(inner.evaluate() as u8, Ordering::Relaxed);
+        Ok(CompositeWatchdog { inner })
+    }
+}
+
+impl StateSource for CompositeWatchdog {
+    fn state(&self) -> State {
+        CompositeWatchdog::state(self)
+    }
+    fn on_state_change(&self, f: StateCallback) {
+        CompositeWatchdog::on_state_change(self, f);
+    }
+}
+
+/// Heart adaptor which forwards beats only while the upstream watchdog is not in Fault state
+///
+/// Chains liveness through multiple hops (e.g. sensor → gateway → SCADA): a gateway beats to
+/// SCADA while it receives the sensor beats, so the sensor faults propagate downstream
+/// automatically. Suppressed beats are not reported as errors.
+///
+/// ```rust,ignore
+/// let heart = GatedHeart::new(UdpHeart::create("scada:9999")?, sensor_watchdog.clone());
+/// heart.beat()?;
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct GatedHeart<H, S: StateSource> {
+    heart: H,
+    upstream: S,
+}
+
+impl<H, S: StateSource> GatedHeart<H, S> {
+    /// Create a new gated heart
+    pub fn new(heart: H, upstream: S) -> Self {
+        Self { heart, upstream }
+    }
+    /// Are the beats forwarded (the upstream is in OK or Warning state)
+    pub fn is_open(&self) -> bool {
+        self.upstream.state() != State::Fault
+    }
+    /// Get the inner heart
+    pub fn heart(&self) -> &H {
+        &self.heart
+    }
+    /// Get the upstream state source
+    pub fn upstream(&self) -> &S {
+        &self.upstream
+    }
+}
+
+impl<H: Heart, S: StateSource> Heart for GatedHeart<H, S> {
+    fn beat(&self) -> Result<()> {
+        if self.is_open() {
+            self.heart.beat()?;
+        }
+        Ok(())
+    }
+    fn ack(&self) -> Result<Option<Ack>> {
+        self.heart.ack()
+    }
+}
+
+impl<H: HeartAsync, S: StateSource> HeartAsync for GatedHeart<H, S> {
+    async fn beat_async(&self) -> Result<()> {
+        if self.is_open() {
+            self.heart.beat_async().await?;
+        }
+        Ok(())
+    }
+    async fn ack_async(&self) -> Result<Option<Ack>> {
+        self.heart.ack_async().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedSource(State);
+
+    impl StateSource for FixedSource {
+        fn state(&self) -> State {
+            self.0
+        }
+        fn on_state_change(&self, _f: StateCallback) {}
+    }
+
+    fn quorum(n: usize, states: &[State]) -> Result<CompositeWatchdog> {
+        states
+            .iter()
+            .fold(CompositeWatchdog::builder(Logic::Quorum(n)), |b, s| {
+                b.source(FixedSource(*s))
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_quorum() {
+        let states = [State::Ok, State::Warning, State::Fault];
+        assert_eq!(quorum(1, &states).unwrap().state(), State::Ok);
+        assert_eq!(quorum(2, &states).unwrap().state(), State::Warning);
+        assert_eq!(quorum(3, &states).unwrap().state(), State::Fault);
+    }
+
+    #[test]
+    fn test_quorum_zero() {
+        assert!(matches!(
+            quorum(0, &[State::Fault, State::Fault]),
+            Err(Error::Config("quorum must be greater than zero"))
+        ));
+    }
+
+    #[test]
+    fn test_quorum_above_sources() {
+        assert!(matches!(
+            quorum(3, &[State::Ok, State::Ok]),
+            Err(Error::Config(
+                "quorum must not exceed the number of sources"
+            ))
+        ));
+        assert!(matches!(
+            quorum(1, &[]),
+            Err(Error::Config(
+                "quorum must not exceed the number of sources"
+            ))
+        ));
+    }
+}
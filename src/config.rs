@@ -0,0 +1,196 @@
+use crate::{parse_duration, Error, Range, Result, WatchdogConfig};
+
+fn env_var(prefix: &str, name: &str) -> Option<String> {
+    std::env::var(format!("{}_{}", prefix, name.to_uppercase())).ok()
+}
+
+fn env_number<T: core::str::FromStr>(prefix: &str, name: &str) -> Result<Option<T>> {
+    env_var(prefix, name)
+        .map(|s| {
+            s.trim()
+                .parse()
+                .map_err(|_| Error::failed(format!("invalid {}: {}", name, s)))
+        })
+        .transpose()
+}
+
+/// Configuration file/environment values, durations are strings with units
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    interval: Option<String>,
+    timeout: Option<String>,
+    window: Option<String>,
+    warmup: Option<String>,
+    min_beats: Option<u32>,
+    warning: Option<String>,
+    fault_tolerance: Option<u32>,
+    history: Option<usize>,
+}
+
+impl RawConfig {
+    fn into_config(self) -> Result<WatchdogConfig> {
+        let interval = self.interval.ok_or(Error::Config("interval is required"))?;
+        let mut config = WatchdogConfig::new(parse_duration(&interval)?);
+        match (self.timeout, self.window) {
+            (Some(_), Some(_)) => {
+                return Err(Error::Config("timeout and window are mutually exclusive"));
+            }
+            (Some(timeout), None) => {
+                config = config.with_range(Range::Timeout(parse_duration(&timeout)?));
+            }
+            (None, Some(window)) => {
+                config = config.with_range(Range::Window(parse_duration(&window)?));
+            }
+            (None, None) => {}
+        }
+        if let Some(warmup) = self.warmup {
+            config = config.with_warmup(parse_duration(&warmup)?);
+        }
+        if let Some(min_beats) = self.min_beats {
+            config = config.with_min_beats(min_beats);
+        }
+        if let Some(warning) = self.warning {
+            config = config.with_warning(parse_duration(&warning)?);
+        }
+        if let Some(n) = self.fault_tolerance {
+            config = config.with_fault_tolerance(n);
+        }
+        if let Some(size) = self.history {
+            config = config.with_history(size);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl WatchdogConfig {
+    /// Load the configuration from a TOML string, durations are set as strings with units, e.g.
+    ///
+    /// ```toml
+    /// interval = "100ms"
+    /// timeout = "50ms" # or window = "10ms"
+    /// warmup = "1s"
+    /// min_beats = 3
+    /// warning = "20ms"
+    /// fault_tolerance = 2
+    /// history = 16
+    /// ```
+    ///
+    /// All fields except the interval are optional. The configuration is validated.
+    pub fn from_toml(s: &str) -> Result<Self> {
+        toml::from_str::<RawConfig>(s)
+            .map_err(Error::failed)?
+            .into_config()
+    }
+    /// Load the configuration from environment variables, the variable names are the TOML keys
+    /// in upper case with the prefix, e.g. `WATCHDOG_INTERVAL=100ms` for the prefix `WATCHDOG`
+    pub fn from_env(prefix: &str) -> Result<Self> {
+        let var = |name| env_var(prefix, name);
+        RawConfig {
+            interval: var("interval"),
+            timeout: var("timeout"),
+            window: var("window"),
+            warmup: var("warmup"),
+            min_beats: env_number(prefix, "min_beats")?,
+            warning: var("warning"),
+            fault_tolerance: env_number(prefix, "fault_tolerance")?,
+            history: env_number(prefix, "history")?,
+        }
+        .into_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+
+    fn config_err(s: &str) -> String {
+        WatchdogConfig::from_toml(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_toml() {
+        let config = WatchdogConfig::from_toml(
+            r#"
+            interval = "100ms"
+            timeout = "50ms"
+            warmup = "1s"
+            min_beats = 3
+            warning = "20ms"
+            fault_tolerance = 2
+            history = 16
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.interval(), Duration::from_millis(100));
+        assert!(matches!(config.range(), Range::Timeout(t) if *t == Duration::from_millis(50)));
+        assert_eq!(config.warmup(), Duration::from_secs(1));
+        assert_eq!(config.min_beats(), 3);
+        assert_eq!(config.warning(), Some(Duration::from_millis(20)));
+        assert_eq!(config.fault_tolerance(), 2);
+        let config = WatchdogConfig::from_toml("interval = \"1s\"\nwindow = \"10ms\"").unwrap();
+        assert!(matches!(config.range(), Range::Window(w) if *w == Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_toml_invalid() {
+        assert_eq!(
+            config_err("timeout = \"50ms\""),
+            "Invalid configuration: interval is required"
+        );
+        assert_eq!(
+            config_err("interval = \"100ms\"\ntimeout = \"50ms\"\nwindow = \"10ms\""),
+            "Invalid configuration: timeout and window are mutually exclusive"
+        );
+        assert_eq!(
+            config_err("interval = \"100ms\"\nwindow = \"100ms\""),
+            "Invalid configuration: window must be less than the interval"
+        );
+        assert_eq!(
+            config_err("interval = \"100\""),
+            "Invalid configuration: duration unit missing"
+        );
+        assert_eq!(
+            config_err("interval = \"18446744073709551615s\""),
+            "Invalid configuration: duration exceeds MAX_DURATION"
+        );
+        assert!(config_err("interval = \"100ms\"\ntimout = \"50ms\"").contains("unknown field"));
+        assert!(config_err("interval = 100").starts_with("Failed: "));
+    }
+
+    #[test]
+    fn test_env() {
+        // the prefix is unique, the tests run in parallel
+        for (name, value) in [
+            ("TEST_CONFIG_ENV_INTERVAL", "1s"),
+            ("TEST_CONFIG_ENV_WINDOW", "100ms"),
+            ("TEST_CONFIG_ENV_MIN_BEATS", " 5 "),
+        ] {
+            std::env::set_var(name, value);
+        }
+        let config = WatchdogConfig::from_env("TEST_CONFIG_ENV").unwrap();
+        assert_eq!(config.interval(), Duration::from_secs(1));
+        assert!(matches!(config.range(), Range::Window(w) if *w == Duration::from_millis(100)));
+        assert_eq!(config.min_beats(), 5);
+        assert_eq!(
+            config.fault_tolerance(),
+            WatchdogConfig::new(Duration::from_secs(1)).fault_tolerance()
+        );
+        std::env::set_var("TEST_CONFIG_ENV_FAULT_TOLERANCE", "x");
+        assert_eq!(
+            WatchdogConfig::from_env("TEST_CONFIG_ENV")
+                .unwrap_err()
+                .to_string(),
+            "Failed: invalid fault_tolerance: x"
+        );
+        assert_eq!(
+            WatchdogConfig::from_env("TEST_CONFIG_ENV_MISSING")
+                .unwrap_err()
+                .to_string(),
+            "Invalid configuration: interval is required"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use core::time::Duration;
+
+use crate::{FaultKind, State};
+
+/// Escalation level action
+#[allow(clippy::module_name_repetitions)]
+pub type EscalationAction = Box<dyn Fn(&Escalation) + Send + Sync>;
+
+/// Fault escalation, passed to the level actions
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Escalation {
+    /// The escalation level (starting from 1)
+    pub level: usize,
+    /// The kind of the fault
+    pub kind: FaultKind,
+    /// The time elapsed since the fault
+    pub elapsed: Duration,
+}
+
+struct Level {
+    after: Duration,
+    action: EscalationAction,
+}
+
+/// Fault escalation policy
+///
+/// Each level is triggered once per fault, when the fault persists longer than the level
+/// duration, e.g.:
+///
+/// ```rust,ignore
+/// let policy = EscalationPolicy::new()
+///     .with_level(Duration::ZERO, |e| warn!("watchdog fault: {:?}", e.kind))
+///     .with_level(Duration::from_secs(5), |_| drive_safe_outputs());
+/// watchdog.set_escalation(policy);
+/// ```
+///
+/// The levels are reset when the watchdog leaves Fault state. The initial fault (no beats have
+/// been received since the start) is not escalated. The actions are called from the watchdog run
+/// loop (the fault duration is checked after each I/O read), so they must not block.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Default)]
+pub struct EscalationPolicy {
+    levels: Vec<Level>,
+}
+
+impl EscalationPolicy {
+    /// Create a new escalation policy without levels
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a level which is triggered when the fault persists longer than the duration (use
+    /// [`Duration::ZERO`] for the first fault actions). The levels are ordered by the duration
+    pub fn with_level(
+        mut self,
+        after: Duration,
+        action: impl Fn(&Escalation) + Send + Sync + 'static,
+    ) -> Self {
+        let pos = self.levels.partition_point(|l| l.after <= after);
+        self.levels.insert(
+            pos,
+            Level {
+                after,
+                action: Box::new(action),
+            },
+        );
+        self
+    }
+    /// Get the number of levels
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+}
+
+/// Escalation state of a watchdog
+pub(crate) struct Escalator {
+    policy: EscalationPolicy,
+    // the number of triggered levels
+    level: usize,
+}
+
+impl Escalator {
+    pub(crate) fn new(policy: EscalationPolicy) -> Self {
+        Self { policy, level: 0 }
+    }
+    /// Trigger the levels of the current fault which are due (the times are the watchdog clock
+    /// times). The initial fault is not escalated, as no beat has been received yet
+    pub(crate) fn update(
+        &mut self,
+        state: State,
+        last_fault: Option<(FaultKind, Duration)>,
+        now: Duration,
+    ) {
+        let Some((kind, since)) =
+            last_fault.filter(|(kind, _)| state == State::Fault && *kind != FaultKind::Initial)
+        else {
+            self.level = 0;
+            return;
+        };
+        let elapsed = now.saturating_sub(since);
+        while let Some(level) = self.policy.levels.get(self.level) {
+            if elapsed < level.after {
+                break;
+            }
+            self.level += 1;
+            (level.action)(&Escalation {
+                level: self.level,
+                kind,
+                elapsed,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtsc::pi::Mutex;
+    use std::sync::Arc;
+
+    const SEC: Duration = Duration::from_secs(1);
+
+    /// An escalator with levels after 0 and 5 seconds, returns the triggered escalations
+    fn escalator() -> (Escalator, Arc<Mutex<Vec<Escalation>>>) {
+        let triggered = Arc::new(Mutex::new(Vec::new()));
+        let (t1, t2) = (triggered.clone(), triggered.clone());
+        let policy = EscalationPolicy::new()
+            .with_level(SEC * 5, move |e| t2.lock().push(*e))
+            .with_level(Duration::ZERO, move |e| t1.lock().push(*e));
+        (Escalator::new(policy), triggered)
+    }
+
+    fn escalation(level: usize, elapsed: Duration) -> Escalation {
+        Escalation {
+            level,
+            kind: FaultKind::Timeout,
+            elapsed,
+        }
+    }
+
+    #[test]
+    fn test_levels() {
+        let (mut escalator, triggered) = escalator();
+        let fault = Some((FaultKind::Timeout, SEC * 10));
+        escalator.update(State::Fault, fault, SEC * 10);
+        escalator.update(State::Fault, fault, SEC * 14);
+        assert_eq!(*triggered.lock(), [escalation(1, Duration::ZERO)]);
+        escalator.update(State::Fault, fault, SEC * 15);
+        // each level is triggered once per fault
+        escalator.update(State::Fault, fault, SEC * 20);
+        assert_eq!(
+            *triggered.lock(),
+            [escalation(1, Duration::ZERO), escalation(2, SEC * 5)]
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let (mut escalator, triggered) = escalator();
+        escalator.update(State::Fault, Some((FaultKind::Timeout, SEC)), SEC);
+        // the levels are reset when the watchdog leaves Fault state
+        escalator.update(State::Ok, Some((FaultKind::Timeout, SEC)), SEC * 2);
+        escalator.update(State::Fault, Some((FaultKind::Timeout, SEC * 3)), SEC * 3);
+        assert_eq!(
+            *triggered.lock(),
+            [escalation(1, Duration::ZERO), escalation(1, Duration::ZERO)]
+        );
+    }
+
+    #[test]
+    fn test_initial() {
+        let (mut escalator, triggered) = escalator();
+        escalator.update(State::Fault, Some((FaultKind::Initial, SEC)), SEC * 10);
+        assert!(triggered.lock().is_empty());
+        escalator.update(State::Fault, Some((FaultKind::Timeout, SEC * 10)), SEC * 10);
+        assert_eq!(*triggered.lock(), [escalation(1, Duration::ZERO)]);
+    }
+}
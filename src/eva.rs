@@ -0,0 +1,93 @@
+use core::time::Duration;
+use std::thread;
+
+use rtsc::policy_channel;
+use serde_json::{json, Value};
+
+use crate::{io::http::HttpClient, Condvar, Error, RawMutex, Result, State, StateSource};
+
+/// The default interval of the state refresh
+pub const DEFAULT_REFRESH: Duration = Duration::from_secs(10);
+
+/// EVA ICS lvar client (HTTP JSON-RPC API of the HMI service)
+///
+/// The watchdog state is mapped to the item status and value: the status is 1 while the watchdog
+/// is alive and -1 (error) in Fault state, the value is the state code (0 - Fault, 1 - OK,
+/// 2 - Warning), so the operators can set up alarms on both.
+#[allow(clippy::module_name_repetitions)]
+pub struct EvaItem {
+    client: HttpClient,
+    oid: String,
+    key: String,
+}
+
+impl EvaItem {
+    /// Create a new client, the URL is the HMI service API URL (e.g. `http://eva:7727/jrpc`),
+    /// the key is an API key with write access to the item, the OID is the item OID (e.g.
+    /// `lvar:watchdog/plc1`)
+    pub fn create(url: &str, key: &str, oid: &str, timeout: Duration) -> Result<Self> {
+        if !oid.starts_with("lvar:") {
+            return Err(Error::failed("only lvar items can be set via the HTTP API"));
+        }
+        Ok(Self {
+            client: HttpClient::create(url, timeout)?,
+            oid: oid.to_owned(),
+            key: key.to_owned(),
+        })
+    }
+    /// Get the item OID
+    pub fn oid(&self) -> &str {
+        &self.oid
+    }
+    /// Set the item state
+    pub fn set(&self, state: State) -> Result<()> {
+        let status = if state == State::Fault { -1 } else { 1 };
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "lvar.set",
+            "params": {
+                "k": self.key,
+                "i": self.oid,
+                "status": status,
+                "value": state as u8,
+            }
+        });
+        let response = self.client.post(
+            "application/json",
+            &serde_json::to_vec(&request).map_err(Error::failed)?,
+        )?;
+        let response: Value = serde_json::from_slice(&response).map_err(Error::failed)?;
+        if let Some(error) = response.get("error") {
+            return Err(Error::failed(format!(
+                "EVA ICS API error: {}",
+                error.get("message").and_then(Value::as_str).unwrap_or("?")
+            )));
+        }
+        Ok(())
+    }
+    /// Follow the state of the watchdog (or any other state source). The item is set from a
+    /// background thread on transitions and is refreshed with the interval (e.g. after the EVA
+    /// ICS node restart), API errors are ignored and the state is sent again on the next refresh
+    pub fn attach<S: StateSource>(self, source: &S, refresh: Duration) -> Result<()> {
+        let (tx, rx) = policy_channel::bounded::<State, RawMutex, Condvar>(1);
+        let mut state = source.state();
+        source.on_state_change(Box::new(move |event| {
+            if let Some(state) = event.state() {
+                // never blocks as the latest state replaces the previous one
+                let _ = tx.try_send(state);
+            }
+        }));
+        thread::Builder::new()
+            .name("watchdog-eva".to_owned())
+            .spawn(move || loop {
+                let _ = self.set(state);
+                match rx.recv_timeout(refresh) {
+                    Ok(new_state) => state = new_state,
+                    Err(rtsc::Error::Timeout) => {}
+                    Err(_) => break,
+                }
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    os::fd::AsRawFd,
+    path::Path,
+};
+
+use rtsc::{pi::Mutex, time::interval};
+
+use crate::{Error, Result, State, StateSource};
+
+/// The default hardware watchdog device
+pub const DEFAULT_DEVICE: &str = "/dev/watchdog";
+
+const WDIOC_SETTIMEOUT: libc::Ioctl = libc::_IOWR::<libc::c_int>(b'W' as u32, 6);
+const WDIOC_GETTIMEOUT: libc::Ioctl = libc::_IOR::<libc::c_int>(b'W' as u32, 7);
+
+/// Linux hardware watchdog device
+///
+/// The watchdog is armed when the device is opened, the system is reset if the device is not fed
+/// within the hardware timeout. The watchdog is disarmed with [`HardwareWatchdog::disarm`] only
+/// (if the kernel allows it), so the system is also reset if the process crashes.
+#[allow(clippy::module_name_repetitions)]
+pub struct HardwareWatchdog {
+    file: Mutex<File>,
+}
+
+impl HardwareWatchdog {
+    /// Open the hardware watchdog device (e.g. [`DEFAULT_DEVICE`]), the watchdog is armed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = OpenOptions::new().write(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+    /// Set the hardware timeout (seconds), returns the actual timeout set by the driver
+    pub fn set_timeout(&self, timeout: u32) -> Result<u32> {
+        let mut value = libc::c_int::try_from(timeout).map_err(Error::failed)?;
+        let file = self.file.lock();
+        if unsafe { libc::ioctl(file.as_raw_fd(), WDIOC_SETTIMEOUT, &mut value) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        u32::try_from(value).map_err(Error::failed)
+    }
+    /// Get the hardware timeout (seconds)
+    pub fn timeout(&self) -> Result<u32> {
+        let mut value: libc::c_int = 0;
+        let file = self.file.lock();
+        if unsafe { libc::ioctl(file.as_raw_fd(), WDIOC_GETTIMEOUT, &mut value) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        u32::try_from(value).map_err(Error::failed)
+    }
+    /// Feed the hardware watchdog
+    pub fn feed(&self) -> Result<()> {
+        self.file.lock().write_all(&[0])?;
+        Ok(())
+    }
+    /// Disarm the hardware watchdog (magic close). Has no effect if the kernel is configured with
+    /// `CONFIG_WATCHDOG_NOWAYOUT`
+    pub fn disarm(self) -> Result<()> {
+        self.file.lock().write_all(b"V")?;
+        Ok(())
+    }
+}
+
+/// Hardware watchdog feeder
+///
+/// Feeds the hardware watchdog with the interval only while the software watchdog (or any other
+/// state source) is not in Fault state, so a missed heartbeat ultimately leads to a hardware
+/// reset. The hardware timeout must exceed the feed interval and the time the software watchdog
+/// spends in the initial Fault state (the warmup and the first beats), e.g.:
+///
+/// ```rust,ignore
+/// let feeder = Feeder::new(HardwareWatchdog::open(DEFAULT_DEVICE)?, watchdog.clone())
+///     .with_interval(Duration::from_secs(1));
+/// std::thread::spawn(move || feeder.run());
+/// ```
+pub struct Feeder<S: StateSource> {
+    device: HardwareWatchdog,
+    source: S,
+    interval: Duration,
+}
+
+impl<S: StateSource> Feeder<S> {
+    /// Create a new feeder, the default interval is 1 second
+    pub fn new(device: HardwareWatchdog, source: S) -> Self {
+        Self {
+            device,
+            source,
+            interval: Duration::from_secs(1),
+        }
+    }
+    /// Set the feed interval
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    /// Get the hardware watchdog device
+    pub fn device(&self) -> &HardwareWatchdog {
+        &self.device
+    }
+    /// Run the feeder
+    pub fn run(&self) -> Result<()> {
+        self.run_until(&AtomicBool::new(false))
+    }
+    /// Run the feeder until the stop flag is set, the hardware watchdog stays armed (see
+    /// [`HardwareWatchdog::disarm`])
+    pub fn run_until(&self, stop: &AtomicBool) -> Result<()> {
+        for _ in interval(self.interval) {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if self.source.state() != State::Fault {
+                self.device.feed()?;
+            }
+        }
+        Ok(())
+    }
+    /// Get the hardware watchdog device back, e.g. to disarm it after the feeder is stopped
+    pub fn into_device(self) -> HardwareWatchdog {
+        self.device
+    }
+}
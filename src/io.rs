@@ -1,6 +1,6 @@
-use core::future::Future;
+use core::{future::Future, time::Duration};
 
-use crate::{Edge, Result};
+use crate::{Edge, Result, State};
 
 /// Generic watchdog I/O trait
 #[allow(clippy::module_name_repetitions)]
@@ -10,32 +10,392 @@ pub trait WatchdogIo {
     fn get(&self, _expected: Edge) -> Result<Edge>;
     /// clears the watchdog I/O, e.g. a socket buffer in case of TCP/IP
     fn clear(&self) -> Result<()>;
+    /// gets the one-way latency of the last received beat (timestamped protocols only)
+    fn latency(&self) -> Option<Duration> {
+        None
+    }
+    /// gets the number of beats lost before the last received one or before
+    /// [`crate::Error::Lost`] is reported (sequenced protocols only)
+    fn lost(&self) -> u32 {
+        0
+    }
+    /// gets the number of packets rejected by the last read (e.g. failed authentication)
+    fn rejected(&self) -> u32 {
+        0
+    }
+    /// resets the watchdog I/O after a failure (e.g. reopens a device or reconnects a client),
+    /// called by the watchdog before an I/O retry, does nothing by default
+    fn reset(&self) -> Result<()> {
+        Ok(())
+    }
+    /// acknowledges the received beat with the current watchdog state (I/Os with a feedback path
+    /// only, see [`crate::Heart::ack`]), does nothing by default
+    fn ack(&self, _state: State) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: WatchdogIo + ?Sized> WatchdogIo for std::sync::Arc<T> {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        (**self).get(expected)
+    }
+    fn clear(&self) -> Result<()> {
+        (**self).clear()
+    }
+    fn latency(&self) -> Option<Duration> {
+        (**self).latency()
+    }
+    fn lost(&self) -> u32 {
+        (**self).lost()
+    }
+    fn rejected(&self) -> u32 {
+        (**self).rejected()
+    }
+    fn reset(&self) -> Result<()> {
+        (**self).reset()
+    }
+    fn ack(&self, state: State) -> Result<()> {
+        (**self).ack(state)
+    }
 }
 
 /// Generic watchdog I/O trait
+///
+/// For `std` the futures must be `Send`, so the watchdog can be run by multi-threaded runtimes.
+/// Bare-metal (embassy) watchdogs are run by local executors, which allows to use HAL futures
+/// which are not `Send`.
 #[allow(clippy::module_name_repetitions)]
 pub trait WatchdogIoAsync {
     /// gets the next edge asynchronously, the expected edge can be used to detect changes in case
+    #[cfg(feature = "std")]
     fn get(&self, _expected: Edge) -> impl Future<Output = Result<Edge>> + Send;
+    /// gets the next edge asynchronously, the expected edge can be used to detect changes in case
+    #[cfg(not(feature = "std"))]
+    fn get(&self, _expected: Edge) -> impl Future<Output = Result<Edge>>;
     /// clears the watchdog I/O asynchronously
+    #[cfg(feature = "std")]
     fn clear(&self) -> impl Future<Output = Result<()>> + Send;
+    /// clears the watchdog I/O asynchronously
+    #[cfg(not(feature = "std"))]
+    fn clear(&self) -> impl Future<Output = Result<()>>;
+    /// gets the one-way latency of the last received beat (timestamped protocols only)
+    fn latency(&self) -> Option<Duration> {
+        None
+    }
+    /// gets the number of beats lost before the last received one or before
+    /// [`crate::Error::Lost`] is reported (sequenced protocols only)
+    fn lost(&self) -> u32 {
+        0
+    }
+    /// gets the number of packets rejected by the last read (e.g. failed authentication)
+    fn rejected(&self) -> u32 {
+        0
+    }
+    /// resets the watchdog I/O asynchronously after a failure, does nothing by default
+    #[cfg(feature = "std")]
+    fn reset(&self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+    /// resets the watchdog I/O asynchronously after a failure, does nothing by default
+    #[cfg(not(feature = "std"))]
+    fn reset(&self) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+    /// acknowledges the received beat asynchronously, does nothing by default
+    #[cfg(feature = "std")]
+    fn ack(&self, _state: State) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+    /// acknowledges the received beat asynchronously, does nothing by default
+    #[cfg(not(feature = "std"))]
+    fn ack(&self, _state: State) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+}
+
+/// Multi-node watchdog I/O trait (see [`crate::MultiWatchdog`])
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub trait MultiWatchdogIo {
+    /// gets the next beat of any node: the node ID and the edge or the node error (e.g.
+    /// [`crate::Error::Lost`]). Returns [`crate::Error::Timeout`] if no beats have been received
+    /// within the I/O timeout
+    fn get(&self) -> Result<(u16, Result<Edge>)>;
+    /// clears the watchdog I/O state of the node (e.g. the sequence numbers) after its fault
+    fn clear(&self, node: u16) -> Result<()>;
+    /// gets the one-way latency of the last received beat (timestamped protocols only)
+    fn latency(&self) -> Option<Duration> {
+        None
+    }
+    /// gets the number of beats lost before the last received one or before
+    /// [`crate::Error::Lost`] is reported (sequenced protocols only)
+    fn lost(&self) -> u32 {
+        0
+    }
+    /// gets the number of packets rejected by the last read (e.g. failed authentication)
+    fn rejected(&self) -> u32 {
+        0
+    }
+    /// resets the watchdog I/O after a failure, does nothing by default
+    fn reset(&self) -> Result<()> {
+        Ok(())
+    }
+    /// acknowledges the received beat with the current node state (I/Os with a feedback path
+    /// only), does nothing by default
+    fn ack(&self, _state: State) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sequence numbers which are behind the last one by not more than the window are considered as
+/// reordered beats, otherwise the heart is considered as restarted
+#[cfg(any(feature = "std", feature = "embassy-can"))]
+const REORDER_WINDOW: u16 = 64;
+
+/// The result of a sequence number check
+#[cfg(any(feature = "std", feature = "embassy-can"))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Sequenced {
+    /// The beat is accepted, the edge is corrected for the tolerated losses
+    Beat {
+        edge: Edge,
+        /// The number of beats lost before this one
+        lost: u16,
+    },
+    /// A duplicate or a reordered beat, which must be ignored
+    Ignored,
+    /// Too many beats have been lost before this one
+    Lost(u16),
+}
+
+/// Sequence number state of the sequenced protocols (UDP v2, CAN FD)
+#[cfg(any(feature = "std", feature = "embassy-can"))]
+#[derive(Debug, Default)]
+pub(crate) struct Sequence {
+    last: Option<u16>,
+    // set if an odd number of beats has been lost, so the edges are out of phase
+    invert: bool,
+}
+
+#[cfg(any(feature = "std", feature = "embassy-can"))]
+impl Sequence {
+    /// checks the sequence number of a beat. A zero sequence number which does not follow the
+    /// last one within the tolerated losses (the counter wrap) is considered as the heart restart,
+    /// unless the restarts are detected by the caller: then `restart` tells if the beat starts a
+    /// new sequence
+    pub(crate) fn check(
+        &mut self,
+        edge: Edge,
+        seq: u16,
+        max_lost: u16,
+        restart: Option<bool>,
+    ) -> Sequenced {
+        let Some(prev) = self.last else {
+            self.last = Some(seq);
+            return Sequenced::Beat { edge, lost: 0 };
+        };
+        let gap = seq.wrapping_sub(prev);
+        // the restart is checked first, as a heart may restart with a sequence number which is
+        // still within the reorder window
+        if restart.unwrap_or(seq == 0 && gap != 0 && gap - 1 > max_lost) {
+            *self = Self {
+                last: Some(seq),
+                invert: false,
+            };
+            return Sequenced::Beat { edge, lost: 0 };
+        }
+        if gap == 0 || prev.wrapping_sub(seq) <= REORDER_WINDOW {
+            return Sequenced::Ignored;
+        }
+        self.last = Some(seq);
+        let lost = gap - 1;
+        if lost > max_lost {
+            return Sequenced::Lost(lost);
+        }
+        // tolerated losses must not be reported as out-of-order edges
+        if lost % 2 == 1 {
+            self.invert = !self.invert;
+        }
+        Sequenced::Beat {
+            edge: self.apply(edge),
+            lost,
+        }
+    }
+    /// corrects the edge of a beat without a sequence number for the tolerated losses
+    pub(crate) fn apply(&self, edge: Edge) -> Edge {
+        if self.invert {
+            !edge
+        } else {
+            edge
+        }
+    }
+}
+
+/// A non-blocking listener of the stream I/Os, which accept a single heart connection at a time
+#[cfg(feature = "std")]
+pub(crate) trait StreamListener {
+    type Stream;
+    /// accepts a pending connection and sets up the stream for blocking reads with the I/O
+    /// timeout, returns [`std::io::ErrorKind::WouldBlock`] if there is none
+    fn accept_stream(&self) -> std::io::Result<Self::Stream>;
+}
+
+/// A stream of the stream I/Os which can be drained
+#[cfg(feature = "std")]
+pub(crate) trait StreamSocket: std::io::Read {
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl StreamSocket for std::net::TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        std::net::TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(feature = "std")]
+impl StreamSocket for std::os::unix::net::UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(feature = "std")]
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// accepts a pending connection, if any
+#[cfg(feature = "std")]
+pub(crate) fn try_accept<L: StreamListener>(listener: &L) -> Result<Option<L::Stream>> {
+    match listener.accept_stream() {
+        Ok(stream) => Ok(Some(stream)),
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// waits for a connection, returns [`crate::Error::Timeout`] if there is none within the timeout
+#[cfg(feature = "std")]
+pub(crate) fn accept<L: StreamListener>(listener: &L, timeout: Duration) -> Result<L::Stream> {
+    let now = std::time::Instant::now();
+    loop {
+        if let Some(stream) = try_accept(listener)? {
+            return Ok(stream);
+        }
+        if now.elapsed() > timeout {
+            return Err(crate::Error::Timeout);
+        }
+        std::thread::sleep(ACCEPT_POLL_INTERVAL);
+    }
+}
+
+/// reads the next edge from the current stream of a listener, a pending connection replaces the
+/// current one. The stream is dropped if the connection is closed or broken
+#[cfg(feature = "std")]
+pub(crate) fn read_edge<L>(
+    listener: &L,
+    stream: &mut Option<L::Stream>,
+    timeout: Duration,
+) -> Result<Edge>
+where
+    L: StreamListener,
+    L::Stream: std::io::Read,
+{
+    // a pending connection replaces the current one (e.g. the heart has been restarted)
+    if let Some(s) = try_accept(listener)? {
+        stream.replace(s);
+    }
+    let s = match stream {
+        Some(s) => s,
+        None => stream.insert(accept(listener, timeout)?),
+    };
+    let mut buf = [0];
+    match std::io::Read::read(s, &mut buf) {
+        Ok(0) => {
+            // connection closed by the peer
+            stream.take();
+            Err(crate::Error::Timeout)
+        }
+        Ok(_) => Ok(Edge::from(buf[0])),
+        Err(e) => {
+            let e = crate::Error::from(e);
+            if !matches!(e, crate::Error::Timeout) {
+                // the stream is broken, the peer is dead
+                stream.take();
+            }
+            Err(e)
+        }
+    }
+}
+
+/// drains the current stream, which is dropped if the connection is closed or broken
+#[cfg(feature = "std")]
+pub(crate) fn clear_stream<S: StreamSocket>(stream: &mut Option<S>) {
+    if let Some(s) = stream.as_mut() {
+        if drain(s).is_err() {
+            stream.take();
+        }
+    }
+}
+
+/// polls with the interval until the function returns a value, returns [`crate::Error::Timeout`]
+/// if there is none within the timeout. The function gets the time remaining, which must bound
+/// its blocking calls
+#[cfg(feature = "std")]
+pub(crate) fn poll_until<T>(
+    timeout: Duration,
+    pull_interval: Duration,
+    mut f: impl FnMut(Duration) -> Option<T>,
+) -> Result<T> {
+    let now = std::time::Instant::now();
+    for _ in rtsc::time::interval(pull_interval) {
+        let Some(remaining) = timeout.checked_sub(now.elapsed()).filter(|r| !r.is_zero()) else {
+            break;
+        };
+        if let Some(value) = f(remaining) {
+            return Ok(value);
+        }
+    }
+    Err(crate::Error::Timeout)
+}
+
+/// reads all pending data, returns an error if the connection is closed or broken
+#[cfg(feature = "std")]
+pub(crate) fn drain<S: StreamSocket>(stream: &mut S) -> std::io::Result<()> {
+    stream.set_nonblocking(true)?;
+    let mut buf = [0; 64];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => std::thread::yield_now(),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e),
+        }
+    }
+    stream.set_nonblocking(false)
 }
 
 #[cfg(feature = "gpio")]
 /// GPIO communication
 pub mod gpio {
 
-    use crate::{Edge, Error, Result};
+    use crate::{Edge, Error, Result, State, StateSource};
     use std::{
+        io::Read,
+        os::fd::AsRawFd,
         path::{Path, PathBuf},
         time::{Duration, Instant},
     };
 
-    use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+    use async_io::{Async, Timer};
+    use futures_lite::future;
+    use gpio_cdev::{
+        Chip, EventRequestFlags, EventType, LineEventHandle, LineHandle, LineRequestFlags,
+    };
     use portable_atomic::AtomicBool;
-    use rtsc::time::interval;
+    use rtsc::{pi::Mutex, time::interval};
 
-    use super::WatchdogIo;
+    use super::{WatchdogIo, WatchdogIoAsync};
 
     #[allow(clippy::module_name_repetitions)]
     /// GPIO client
@@ -71,6 +431,54 @@ pub mod gpio {
         }
     }
 
+    /// GPIO output which reflects the watchdog state
+    ///
+    /// The line is active while the watchdog is in OK or Warning state and inactive in Fault
+    /// state (and initially), so a hardware safety relay can be tripped directly, e.g.:
+    ///
+    /// ```rust,ignore
+    /// GpioStateOutput::create("/dev/gpiochip0", 17, false)?.attach(&watchdog);
+    /// ```
+    #[allow(clippy::module_name_repetitions)]
+    pub struct GpioStateOutput {
+        handle: LineHandle,
+    }
+
+    impl GpioStateOutput {
+        /// creates a new state output, the line is set to the inactive level. If active-low, the
+        /// line is driven low while the watchdog is OK
+        pub fn create<P: AsRef<Path>>(chip: P, offset: u32, active_low: bool) -> Result<Self> {
+            let mut chip = Chip::new(chip).map_err(Error::failed)?;
+            let line = chip.get_line(offset).map_err(Error::failed)?;
+            let mut flags = LineRequestFlags::OUTPUT;
+            if active_low {
+                flags |= LineRequestFlags::ACTIVE_LOW;
+            }
+            let handle = line
+                .request(flags, 0, "gpio-watchdog-state")
+                .map_err(Error::failed)?;
+            Ok(Self { handle })
+        }
+        /// sets the output according to the state
+        pub fn set(&self, state: State) -> Result<()> {
+            self.handle
+                .set_value(u8::from(state != State::Fault))
+                .map_err(Error::failed)
+        }
+        /// drives the output by the state events of the watchdog (or any other state source), the
+        /// output is set to the current state. The line write errors in the state events are
+        /// ignored (the line keeps its level)
+        pub fn attach<S: StateSource>(self, source: &S) -> Result<()> {
+            self.set(source.state())?;
+            source.on_state_change(Box::new(move |event| {
+                if let Some(state) = event.state() {
+                    let _ = self.set(state);
+                }
+            }));
+            Ok(())
+        }
+    }
+
     #[allow(clippy::module_name_repetitions)]
     /// GPIO watchdog I/O configuration
     #[derive(Debug, Clone)]
@@ -133,57 +541,764 @@ pub mod gpio {
             Ok(())
         }
     }
+
+    /// Kernel line event timestamps replay
+    ///
+    /// If an event is read late (e.g. the watchdog thread has been delayed), it is reported no
+    /// earlier than the previous one plus the timestamp difference, so the watchdog measures the
+    /// intervals of the line edges rather than of the reads.
+    #[derive(Default)]
+    struct Replay {
+        // the last reported event: report time, kernel timestamp
+        last: Option<(Instant, u64)>,
+    }
+
+    impl Replay {
+        /// returns the delay before the event can be reported
+        fn delay(&self, timestamp: u64) -> Duration {
+            self.last.map_or(Duration::ZERO, |(reported_at, prev)| {
+                (reported_at + Duration::from_nanos(timestamp.saturating_sub(prev)))
+                    .saturating_duration_since(Instant::now())
+            })
+        }
+        fn reported(&mut self, timestamp: u64) {
+            self.last = Some((Instant::now(), timestamp));
+        }
+        fn reset(&mut self) {
+            self.last = None;
+        }
+    }
+
+    /// Event-driven GPIO watchdog I/O
+    ///
+    /// Blocks on kernel line edge events instead of polling the line value. The events are
+    /// timestamped by the kernel, which is used to keep the watchdog window accurate if the
+    /// events are read late.
+    #[allow(clippy::module_name_repetitions)]
+    pub struct GpioEventIo {
+        events: Mutex<Events>,
+        timeout: Duration,
+    }
+
+    struct Events {
+        handle: LineEventHandle,
+        replay: Replay,
+    }
+
+    fn request_events<P: AsRef<Path>>(chip: P, offset: u32) -> Result<LineEventHandle> {
+        let mut chip = Chip::new(chip).map_err(Error::failed)?;
+        let line = chip.get_line(offset).map_err(Error::failed)?;
+        line.events(
+            LineRequestFlags::INPUT,
+            EventRequestFlags::BOTH_EDGES,
+            "gpio-watchdog",
+        )
+        .map_err(Error::failed)
+    }
+
+    impl GpioEventIo {
+        /// creates a new event-driven GPIO watchdog I/O
+        pub fn create<P: AsRef<Path>>(chip: P, offset: u32, timeout: Duration) -> Result<Self> {
+            Ok(Self {
+                events: Mutex::new(Events {
+                    handle: request_events(chip, offset)?,
+                    replay: Replay::default(),
+                }),
+                timeout,
+            })
+        }
+    }
+
+    impl Events {
+        /// waits for an event, returns true if an event is available
+        fn wait(&self, timeout: Duration) -> Result<bool> {
+            let mut pfd = libc::pollfd {
+                fd: self.handle.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+            let res = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+            if res < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+                return Err(e.into());
+            }
+            Ok(res > 0)
+        }
+    }
+
+    impl WatchdogIo for GpioEventIo {
+        fn get(&self, _expected: Edge) -> Result<Edge> {
+            let mut events = self.events.lock();
+            let now = Instant::now();
+            loop {
+                let remaining = self.timeout.saturating_sub(now.elapsed());
+                if remaining.is_zero() {
+                    return Err(Error::Timeout);
+                }
+                if events.wait(remaining)? {
+                    break;
+                }
+            }
+            let event = events.handle.get_event().map_err(Error::failed)?;
+            let timestamp = event.timestamp();
+            let delay = events.replay.delay(timestamp);
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+            events.replay.reported(timestamp);
+            Ok(match event.event_type() {
+                EventType::RisingEdge => Edge::Rising,
+                EventType::FallingEdge => Edge::Falling,
+            })
+        }
+
+        fn clear(&self) -> Result<()> {
+            let mut events = self.events.lock();
+            while events.wait(Duration::ZERO)? {
+                events.handle.get_event().map_err(Error::failed)?;
+            }
+            events.replay.reset();
+            Ok(())
+        }
+    }
+
+    /// Kernel `gpioevent_data` size: u64 timestamp, u32 event id, padding
+    const EVENT_DATA_SIZE: usize = 16;
+    const EVENT_RISING_EDGE: u32 = 0x01;
+
+    /// Reads an event from a non-blocking event handle: kernel timestamp, edge
+    fn read_event(handle: &LineEventHandle) -> std::io::Result<(u64, Edge)> {
+        let mut buf = [0u8; EVENT_DATA_SIZE];
+        let mut file = handle.file();
+        if file.read(&mut buf)? != EVENT_DATA_SIZE {
+            return Err(std::io::Error::other("invalid GPIO event size"));
+        }
+        let timestamp = u64::from_ne_bytes(buf[..8].try_into().unwrap());
+        let id = u32::from_ne_bytes(buf[8..12].try_into().unwrap());
+        Ok((
+            timestamp,
+            if id == EVENT_RISING_EDGE {
+                Edge::Rising
+            } else {
+                Edge::Falling
+            },
+        ))
+    }
+
+    /// Asynchronous event-driven GPIO watchdog I/O
+    ///
+    /// The same as [`GpioEventIo`] but for [`crate::WatchdogAsync`]. The events are processed by
+    /// the `async-io` reactor, so the I/O can be used with any async runtime.
+    #[allow(clippy::module_name_repetitions)]
+    pub struct GpioEventIoAsync {
+        handle: Async<LineEventHandle>,
+        replay: Mutex<Replay>,
+        timeout: Duration,
+    }
+
+    impl GpioEventIoAsync {
+        /// creates a new asynchronous event-driven GPIO watchdog I/O
+        pub fn create<P: AsRef<Path>>(chip: P, offset: u32, timeout: Duration) -> Result<Self> {
+            Ok(Self {
+                handle: Async::new(request_events(chip, offset)?)?,
+                replay: Mutex::new(Replay::default()),
+                timeout,
+            })
+        }
+    }
+
+    impl WatchdogIoAsync for GpioEventIoAsync {
+        async fn get(&self, _expected: Edge) -> Result<Edge> {
+            let (timestamp, edge) = future::or(
+                async { self.handle.read_with(read_event).await.map_err(Error::from) },
+                async {
+                    Timer::after(self.timeout).await;
+                    Err(Error::Timeout)
+                },
+            )
+            .await?;
+            let delay = self.replay.lock().delay(timestamp);
+            if !delay.is_zero() {
+                Timer::after(delay).await;
+            }
+            self.replay.lock().reported(timestamp);
+            Ok(edge)
+        }
+
+        async fn clear(&self) -> Result<()> {
+            loop {
+                match read_event(self.handle.get_ref()) {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            self.replay.lock().reset();
+            Ok(())
+        }
+    }
 }
 
+/// Embassy (MCU) communication
+#[cfg(feature = "embassy")]
+pub mod embassy;
+
 /// UDP communication
 #[cfg(feature = "std")]
 pub mod udp {
-    use crate::{Edge, Error, Heart, Result};
+    #[cfg(feature = "auth")]
+    use crate::auth::{Authenticator, TAG_SIZE};
+    #[cfg(feature = "encryption")]
+    use crate::payload::{PayloadCipher, ENCRYPTION_OVERHEAD};
+    use crate::{
+        payload::{Decode, Encode, Payload, PayloadHeart, PayloadSender, MAX_PAYLOAD},
+        Ack, Edge, Error, Heart, Result, State,
+    };
     use core::time::Duration;
+    #[cfg(feature = "auth")]
+    use std::collections::VecDeque;
     use std::{
-        net::{ToSocketAddrs, UdpSocket},
+        collections::BTreeMap,
+        net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket},
+        os::fd::{AsRawFd, OwnedFd},
         thread,
+        time::Instant,
     };
 
-    use portable_atomic::{AtomicBool, Ordering};
+    use portable_atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering};
+    use rtsc::pi::Mutex;
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    use super::{MultiWatchdogIo, Sequence, Sequenced, WatchdogIo};
+
+    /// Edge and sequence number size of v2 datagrams, the optional fields and the payload follow
+    const HEADER_SIZE: usize = 3;
+    /// Optional header field size (the send time, the challenge answer)
+    const FIELD_SIZE: usize = 8;
+    /// Set in the edge byte of timestamped datagrams (the send time follows the sequence number)
+    const TIMESTAMP_FLAG: u8 = 0x80;
+    /// Set in the edge byte of datagrams which carry the challenge answer (follows the send time)
+    const ANSWER_FLAG: u8 = 0x40;
+    /// Set in the edge byte of datagrams which carry the node ID (the last header field)
+    const NODE_FLAG: u8 = 0x10;
+    const NODE_SIZE: usize = 2;
+    // the flags use the bits which are not set in the edges ("+" or ".")
+    const FLAGS: u8 = TIMESTAMP_FLAG | ANSWER_FLAG | NODE_FLAG;
+    const MAX_HEADER_SIZE: usize = HEADER_SIZE + 2 * FIELD_SIZE + NODE_SIZE;
 
-    use super::WatchdogIo;
+    /// Random value from the OS random source (challenge nonces, session IDs)
+    fn random_u64() -> Result<u64> {
+        let mut buf = [0; FIELD_SIZE];
+        getrandom::getrandom(&mut buf).map_err(Error::failed)?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+    /// The first byte of challenge datagrams, the nonce follows
+    const CHALLENGE_MARKER: u8 = b'?';
+    const CHALLENGE_SIZE: usize = 1 + FIELD_SIZE;
+    /// The first byte of acknowledgements, the watchdog state follows
+    const ACK_MARKER: u8 = b'!';
+    const ACK_SIZE: usize = 2;
+    #[cfg(not(feature = "auth"))]
+    const TAG_SIZE: usize = 0;
+    /// The session ID and the beat counter of authenticated datagrams, which precede the tag
+    #[cfg(feature = "auth")]
+    const SESSION_SIZE: usize = 2 * FIELD_SIZE;
+    #[cfg(not(feature = "auth"))]
+    const SESSION_SIZE: usize = 0;
+    /// The number of the previous sessions of a heart which are remembered to reject their replays
+    #[cfg(feature = "auth")]
+    const MAX_RETIRED_SESSIONS: usize = 16;
+    #[cfg(not(feature = "encryption"))]
+    const ENCRYPTION_OVERHEAD: usize = 0;
+    const MAX_DATAGRAM_SIZE: usize =
+        MAX_HEADER_SIZE + MAX_PAYLOAD + ENCRYPTION_OVERHEAD + SESSION_SIZE + TAG_SIZE;
+
+    /// v2 header size with the optional fields
+    fn header_size(flags: u8) -> usize {
+        let node = if flags & NODE_FLAG == 0 { 0 } else { NODE_SIZE };
+        HEADER_SIZE
+            + FIELD_SIZE * (flags & (TIMESTAMP_FLAG | ANSWER_FLAG)).count_ones() as usize
+            + node
+    }
+
+    /// Reads the optional header field which follows the given number of fields
+    fn header_field(header: &[u8], n: usize) -> u64 {
+        let mut field = [0; FIELD_SIZE];
+        let start = HEADER_SIZE + FIELD_SIZE * n;
+        field.copy_from_slice(&header[start..start + FIELD_SIZE]);
+        u64::from_be_bytes(field)
+    }
+
+    /// Wall-clock time, used for timestamps
+    fn unix_time() -> Duration {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    type PayloadSink = Box<dyn Fn(Edge, &[u8]) + Send + Sync>;
+
+    /// Binds a UDP socket with SO_REUSEADDR, so several watchdogs on the host can bind the port to
+    /// receive the same broadcast/multicast datagrams (a unicast datagram is delivered to a single
+    /// socket only)
+    fn bind_reuse(addr: SocketAddrV4) -> Result<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        socket.bind(&SocketAddr::V4(addr).into())?;
+        Ok(socket.into())
+    }
 
     /// UDP client
     #[allow(clippy::module_name_repetitions)]
     pub struct UdpHeart {
         socket: UdpSocket,
         next: AtomicBool,
+        seq: Option<AtomicU16>,
+        timestamp: bool,
+        // the last received challenge nonce, 0 if none
+        answer: Option<AtomicU64>,
+        ack: Mutex<Option<(State, Instant)>>,
+        node: Option<u16>,
+        #[cfg(feature = "auth")]
+        auth: Option<Authenticator>,
+        // a random ID of the heart run and the number of authenticated datagrams sent
+        #[cfg(feature = "auth")]
+        session: (u64, AtomicU64),
+        #[cfg(feature = "encryption")]
+        cipher: Option<PayloadCipher>,
     }
 
     impl UdpHeart {
-        /// creates a new UDP client
-        pub fn create<A: ToSocketAddrs>(addr: A) -> Result<Self> {
-            let socket = UdpSocket::bind((std::net::Ipv4Addr::UNSPECIFIED, 0))?;
-            socket.connect(addr)?;
+        // the session ID of authenticated beats is generated from the OS random source
+        #[cfg_attr(not(feature = "auth"), allow(clippy::unnecessary_wraps))]
+        fn from_socket(socket: UdpSocket) -> Result<Self> {
             Ok(Self {
                 socket,
                 next: AtomicBool::new(true),
+                seq: None,
+                timestamp: false,
+                answer: None,
+                ack: Mutex::new(None),
+                node: None,
+                #[cfg(feature = "auth")]
+                auth: None,
+                #[cfg(feature = "auth")]
+                session: (random_u64()?, AtomicU64::new(0)),
+                #[cfg(feature = "encryption")]
+                cipher: None,
             })
         }
+        /// enables protocol v2: each beat carries a 2-byte sequence number, so the watchdog can
+        /// distinguish lost and reordered datagrams from out-of-order edges
+        pub fn with_sequence(mut self) -> Self {
+            self.seq = Some(AtomicU16::new(0));
+            self
+        }
+        /// enables timestamped v2 beats (implies [`UdpHeart::with_sequence`]): each beat carries
+        /// the send time, so the watchdog can measure the one-way latency (the clocks must be
+        /// synchronized, e.g. with NTP or PTP). Requires a watchdog which supports timestamps
+        pub fn with_timestamp(mut self) -> Self {
+            self.seq.get_or_insert_with(|| AtomicU16::new(0));
+            self.timestamp = true;
+            self
+        }
+        /// enables v2 beats with the node ID (implies [`UdpHeart::with_sequence`]), so several
+        /// hearts can be supervised by a single multi-node watchdog (see
+        /// [`crate::MultiWatchdog`])
+        pub fn with_node_id(mut self, node: u16) -> Self {
+            self.seq.get_or_insert_with(|| AtomicU16::new(0));
+            self.node = Some(node);
+            self
+        }
+        /// enables challenge-response v2 beats (implies [`UdpHeart::with_sequence`]): each beat
+        /// carries the last challenge nonce, which has been received by
+        /// [`UdpHeart::answer_challenge`]. Requires a watchdog with the challenge-response mode
+        /// enabled (see [`UdpIo::with_challenge`])
+        pub fn with_challenge(mut self) -> Self {
+            self.seq.get_or_insert_with(|| AtomicU16::new(0));
+            self.answer = Some(AtomicU64::new(0));
+            self
+        }
+        /// receives pending watchdog challenges (never blocks), the last nonce is echoed with the
+        /// next beats. Must be called from the application main loop (rather than from the
+        /// thread/timer which sends beats), so a wedged application stops answering challenges
+        /// even if its beats are still sent
+        pub fn answer_challenge(&self) -> Result<()> {
+            if self.answer.is_none() {
+                return Err(Error::failed("challenge-response mode is not enabled"));
+            }
+            self.receive()
+        }
+        /// receives pending challenges and acknowledgements from the watchdog (never blocks)
+        fn receive(&self) -> Result<()> {
+            let mut buf = [0; CHALLENGE_SIZE + 1];
+            loop {
+                let res = unsafe {
+                    libc::recv(
+                        self.socket.as_raw_fd(),
+                        buf.as_mut_ptr().cast(),
+                        buf.len(),
+                        libc::MSG_DONTWAIT,
+                    )
+                };
+                if res < 0 {
+                    let e = std::io::Error::last_os_error();
+                    match e.kind() {
+                        std::io::ErrorKind::WouldBlock => return Ok(()),
+                        // the watchdog is not started yet
+                        std::io::ErrorKind::ConnectionRefused => continue,
+                        _ => return Err(e.into()),
+                    }
+                }
+                match (usize::try_from(res), buf[0]) {
+                    (Ok(CHALLENGE_SIZE), CHALLENGE_MARKER) => {
+                        if let Some(ref answer) = self.answer {
+                            let mut nonce = [0; FIELD_SIZE];
+                            nonce.copy_from_slice(&buf[1..CHALLENGE_SIZE]);
+                            answer.store(u64::from_be_bytes(nonce), Ordering::Relaxed);
+                        }
+                    }
+                    (Ok(ACK_SIZE), ACK_MARKER) => {
+                        *self.ack.lock() = Some((State::from(buf[1]), Instant::now()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        /// enables authenticated v2 beats (implies [`UdpHeart::with_sequence`]): each beat carries
+        /// a random ID of the heart run (session), a beat counter and an HMAC tag of the datagram,
+        /// calculated with the shared key
+        #[cfg(feature = "auth")]
+        pub fn with_auth(mut self, key: &[u8]) -> Self {
+            self.seq.get_or_insert_with(|| AtomicU16::new(0));
+            self.auth = Some(Authenticator::new(key));
+            self
+        }
+        /// enables payload encryption (XChaCha20-Poly1305) with the shared key, implies
+        /// [`UdpHeart::with_sequence`]. The beat header is authenticated but not encrypted. Every
+        /// beat is sealed, including beats without a payload
+        #[cfg(feature = "encryption")]
+        pub fn with_encryption(mut self, key: &[u8; 32]) -> Result<Self> {
+            self.seq.get_or_insert_with(|| AtomicU16::new(0));
+            self.cipher = Some(PayloadCipher::new(key)?);
+            Ok(self)
+        }
+        fn flags(&self) -> u8 {
+            let mut flags = 0;
+            if self.timestamp {
+                flags |= TIMESTAMP_FLAG;
+            }
+            if self.answer.is_some() {
+                flags |= ANSWER_FLAG;
+            }
+            if self.node.is_some() {
+                flags |= NODE_FLAG;
+            }
+            flags
+        }
+        /// writes the v2 header
+        fn write_header(&self, seq: &AtomicU16, buf: &mut [u8]) {
+            buf[0] = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8 | self.flags();
+            buf[1..HEADER_SIZE].copy_from_slice(&seq.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+            let mut offset = HEADER_SIZE;
+            if self.timestamp {
+                let nanos = u64::try_from(unix_time().as_nanos()).unwrap_or(u64::MAX);
+                buf[offset..offset + FIELD_SIZE].copy_from_slice(&nanos.to_be_bytes());
+                offset += FIELD_SIZE;
+            }
+            if let Some(ref answer) = self.answer {
+                buf[offset..offset + FIELD_SIZE]
+                    .copy_from_slice(&answer.load(Ordering::Relaxed).to_be_bytes());
+                offset += FIELD_SIZE;
+            }
+            if let Some(node) = self.node {
+                buf[offset..offset + NODE_SIZE].copy_from_slice(&node.to_be_bytes());
+            }
+        }
+        /// sends a v2 datagram, the payload is written by the function
+        fn send_sequenced(
+            &self,
+            seq: &AtomicU16,
+            payload: impl FnOnce(&mut [u8]) -> Result<usize>,
+        ) -> Result<()> {
+            let mut buf = [0; MAX_DATAGRAM_SIZE];
+            let offset = header_size(self.flags());
+            #[allow(unused_mut)]
+            let mut len = offset + payload(&mut buf[offset..offset + MAX_PAYLOAD])?;
+            self.write_header(seq, &mut buf);
+            #[cfg(feature = "encryption")]
+            if let Some(ref cipher) = self.cipher {
+                // empty payloads are sealed as well, so the beat itself is authenticated
+                let (header, data) = buf.split_at_mut(offset);
+                len = offset + cipher.seal(header, data, len - offset)?;
+            }
+            #[cfg(feature = "auth")]
+            if let Some(ref auth) = self.auth {
+                let (session, ref counter) = self.session;
+                buf[len..len + FIELD_SIZE].copy_from_slice(&session.to_be_bytes());
+                buf[len + FIELD_SIZE..len + SESSION_SIZE]
+                    .copy_from_slice(&counter.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+                len += SESSION_SIZE;
+                let tag = auth.sign(&buf[..len]);
+                buf[len..len + TAG_SIZE].copy_from_slice(&tag);
+                len += TAG_SIZE;
+            }
+            self.socket.send(&buf[..len]).map_err(Error::from)?;
+            Ok(())
+        }
+        /// creates a new UDP client
+        pub fn create<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+            let socket = UdpSocket::bind((std::net::Ipv4Addr::UNSPECIFIED, 0))?;
+            socket.connect(addr)?;
+            Self::from_socket(socket)
+        }
+        /// creates a new UDP client, sending beats to a broadcast address (e.g.
+        /// "255.255.255.255:9999" or a subnet-directed one), so the watchdog address is not
+        /// required to be known in advance
+        pub fn create_broadcast<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+            socket.set_broadcast(true)?;
+            socket.connect(addr)?;
+            Self::from_socket(socket)
+        }
+        /// creates a new UDP client, sending beats to an IPv4 multicast group, so the heart can be
+        /// observed by several watchdogs. The TTL limits the number of hops (1 - local network only)
+        pub fn create_multicast(group: SocketAddrV4, ttl: u32) -> Result<Self> {
+            if !group.ip().is_multicast() {
+                return Err(Error::failed("not a multicast address"));
+            }
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+            socket.set_multicast_ttl_v4(ttl)?;
+            socket.connect(group)?;
+            Self::from_socket(socket)
+        }
     }
 
     impl Heart for UdpHeart {
         fn beat(&self) -> Result<()> {
-            self.socket
-                .send(&[Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8])
-                .map_err(Error::from)?;
+            if let Some(ref seq) = self.seq {
+                self.send_sequenced(seq, |_| Ok(0))?;
+            } else {
+                let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+                self.socket.send(&[edge]).map_err(Error::from)?;
+            }
             Ok(())
         }
+
+        /// Requires a watchdog which sends acknowledgements (see [`UdpIo::with_ack`])
+        fn ack(&self) -> Result<Option<Ack>> {
+            self.receive()?;
+            Ok(self.ack.lock().map(|(state, received)| Ack {
+                state,
+                elapsed: received.elapsed(),
+            }))
+        }
+    }
+
+    /// Payloads are sent with v2 datagrams only (see [`UdpHeart::with_sequence`]), watchdogs
+    /// which do not expect payloads ignore them
+    impl<T: Encode> PayloadHeart<T> for UdpHeart {
+        fn beat_with(&self, payload: &T) -> Result<()> {
+            let Some(ref seq) = self.seq else {
+                return Err(Error::failed("payloads require sequence numbers"));
+            };
+            self.send_sequenced(seq, |buf| payload.encode(buf))
+        }
+    }
+
+    /// Session state of authenticated datagrams
+    #[cfg(feature = "auth")]
+    #[derive(Default)]
+    struct Session {
+        id: Option<u64>,
+        counter: u64,
+        // the sessions of the previous heart runs, which must not be accepted again
+        retired: VecDeque<u64>,
+    }
+
+    #[cfg(feature = "auth")]
+    impl Session {
+        /// checks the session ID and the counter of an authenticated datagram, returns `None` for
+        /// replayed datagrams, `Some(true)` if a new session (the heart run) is started
+        fn check(&mut self, id: u64, counter: u64) -> Option<bool> {
+            match self.id {
+                Some(current) if current == id => {
+                    if counter <= self.counter {
+                        return None;
+                    }
+                    self.counter = counter;
+                    Some(false)
+                }
+                _ if self.retired.contains(&id) => None,
+                current => {
+                    if let Some(current) = current {
+                        if self.retired.len() == MAX_RETIRED_SESSIONS {
+                            self.retired.pop_front();
+                        }
+                        self.retired.push_back(current);
+                    }
+                    self.id = Some(id);
+                    self.counter = counter;
+                    Some(true)
+                }
+            }
+        }
+    }
+
+    /// Beat stream state of a heart
+    #[derive(Default)]
+    struct Stream {
+        sequence: Sequence,
+        // kept when the I/O is cleared, so the datagrams sent before are not accepted again
+        #[cfg(feature = "auth")]
+        session: Session,
+    }
+
+    /// Challenge-response state
+    struct Challenge {
+        interval: Duration,
+        deadline: Duration,
+        // the nonce which has been sent and is not answered yet
+        pending: Option<(u64, Instant)>,
+        next: Option<Instant>,
+        peer: Option<SocketAddr>,
+    }
+
+    impl Challenge {
+        fn reset(&mut self) {
+            self.pending = None;
+            self.next = None;
+        }
     }
 
     /// UDP watchdog I/O
+    ///
+    /// Accepts both single-byte (v1) and sequenced (v2, see [`UdpHeart::with_sequence`])
+    /// datagrams. For v2, duplicate and reordered datagrams are ignored, if more than the allowed
+    /// number of datagrams is lost, [`Error::Lost`] is reported (the tolerated losses are
+    /// counted in the watchdog statistics). With the `auth` feature, beats can be authenticated
+    /// with a shared key (see [`UdpIo::with_auth`]). The payloads of v2 datagrams
+    /// can be received with [`UdpIo::with_payload_tx`], the latency of timestamped ones (see
+    /// [`UdpHeart::with_timestamp`]) is reported to the watchdog statistics. To detect senders
+    /// which keep beating while the application is wedged, the challenge-response mode can be
+    /// enabled (see [`UdpIo::with_challenge`]), accepted beats can be acknowledged with the
+    /// watchdog state (see [`UdpIo::with_ack`]).
+    ///
+    /// The I/O also implements [`MultiWatchdogIo`]: beats with node IDs (see
+    /// [`UdpHeart::with_node_id`]) of many hearts are received with a single socket and
+    /// supervised by a [`crate::MultiWatchdog`], other datagrams are ignored. The sequence
+    /// numbers are tracked per node, the challenge-response mode is not supported.
     #[allow(clippy::module_name_repetitions)]
     pub struct UdpIo {
         socket: UdpSocket,
+        stream: Mutex<Stream>,
+        nodes: Mutex<BTreeMap<u16, Stream>>,
+        max_lost: u16,
+        payload: Option<PayloadSink>,
+        latency: Mutex<Option<Duration>>,
+        lost: AtomicU32,
+        rejected: AtomicU32,
+        #[cfg(feature = "encryption")]
+        cipher: Option<PayloadCipher>,
+        challenge: Option<Mutex<Challenge>>,
+        // the address of the last accepted beat, set if acknowledgements are enabled
+        ack: Option<Mutex<Option<SocketAddr>>>,
+        timeout: Duration,
+        // the read timeout is shortened if the datagrams are ignored
+        shortened: AtomicBool,
+        #[cfg(feature = "auth")]
+        auth: Option<Authenticator>,
     }
 
     impl UdpIo {
+        fn from_socket(socket: UdpSocket, timeout: Duration) -> Self {
+            Self {
+                socket,
+                timeout,
+                shortened: AtomicBool::new(false),
+                stream: <_>::default(),
+                nodes: <_>::default(),
+                max_lost: 0,
+                payload: None,
+                latency: Mutex::new(None),
+                lost: AtomicU32::new(0),
+                rejected: AtomicU32::new(0),
+                #[cfg(feature = "encryption")]
+                cipher: None,
+                challenge: None,
+                ack: None,
+                #[cfg(feature = "auth")]
+                auth: None,
+            }
+        }
+        /// acknowledges accepted beats with the watchdog state, which is received by the heart
+        /// with [`Heart::ack`]. The acknowledgements are sent to the address of the beat and are
+        /// not authenticated
+        pub fn with_ack(mut self) -> Self {
+            self.ack = Some(Mutex::new(None));
+            self
+        }
+        /// enables the challenge-response mode (see [`UdpHeart::with_challenge`]): a random nonce
+        /// is sent to the heart every interval (in reply to a beat), the heart must echo it within
+        /// the deadline, otherwise [`Error::Challenge`] is reported. The deadline must exceed the
+        /// beat interval and the period of the heart application loop. Unicast hearts only (the
+        /// challenges are sent to the address of the last beat)
+        pub fn with_challenge(mut self, interval: Duration, deadline: Duration) -> Self {
+            self.challenge = Some(Mutex::new(Challenge {
+                interval,
+                deadline,
+                pending: None,
+                next: None,
+                peer: None,
+            }));
+            self
+        }
+        /// decrypts the payloads with the shared key (see [`UdpHeart::with_encryption`]),
+        /// datagrams with forged, corrupted or replayed payloads are ignored and counted in the
+        /// watchdog statistics as rejected. Every beat must carry a sealed payload (empty ones
+        /// included), so datagrams without it (e.g. legacy beats or beats with the stripped
+        /// ciphertext) are rejected as well
+        #[cfg(feature = "encryption")]
+        pub fn with_encryption(mut self, key: &[u8; 32]) -> Result<Self> {
+            self.cipher = Some(PayloadCipher::new(key)?);
+            Ok(self)
+        }
+        /// accepts authenticated beats only (see [`UdpHeart::with_auth`]), other datagrams are
+        /// ignored and counted in the watchdog statistics as rejected. The session of the heart
+        /// is latched, replayed datagrams of the current or the previous sessions are rejected
+        /// as well. The session is not known when the watchdog is started, so use the
+        /// challenge-response mode (see [`UdpIo::with_challenge`]) to reject replayed streams
+        /// of a dead heart right after the start
+        #[cfg(feature = "auth")]
+        pub fn with_auth(mut self, key: &[u8]) -> Self {
+            self.auth = Some(Authenticator::new(key));
+            self
+        }
+        /// sets the payload channel (see [`crate::payload::channel`]), the payloads of accepted
+        /// beats are decoded and sent to the channel, beats with invalid payloads are still
+        /// accepted
+        pub fn with_payload_tx<T: Decode + Send + 'static>(mut self, tx: PayloadSender<T>) -> Self {
+            self.payload = Some(Box::new(move |edge, data| {
+                if data.is_empty() {
+                    return;
+                }
+                if let Ok(data) = T::decode(data) {
+                    // never blocks as the latest payload replaces the previous one
+                    let _ = tx.try_send(Payload { edge, data });
+                }
+            }));
+            self
+        }
+        /// sets the number of consecutive lost datagrams (v2 protocol only) which are tolerated,
+        /// the default is 0
+        pub fn with_max_lost(mut self, max_lost: u16) -> Self {
+            self.max_lost = max_lost;
+            self
+        }
         /// creates a new UDP watchdog I/O
         pub fn create<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<Self>
         where
@@ -191,18 +1306,279 @@ pub mod udp {
         {
             let socket = UdpSocket::bind(addr)?;
             socket.set_read_timeout(Some(timeout))?;
-            Ok(Self { socket })
+            Ok(Self::from_socket(socket, timeout))
+        }
+        /// creates a new UDP watchdog I/O from a bound socket, e.g. inherited from the service
+        /// manager (see [`super::systemd::listen_fds`])
+        pub fn from_fd(fd: OwnedFd, timeout: Duration) -> Result<Self> {
+            super::systemd::check_socket(&fd, libc::SOCK_DGRAM)?;
+            let socket = UdpSocket::from(fd);
+            socket.set_nonblocking(false)?;
+            socket.set_read_timeout(Some(timeout))?;
+            Ok(Self::from_socket(socket, timeout))
+        }
+        /// creates a new UDP watchdog I/O, receiving broadcast (as well as unicast) beats on all
+        /// interfaces. Other watchdogs on the same host can bind the port as well to receive the
+        /// broadcast beats
+        pub fn create_broadcast(port: u16, timeout: Duration) -> Result<Self> {
+            let socket = bind_reuse(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))?;
+            socket.set_read_timeout(Some(timeout))?;
+            Ok(Self::from_socket(socket, timeout))
+        }
+        /// creates a new UDP watchdog I/O, joining an IPv4 multicast group on the given interface
+        /// (use [`Ipv4Addr::UNSPECIFIED`] for the default one). Other watchdogs on the same host
+        /// can join the group with the same port as well
+        pub fn create_multicast(
+            group: SocketAddrV4,
+            interface: Ipv4Addr,
+            timeout: Duration,
+        ) -> Result<Self> {
+            if !group.ip().is_multicast() {
+                return Err(Error::failed("not a multicast address"));
+            }
+            // binding to the group address filters out datagrams sent to other groups
+            let socket = bind_reuse(group)?;
+            socket.join_multicast_v4(group.ip(), &interface)?;
+            socket.set_read_timeout(Some(timeout))?;
+            Ok(Self::from_socket(socket, timeout))
+        }
+    }
+
+    impl UdpIo {
+        /// checks the authentication tag, returns the datagram size without the session and the
+        /// tag, the session ID and the counter or `None` if the datagram is rejected
+        #[cfg(feature = "auth")]
+        fn authenticated(&self, datagram: &[u8]) -> Option<(usize, Option<(u64, u64)>)> {
+            let Some(ref auth) = self.auth else {
+                return Some((datagram.len(), None));
+            };
+            let signed = datagram.len().checked_sub(TAG_SIZE)?;
+            let len = signed.checked_sub(SESSION_SIZE)?;
+            if len < HEADER_SIZE {
+                return None;
+            }
+            let (data, tag) = datagram.split_at(signed);
+            if !auth.verify(data, tag) {
+                return None;
+            }
+            let field = |n: usize| {
+                let mut field = [0; FIELD_SIZE];
+                let start = len + FIELD_SIZE * n;
+                field.copy_from_slice(&data[start..start + FIELD_SIZE]);
+                u64::from_be_bytes(field)
+            };
+            Some((len, Some((field(0), field(1)))))
+        }
+        /// ignored datagrams (e.g. forged ones) must not postpone the timeout, so the read timeout
+        /// is shortened to the remaining time
+        fn shorten_timeout(&self, start: Instant) -> Result<()> {
+            let remaining = self.timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            self.shortened.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        /// records the address of an accepted beat, checks its challenge answer and sends a new
+        /// challenge if due
+        fn challenged(&self, answer: Option<u64>, peer: SocketAddr) -> Result<()> {
+            if let Some(ref ack) = self.ack {
+                *ack.lock() = Some(peer);
+            }
+            let Some(ref challenge) = self.challenge else {
+                return Ok(());
+            };
+            let mut challenge = challenge.lock();
+            let now = Instant::now();
+            challenge.peer = Some(peer);
+            if let Some((nonce, sent)) = challenge.pending {
+                if answer == Some(nonce) {
+                    challenge.pending = None;
+                    challenge.next = Some(now + challenge.interval);
+                } else if now.duration_since(sent) > challenge.deadline {
+                    challenge.reset();
+                    return Err(Error::Challenge);
+                } else {
+                    return Ok(());
+                }
+            }
+            if challenge.next.map_or(true, |next| next <= now) {
+                let nonce = random_u64()?.max(1);
+                let mut buf = [CHALLENGE_MARKER; CHALLENGE_SIZE];
+                buf[1..].copy_from_slice(&nonce.to_be_bytes());
+                self.socket.send_to(&buf, peer)?;
+                challenge.pending = Some((nonce, now));
+            }
+            Ok(())
+        }
+        /// checks the session and the sequence number, returns `None` for duplicate, reordered
+        /// and replayed datagrams
+        fn sequenced(
+            &self,
+            stream: &mut Stream,
+            edge: Edge,
+            seq: u16,
+            session: Option<(u64, u64)>,
+        ) -> Result<Option<Edge>> {
+            #[cfg(feature = "auth")]
+            let restart = match session {
+                Some((id, counter)) => {
+                    let Some(restart) = stream.session.check(id, counter) else {
+                        self.rejected.fetch_add(1, Ordering::Relaxed);
+                        return Ok(None);
+                    };
+                    Some(restart)
+                }
+                None => None,
+            };
+            #[cfg(not(feature = "auth"))]
+            let restart = session.map(|_| false);
+            match stream.sequence.check(edge, seq, self.max_lost, restart) {
+                Sequenced::Beat { edge, lost } => {
+                    self.lost.store(u32::from(lost), Ordering::Relaxed);
+                    Ok(Some(edge))
+                }
+                Sequenced::Ignored => Ok(None),
+                Sequenced::Lost(lost) => {
+                    self.lost.store(u32::from(lost), Ordering::Relaxed);
+                    Err(Error::Lost)
+                }
+            }
+        }
+    }
+
+    impl UdpIo {
+        /// receives the next accepted beat, datagrams without node IDs are ignored in the
+        /// multi-node mode. Per-beat errors (e.g. lost beats) are returned in the inner result
+        fn receive(&self, multi: bool) -> Result<(Option<u16>, Result<Edge>)> {
+            let mut buf = [0; MAX_DATAGRAM_SIZE];
+            self.rejected.store(0, Ordering::Relaxed);
+            if self.shortened.swap(false, Ordering::Relaxed) {
+                self.socket.set_read_timeout(Some(self.timeout))?;
+            }
+            let start = Instant::now();
+            let mut ignored = false;
+            loop {
+                if ignored {
+                    self.shorten_timeout(start)?;
+                }
+                // the next iteration means the datagram has been ignored
+                ignored = true;
+                let (len, peer) = self.socket.recv_from(&mut buf)?;
+                if len == 0 {
+                    continue;
+                }
+                #[cfg(feature = "auth")]
+                let Some((len, session)) = self.authenticated(&buf[..len]) else {
+                    // forged or corrupted
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                };
+                #[cfg(not(feature = "auth"))]
+                let session = None;
+                #[cfg(feature = "encryption")]
+                if self.cipher.is_some() && len < HEADER_SIZE {
+                    // legacy beats carry no sealed payload
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                let flags = buf[0] & FLAGS;
+                let edge = Edge::from(buf[0] & !FLAGS);
+                if multi && (len < HEADER_SIZE || flags & NODE_FLAG == 0) {
+                    continue;
+                }
+                if len < HEADER_SIZE {
+                    self.lost.store(0, Ordering::Relaxed);
+                    return Ok((None, self.challenged(None, peer).map(|()| edge)));
+                }
+                let offset = header_size(flags);
+                if len < offset {
+                    // a truncated datagram
+                    continue;
+                }
+                let (header, data) = buf[..len].split_at_mut(offset);
+                #[cfg(feature = "encryption")]
+                let data = match self.cipher {
+                    Some(ref cipher) => {
+                        let Some(data) = cipher.open(header, data) else {
+                            // forged, corrupted or stripped
+                            self.rejected.fetch_add(1, Ordering::Relaxed);
+                            continue;
+                        };
+                        data
+                    }
+                    _ => data,
+                };
+                let seq = u16::from_be_bytes([header[1], header[2]]);
+                let node = (flags & NODE_FLAG != 0)
+                    .then(|| u16::from_be_bytes([header[offset - 2], header[offset - 1]]));
+                let sequenced = match node {
+                    Some(node) if multi => {
+                        let mut nodes = self.nodes.lock();
+                        self.sequenced(nodes.entry(node).or_default(), edge, seq, session)
+                    }
+                    _ => self.sequenced(&mut self.stream.lock(), edge, seq, session),
+                };
+                let edge = match sequenced {
+                    Ok(Some(edge)) => edge,
+                    // a duplicate or a reordered datagram
+                    Ok(None) => continue,
+                    Err(e) => return Ok((node, Err(e))),
+                };
+                let timestamped = flags & TIMESTAMP_FLAG != 0;
+                if !multi {
+                    let answer = (flags & ANSWER_FLAG != 0)
+                        .then(|| header_field(header, usize::from(timestamped)));
+                    if let Err(e) = self.challenged(answer, peer) {
+                        return Ok((node, Err(e)));
+                    }
+                } else if let Some(ref ack) = self.ack {
+                    *ack.lock() = Some(peer);
+                }
+                *self.latency.lock() = timestamped.then(|| {
+                    let nanos = header_field(header, 0);
+                    unix_time().saturating_sub(Duration::from_nanos(nanos))
+                });
+                if let Some(ref sink) = self.payload {
+                    sink(edge, data);
+                }
+                return Ok((node, Ok(edge)));
+            }
         }
     }
 
     impl WatchdogIo for UdpIo {
         fn get(&self, _expected: Edge) -> Result<Edge> {
-            let mut buf = [0];
-            while self.socket.recv(&mut buf)? == 0 {}
-            Ok(Edge::from(buf[0]))
+            self.receive(false)?.1
+        }
+
+        fn latency(&self) -> Option<Duration> {
+            *self.latency.lock()
+        }
+
+        fn lost(&self) -> u32 {
+            self.lost.load(Ordering::Relaxed)
+        }
+
+        fn rejected(&self) -> u32 {
+            self.rejected.load(Ordering::Relaxed)
+        }
+
+        fn ack(&self, state: State) -> Result<()> {
+            if let Some(peer) = self.ack.as_ref().and_then(|ack| *ack.lock()) {
+                self.socket.send_to(&[ACK_MARKER, state as u8], peer)?;
+            }
+            Ok(())
         }
 
         fn clear(&self) -> Result<()> {
+            self.stream.lock().sequence = Sequence::default();
+            *self.latency.lock() = None;
+            if let Some(ref challenge) = self.challenge {
+                challenge.lock().reset();
+            }
+            self.lost.store(0, Ordering::Relaxed);
             self.socket.set_nonblocking(true)?;
             while self.socket.recv(&mut [0]).is_ok() {
                 // should never happen, but just in case
@@ -212,4 +1588,444 @@ pub mod udp {
             Ok(())
         }
     }
+
+    impl MultiWatchdogIo for UdpIo {
+        fn get(&self) -> Result<(u16, Result<Edge>)> {
+            let (node, res) = self.receive(true)?;
+            // node IDs are always set in the multi-node mode
+            Ok((node.unwrap_or_default(), res))
+        }
+
+        fn latency(&self) -> Option<Duration> {
+            *self.latency.lock()
+        }
+
+        fn lost(&self) -> u32 {
+            self.lost.load(Ordering::Relaxed)
+        }
+
+        fn rejected(&self) -> u32 {
+            self.rejected.load(Ordering::Relaxed)
+        }
+
+        fn ack(&self, state: State) -> Result<()> {
+            WatchdogIo::ack(self, state)
+        }
+
+        fn clear(&self, node: u16) -> Result<()> {
+            // the socket buffer contains the beats of other nodes, so it is not cleared
+            if let Some(stream) = self.nodes.lock().get_mut(&node) {
+                stream.sequence = Sequence::default();
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(all(test, feature = "auth"))]
+    mod tests {
+        use super::*;
+
+        const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+        const TIMEOUT: Duration = Duration::from_millis(50);
+
+        /// A watchdog I/O and a socket which sends datagrams to it
+        fn watchdog() -> (UdpIo, UdpSocket) {
+            let io = UdpIo::create("127.0.0.1:0", TIMEOUT)
+                .unwrap()
+                .with_auth(KEY);
+            let tx = UdpSocket::bind("127.0.0.1:0").unwrap();
+            tx.connect(io.socket.local_addr().unwrap()).unwrap();
+            (io, tx)
+        }
+
+        /// Records n beats of a heart
+        fn record(heart: &UdpHeart, n: usize) -> Vec<Vec<u8>> {
+            let rx = UdpSocket::bind("127.0.0.1:0").unwrap();
+            heart.socket.connect(rx.local_addr().unwrap()).unwrap();
+            let mut buf = [0; MAX_DATAGRAM_SIZE];
+            (0..n)
+                .map(|_| {
+                    heart.beat().unwrap();
+                    let len = rx.recv(&mut buf).unwrap();
+                    buf[..len].to_vec()
+                })
+                .collect()
+        }
+
+        fn heart(key: &[u8]) -> UdpHeart {
+            UdpHeart::create("127.0.0.1:9").unwrap().with_auth(key)
+        }
+
+        /// Delivers a datagram, returns the edge or the error and the number of rejected ones
+        fn deliver(io: &UdpIo, tx: &UdpSocket, datagram: &[u8]) -> (Result<Edge>, u32) {
+            tx.send(datagram).unwrap();
+            let res = WatchdogIo::get(io, Edge::Rising);
+            (res, WatchdogIo::rejected(io))
+        }
+
+        fn timed_out(res: &(Result<Edge>, u32)) -> bool {
+            matches!(res, (Err(Error::Timeout), 1))
+        }
+
+        #[test]
+        fn test_authenticated() {
+            let (io, tx) = watchdog();
+            let beats = record(&heart(KEY), 2);
+            assert!(matches!(
+                deliver(&io, &tx, &beats[0]),
+                (Ok(Edge::Rising), 0)
+            ));
+            assert!(matches!(
+                deliver(&io, &tx, &beats[1]),
+                (Ok(Edge::Falling), 0)
+            ));
+        }
+
+        #[test]
+        fn test_forged() {
+            let (io, tx) = watchdog();
+            let beats = record(&heart(b"another key"), 1);
+            assert!(timed_out(&deliver(&io, &tx, &beats[0])));
+            // unauthenticated beats
+            assert!(timed_out(&deliver(&io, &tx, b"+")));
+            assert!(timed_out(&deliver(&io, &tx, &[b'+', 0, 0])));
+        }
+
+        #[test]
+        fn test_corrupted() {
+            let (io, tx) = watchdog();
+            let beats = record(&heart(KEY), 1);
+            for i in 0..beats[0].len() {
+                let mut datagram = beats[0].clone();
+                datagram[i] ^= 1;
+                assert!(timed_out(&deliver(&io, &tx, &datagram)), "byte {}", i);
+            }
+            assert!(timed_out(&deliver(&io, &tx, &beats[0][1..])));
+            assert!(matches!(
+                deliver(&io, &tx, &beats[0]),
+                (Ok(Edge::Rising), 0)
+            ));
+        }
+
+        #[test]
+        fn test_replayed() {
+            let (io, tx) = watchdog();
+            let beats = record(&heart(KEY), 4);
+            for beat in &beats[..3] {
+                assert!(deliver(&io, &tx, beat).0.is_ok());
+            }
+            assert!(timed_out(&deliver(&io, &tx, &beats[1])));
+            // the session is kept after the I/O is cleared (e.g. after a fault)
+            WatchdogIo::clear(&io).unwrap();
+            for beat in &beats[..3] {
+                assert!(timed_out(&deliver(&io, &tx, beat)));
+            }
+            assert!(matches!(
+                deliver(&io, &tx, &beats[3]),
+                (Ok(Edge::Falling), 0)
+            ));
+        }
+
+        #[test]
+        fn test_restart() {
+            let (io, tx) = watchdog();
+            let old = record(&heart(KEY), 4);
+            for beat in &old[..2] {
+                assert!(deliver(&io, &tx, beat).0.is_ok());
+            }
+            // a new heart run is accepted from any sequence number
+            let new = record(&heart(KEY), 3);
+            assert!(matches!(deliver(&io, &tx, &new[1]), (Ok(Edge::Falling), 0)));
+            assert!(matches!(deliver(&io, &tx, &new[2]), (Ok(Edge::Rising), 0)));
+            // the previous run can not be resumed, also from its start
+            assert!(timed_out(&deliver(&io, &tx, &old[2])));
+            assert!(timed_out(&deliver(&io, &tx, &old[0])));
+            assert!(timed_out(&deliver(&io, &tx, &new[0])));
+        }
+
+        #[test]
+        fn test_session() {
+            let mut session = Session::default();
+            assert_eq!(session.check(1, 5), Some(true));
+            assert_eq!(session.check(1, 5), None);
+            assert_eq!(session.check(1, 4), None);
+            assert_eq!(session.check(1, 6), Some(false));
+            for id in 2..=u64::try_from(MAX_RETIRED_SESSIONS).unwrap() + 2 {
+                assert_eq!(session.check(id, 0), Some(true));
+                assert_eq!(session.check(id - 1, 100), None);
+            }
+            // the oldest sessions are forgotten
+            assert_eq!(session.check(1, 100), Some(true));
+        }
+    }
+
+    #[cfg(all(test, feature = "encryption"))]
+    mod encryption_tests {
+        use super::*;
+
+        const KEY: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
+
+        #[test]
+        fn test_sealed() {
+            let io = UdpIo::create("127.0.0.1:0", Duration::from_millis(50))
+                .unwrap()
+                .with_encryption(KEY)
+                .unwrap();
+            let heart = UdpHeart::create(io.socket.local_addr().unwrap())
+                .unwrap()
+                .with_encryption(KEY)
+                .unwrap();
+            let tx = UdpSocket::bind("127.0.0.1:0").unwrap();
+            tx.connect(io.socket.local_addr().unwrap()).unwrap();
+            // a legacy beat and a v2 beat without the sealed payload
+            for datagram in [&b"+"[..], &[b'+', 0, 0]] {
+                tx.send(datagram).unwrap();
+                assert!(matches!(
+                    WatchdogIo::get(&io, Edge::Rising),
+                    Err(Error::Timeout)
+                ));
+                assert_eq!(WatchdogIo::rejected(&io), 1);
+            }
+            heart.beat().unwrap();
+            assert!(matches!(
+                WatchdogIo::get(&io, Edge::Rising),
+                Ok(Edge::Rising)
+            ));
+            assert_eq!(WatchdogIo::rejected(&io), 0);
+        }
+    }
+}
+
+/// TCP communication
+#[cfg(feature = "std")]
+pub mod tcp;
+
+/// TLS (over TCP) communication with client certificate verification
+#[cfg(feature = "tls")]
+pub mod tls;
+
+/// DTLS (over UDP) communication with mutual certificate verification
+#[cfg(feature = "dtls")]
+pub mod dtls;
+
+/// QUIC (unreliable datagrams) communication
+#[cfg(feature = "quic")]
+pub mod quic;
+
+/// gRPC (client-streaming call) communication
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+/// Unix socket communication
+#[cfg(feature = "std")]
+pub mod unix;
+
+/// systemd integration (notifications, socket activation)
+#[cfg(feature = "std")]
+pub mod systemd;
+
+/// Serial port communication
+#[cfg(feature = "serial")]
+pub mod serial;
+
+/// Shared memory communication
+#[cfg(feature = "std")]
+pub mod shm;
+
+/// POSIX message queue communication
+#[cfg(feature = "std")]
+pub mod mqueue;
+
+/// eventfd communication
+#[cfg(feature = "std")]
+pub mod eventfd;
+
+/// In-process loopback communication
+#[cfg(feature = "std")]
+pub mod loopback;
+
+/// In-process atomic flag/counter communication
+#[cfg(feature = "std")]
+pub mod atomic;
+
+/// Scriptable mock I/O and test helpers
+#[cfg(feature = "std")]
+pub mod mock;
+
+/// MQTT communication
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+
+/// Redis pub/sub communication
+#[cfg(feature = "redis")]
+pub mod redis;
+
+/// HTTP communication
+#[cfg(feature = "std")]
+pub mod http;
+
+/// CoAP communication
+#[cfg(feature = "std")]
+pub mod coap;
+
+/// zenoh communication
+#[cfg(feature = "zenoh")]
+pub mod zenoh;
+
+/// D-Bus communication
+#[cfg(feature = "dbus")]
+pub mod dbus;
+
+/// Raw Ethernet communication
+#[cfg(feature = "std")]
+pub mod ethernet;
+
+/// Modbus communication
+#[cfg(feature = "modbus")]
+pub mod modbus;
+
+/// I2C communication (Linux i2c-dev)
+#[cfg(feature = "std")]
+pub mod i2c;
+
+/// PWM (sysfs) hardware heartbeat output
+#[cfg(feature = "std")]
+pub mod pwm;
+
+/// Memory-mapped file communication
+#[cfg(feature = "std")]
+pub mod mmap;
+
+/// File (inotify) communication
+#[cfg(feature = "std")]
+pub mod file;
+
+/// Process liveness (pidfd) supervision
+#[cfg(feature = "std")]
+pub mod process;
+
+/// vsock (VM guest/host) communication
+#[cfg(feature = "std")]
+pub mod vsock;
+
+/// Netlink communication
+#[cfg(feature = "std")]
+pub mod netlink;
+
+/// Bluetooth LE communication (BlueZ)
+#[cfg(feature = "ble")]
+pub mod ble;
+
+/// SCTP communication
+#[cfg(feature = "std")]
+pub mod sctp;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn beat(edge: Edge, lost: u16) -> Sequenced {
+        Sequenced::Beat { edge, lost }
+    }
+
+    #[test]
+    fn test_sequence_loss() {
+        let mut seq = Sequence::default();
+        assert_eq!(seq.check(Edge::Rising, 10, 1, None), beat(Edge::Rising, 0));
+        // an odd tolerated loss inverts the following edges
+        assert_eq!(seq.check(Edge::Rising, 12, 1, None), beat(Edge::Falling, 1));
+        assert_eq!(seq.check(Edge::Falling, 13, 1, None), beat(Edge::Rising, 0));
+        assert_eq!(seq.apply(Edge::Rising), Edge::Falling);
+        assert_eq!(seq.check(Edge::Falling, 16, 1, None), Sequenced::Lost(2));
+        // the sequence continues after the loss
+        assert_eq!(seq.check(Edge::Rising, 17, 1, None), beat(Edge::Falling, 0));
+    }
+
+    #[test]
+    fn test_sequence_reorder() {
+        let mut seq = Sequence::default();
+        for (n, edge) in [(1, Edge::Rising), (2, Edge::Falling)] {
+            assert_eq!(seq.check(edge, n, 0, None), beat(edge, 0));
+        }
+        assert_eq!(seq.check(Edge::Rising, 3, 0, None), beat(Edge::Rising, 0));
+        assert_eq!(seq.check(Edge::Falling, 2, 0, None), Sequenced::Ignored);
+        assert_eq!(
+            seq.check(Edge::Falling, 3_u16.wrapping_sub(REORDER_WINDOW), 0, None),
+            Sequenced::Ignored
+        );
+        assert_eq!(seq.check(Edge::Falling, 4, 0, None), beat(Edge::Falling, 0));
+    }
+
+    #[test]
+    fn test_sequence_duplicates() {
+        let mut seq = Sequence::default();
+        assert_eq!(seq.check(Edge::Rising, 5, 0, None), beat(Edge::Rising, 0));
+        assert_eq!(seq.check(Edge::Rising, 5, 0, None), Sequenced::Ignored);
+        assert_eq!(seq.check(Edge::Falling, 6, 0, None), beat(Edge::Falling, 0));
+        assert_eq!(seq.check(Edge::Falling, 6, 0, None), Sequenced::Ignored);
+    }
+
+    #[test]
+    fn test_sequence_wrap() {
+        let mut seq = Sequence::default();
+        assert_eq!(
+            seq.check(Edge::Rising, 65534, 1, None),
+            beat(Edge::Rising, 0)
+        );
+        // the invert state is kept over the counter wrap
+        assert_eq!(seq.check(Edge::Rising, 0, 1, None), beat(Edge::Falling, 1));
+        assert_eq!(seq.check(Edge::Falling, 1, 1, None), beat(Edge::Rising, 0));
+        let mut seq = Sequence::default();
+        assert_eq!(
+            seq.check(Edge::Rising, 65535, 0, None),
+            beat(Edge::Rising, 0)
+        );
+        assert_eq!(seq.check(Edge::Falling, 0, 0, None), beat(Edge::Falling, 0));
+    }
+
+    #[test]
+    fn test_sequence_restart() {
+        let mut seq = Sequence::default();
+        for (n, edge) in [(0, Edge::Rising), (1, Edge::Falling), (2, Edge::Rising)] {
+            assert_eq!(seq.check(edge, n, 1, None), beat(edge, 0));
+        }
+        assert_eq!(seq.check(Edge::Rising, 4, 1, None), beat(Edge::Falling, 1));
+        // the heart restarts before its sequence number leaves the reorder window
+        assert_eq!(seq.check(Edge::Rising, 0, 1, None), beat(Edge::Rising, 0));
+        assert_eq!(seq.check(Edge::Falling, 1, 1, None), beat(Edge::Falling, 0));
+        assert_eq!(seq.check(Edge::Rising, 2, 1, None), beat(Edge::Rising, 0));
+        // restarts detected by the caller
+        assert_eq!(
+            seq.check(Edge::Rising, 7, 1, Some(false)),
+            Sequenced::Lost(4)
+        );
+        assert_eq!(
+            seq.check(Edge::Rising, 3, 1, Some(true)),
+            beat(Edge::Rising, 0)
+        );
+        assert_eq!(
+            seq.check(Edge::Falling, 0, 1, Some(false)),
+            Sequenced::Ignored
+        );
+    }
+
+    #[test]
+    fn test_poll_until() {
+        let (timeout, pull_interval) = (Duration::from_millis(50), Duration::from_millis(5));
+        let mut polls = 0;
+        let res = poll_until(timeout, pull_interval, |remaining| {
+            assert!(remaining > Duration::ZERO && remaining <= timeout);
+            polls += 1;
+            (polls == 3).then_some(polls)
+        });
+        assert_eq!(res.unwrap(), 3);
+        let start = std::time::Instant::now();
+        let res = poll_until(timeout, pull_interval, |remaining| {
+            // a blocking poll bounded by the time remaining
+            std::thread::sleep(remaining);
+            None::<()>
+        });
+        assert!(matches!(res, Err(crate::Error::Timeout)));
+        assert!(start.elapsed() < timeout * 2);
+    }
 }
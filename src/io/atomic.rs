@@ -0,0 +1,151 @@
+use crate::{Edge, Heart, Result};
+use core::time::Duration;
+use std::sync::Arc;
+
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::{poll_until, WatchdogIo};
+
+/// Creates a connected in-process flag client and watchdog I/O pair. The watchdog polls the flag
+/// with the pull interval
+pub fn flag_pair(pull_interval: Duration, timeout: Duration) -> (FlagHeart, FlagIo) {
+    let flag = Arc::new(AtomicBool::new(false));
+    (
+        FlagHeart { flag: flag.clone() },
+        FlagIo {
+            flag,
+            timeout,
+            pull_interval,
+        },
+    )
+}
+
+/// Creates a connected in-process counter client and watchdog I/O pair. The watchdog polls the
+/// counter with the pull interval
+pub fn counter_pair(pull_interval: Duration, timeout: Duration) -> (CounterHeart, CounterIo) {
+    let counter = Arc::new(AtomicU32::new(0));
+    (
+        CounterHeart {
+            counter: counter.clone(),
+        },
+        CounterIo {
+            counter,
+            last: AtomicU32::new(0),
+            timeout,
+            pull_interval,
+        },
+    )
+}
+
+/// Atomic flag client
+///
+/// Each beat inverts the shared flag, the flag value is the edge. A beat is a single atomic
+/// operation, so the client can be used in real-time threads.
+#[allow(clippy::module_name_repetitions)]
+pub struct FlagHeart {
+    flag: Arc<AtomicBool>,
+}
+
+impl Heart for FlagHeart {
+    fn beat(&self) -> Result<()> {
+        self.flag.fetch_xor(true, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Atomic flag watchdog I/O
+#[allow(clippy::module_name_repetitions)]
+pub struct FlagIo {
+    flag: Arc<AtomicBool>,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl WatchdogIo for FlagIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |_| {
+            let edge = Edge::from(self.flag.load(Ordering::Acquire));
+            (edge == expected).then_some(edge)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Atomic counter client
+///
+/// Each beat increments the shared counter. A beat is a single atomic operation, so the client can
+/// be used in real-time threads.
+#[allow(clippy::module_name_repetitions)]
+pub struct CounterHeart {
+    counter: Arc<AtomicU32>,
+}
+
+impl Heart for CounterHeart {
+    fn beat(&self) -> Result<()> {
+        self.counter.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Atomic counter watchdog I/O
+///
+/// As the counter carries no edge information, every counter change is reported as the expected
+/// edge (several beats between polls are counted as one), so only timeouts and window violations
+/// are detected.
+#[allow(clippy::module_name_repetitions)]
+pub struct CounterIo {
+    counter: Arc<AtomicU32>,
+    last: AtomicU32,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl WatchdogIo for CounterIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |_| {
+            let value = self.counter.load(Ordering::Acquire);
+            (self.last.swap(value, Ordering::Relaxed) != value).then_some(expected)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.last
+            .store(self.counter.load(Ordering::Acquire), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const PULL_INTERVAL: Duration = Duration::from_millis(1);
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_flag() {
+        let (heart, io) = flag_pair(PULL_INTERVAL, TIMEOUT);
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+        // the flag has not been inverted
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn test_counter() {
+        let (heart, io) = counter_pair(PULL_INTERVAL, TIMEOUT);
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert!(matches!(io.get(Edge::Falling), Err(Error::Timeout)));
+        // beats before the I/O is cleared are not reported
+        heart.beat().unwrap();
+        io.clear().unwrap();
+        assert!(matches!(io.get(Edge::Falling), Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::{collections::HashMap, thread};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::channel;
+use zbus::{
+    blocking::{Connection, MessageIterator},
+    message::Type,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+    MatchRule,
+};
+
+use super::WatchdogIo;
+
+const BLUEZ: &str = "org.bluez";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const BEAT_QUEUE_SIZE: usize = 16;
+
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+/// A GATT characteristic of a remote device, managed by BlueZ
+struct Characteristic {
+    device: String,
+    path: OwnedObjectPath,
+}
+
+impl Characteristic {
+    /// finds the characteristic by the device address and the characteristic UUID
+    fn find(connection: &Connection, adapter: &str, address: &str, uuid: &str) -> Result<Self> {
+        let device = format!(
+            "/org/bluez/{}/dev_{}",
+            adapter,
+            address.to_uppercase().replace(':', "_")
+        );
+        let objects: ManagedObjects = connection
+            .call_method(
+                Some(BLUEZ),
+                "/",
+                Some("org.freedesktop.DBus.ObjectManager"),
+                "GetManagedObjects",
+                &(),
+            )
+            .and_then(|reply| reply.body().deserialize())
+            .map_err(Error::failed)?;
+        let prefix = format!("{}/", device);
+        for (path, interfaces) in objects {
+            if !path.as_str().starts_with(&prefix) {
+                continue;
+            }
+            let Some(props) = interfaces.get(CHARACTERISTIC_INTERFACE) else {
+                continue;
+            };
+            if props
+                .get("UUID")
+                .and_then(|v| <&str>::try_from(v).ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case(uuid))
+            {
+                return Ok(Self { device, path });
+            }
+        }
+        Err(Error::failed(format!(
+            "GATT characteristic {} not found for {} (is the device connected?)",
+            uuid, address
+        )))
+    }
+    /// asks BlueZ to connect the device, errors are ignored as the result is checked by the next
+    /// operation
+    fn connect_device(&self, connection: &Connection) {
+        let _ = connection.call_method(
+            Some(BLUEZ),
+            self.device.as_str(),
+            Some(DEVICE_INTERFACE),
+            "Connect",
+            &(),
+        );
+    }
+}
+
+/// Bluetooth LE client
+///
+/// Edges are written to a GATT characteristic of a remote device (via BlueZ, the system D-Bus
+/// bus). If a write fails, the device is asked to be re-connected.
+#[allow(clippy::module_name_repetitions)]
+pub struct BleHeart {
+    connection: Connection,
+    characteristic: Characteristic,
+    next: AtomicBool,
+}
+
+impl BleHeart {
+    /// creates a new Bluetooth LE client. The adapter is a BlueZ adapter name (e.g. "hci0"), the
+    /// address is the remote device address, the uuid is the characteristic UUID
+    pub fn create(adapter: &str, address: &str, uuid: &str) -> Result<Self> {
+        let connection = Connection::system().map_err(Error::failed)?;
+        Self::with_connection(connection, adapter, address, uuid)
+    }
+    /// creates a new Bluetooth LE client with an existing D-Bus connection
+    pub fn with_connection(
+        connection: Connection,
+        adapter: &str,
+        address: &str,
+        uuid: &str,
+    ) -> Result<Self> {
+        let characteristic = Characteristic::find(&connection, adapter, address, uuid)?;
+        Ok(Self {
+            connection,
+            characteristic,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for BleHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+        let options: HashMap<&str, Value> = HashMap::from([("type", Value::from("command"))]);
+        let res = self.connection.call_method(
+            Some(BLUEZ),
+            self.characteristic.path.as_str(),
+            Some(CHARACTERISTIC_INTERFACE),
+            "WriteValue",
+            &(vec![edge], options),
+        );
+        if let Err(e) = res {
+            self.characteristic.connect_device(&self.connection);
+            return Err(Error::failed(e));
+        }
+        Ok(())
+    }
+}
+
+/// Bluetooth LE watchdog I/O
+///
+/// Subscribes to notifications of a GATT characteristic of a remote device (via BlueZ, the system
+/// D-Bus bus), the first byte of the value is the edge. The notifications are received by a
+/// background thread.
+#[allow(clippy::module_name_repetitions)]
+pub struct BleIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+}
+
+impl BleIo {
+    /// creates a new Bluetooth LE watchdog I/O, see [`BleHeart::create`] for the arguments
+    pub fn create(adapter: &str, address: &str, uuid: &str, timeout: Duration) -> Result<Self> {
+        let connection = Connection::system().map_err(Error::failed)?;
+        Self::with_connection(&connection, adapter, address, uuid, timeout)
+    }
+    /// creates a new Bluetooth LE watchdog I/O with an existing D-Bus connection
+    pub fn with_connection(
+        connection: &Connection,
+        adapter: &str,
+        address: &str,
+        uuid: &str,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let characteristic = Characteristic::find(connection, adapter, address, uuid)?;
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender(BLUEZ)
+            .and_then(|b| b.interface(PROPERTIES_INTERFACE))
+            .and_then(|b| b.member("PropertiesChanged"))
+            .and_then(|b| b.path(characteristic.path.as_str()))
+            .map_err(Error::failed)?
+            .build()
+            .to_owned();
+        let messages = MessageIterator::for_match_rule(rule, connection, Some(BEAT_QUEUE_SIZE))
+            .map_err(Error::failed)?;
+        connection
+            .call_method(
+                Some(BLUEZ),
+                characteristic.path.as_str(),
+                Some(CHARACTERISTIC_INTERFACE),
+                "StartNotify",
+                &(),
+            )
+            .map_err(Error::failed)?;
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("ble-watchdog".to_owned())
+            .spawn(move || {
+                for message in messages.flatten() {
+                    let Ok((interface, mut changed, _)) =
+                        message
+                            .body()
+                            .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+                    else {
+                        continue;
+                    };
+                    if interface != CHARACTERISTIC_INTERFACE {
+                        continue;
+                    }
+                    let Some(value) = changed
+                        .remove("Value")
+                        .and_then(|v| Vec::<u8>::try_from(v).ok())
+                    else {
+                        continue;
+                    };
+                    if let Some(b) = value.first() {
+                        // if the queue is full, the watchdog is not running
+                        if let Err(rtsc::Error::ChannelClosed) = tx.try_send(Edge::from(*b)) {
+                            break;
+                        }
+                    }
+                }
+            })?;
+        Ok(Self { rx, timeout })
+    }
+}
+
+impl WatchdogIo for BleIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    collections::VecDeque,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+    time::Instant,
+};
+
+use portable_atomic::{AtomicBool, AtomicU16, Ordering};
+use rtsc::pi::Mutex;
+
+use super::WatchdogIo;
+
+/// Default CoAP port
+pub const DEFAULT_PORT: u16 = 5683;
+
+const VERSION: u8 = 1;
+const TYPE_CON: u8 = 0;
+const TYPE_NON: u8 = 1;
+const TYPE_ACK: u8 = 2;
+const CODE_POST: u8 = 0x02;
+const CODE_CHANGED: u8 = 0x44;
+const CODE_NOT_FOUND: u8 = 0x84;
+const OPTION_URI_PATH: u16 = 11;
+const PAYLOAD_MARKER: u8 = 0xff;
+const MAX_MESSAGE_SIZE: usize = 1152;
+// the number of recent confirmable requests, the retransmissions of which are acknowledged again
+// but not processed
+const DEDUP_CACHE_SIZE: usize = 16;
+
+/// Encodes a non-confirmable POST request with Uri-Path options and a payload
+fn encode_request(message_id: u16, path: &str, payload: u8) -> Result<Vec<u8>> {
+    let mut buf = vec![VERSION << 6 | TYPE_NON << 4, CODE_POST];
+    buf.extend(message_id.to_be_bytes());
+    let mut delta = OPTION_URI_PATH;
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let len = segment.len();
+        // option delta is always below 13 (11 for the first segment, 0 for others)
+        #[allow(clippy::cast_possible_truncation)]
+        let delta_nibble = (delta as u8) << 4;
+        if len < 13 {
+            #[allow(clippy::cast_possible_truncation)]
+            buf.push(delta_nibble | len as u8);
+        } else if len < 269 {
+            buf.push(delta_nibble | 13);
+            #[allow(clippy::cast_possible_truncation)]
+            buf.push((len - 13) as u8);
+        } else {
+            return Err(Error::failed("CoAP path segment is too long"));
+        }
+        buf.extend(segment.as_bytes());
+        delta = 0;
+    }
+    buf.push(PAYLOAD_MARKER);
+    buf.push(payload);
+    Ok(buf)
+}
+
+/// Decoded CoAP request
+struct Request<'a> {
+    kind: u8,
+    code: u8,
+    message_id: u16,
+    token: &'a [u8],
+    path: String,
+    payload: &'a [u8],
+}
+
+/// Reads an extended option delta/length value
+fn read_extended(nibble: u8, data: &[u8], pos: &mut usize) -> Option<usize> {
+    match nibble {
+        0..=12 => Some(usize::from(nibble)),
+        13 => {
+            let v = *data.get(*pos)?;
+            *pos += 1;
+            Some(usize::from(v) + 13)
+        }
+        14 => {
+            let v = u16::from_be_bytes([*data.get(*pos)?, *data.get(*pos + 1)?]);
+            *pos += 2;
+            Some(usize::from(v) + 269)
+        }
+        _ => None,
+    }
+}
+
+fn decode_request(data: &[u8]) -> Option<Request> {
+    if data.len() < 4 || data[0] >> 6 != VERSION {
+        return None;
+    }
+    let kind = (data[0] >> 4) & 0b11;
+    let token_len = usize::from(data[0] & 0x0f);
+    let code = data[1];
+    let message_id = u16::from_be_bytes([data[2], data[3]]);
+    let token = data.get(4..4 + token_len)?;
+    let mut pos = 4 + token_len;
+    let mut option = 0;
+    let mut path = String::new();
+    let mut payload: &[u8] = &[];
+    while pos < data.len() {
+        let byte = data[pos];
+        pos += 1;
+        if byte == PAYLOAD_MARKER {
+            payload = &data[pos..];
+            break;
+        }
+        option += read_extended(byte >> 4, data, &mut pos)?;
+        let len = read_extended(byte & 0x0f, data, &mut pos)?;
+        let value = data.get(pos..pos + len)?;
+        pos += len;
+        if option == usize::from(OPTION_URI_PATH) {
+            path.push('/');
+            path.push_str(core::str::from_utf8(value).ok()?);
+        }
+    }
+    Some(Request {
+        kind,
+        code,
+        message_id,
+        token,
+        path,
+        payload,
+    })
+}
+
+/// Encodes a piggybacked response (acknowledgement) to the request
+fn encode_response(request: &Request, code: u8) -> Vec<u8> {
+    #[allow(clippy::cast_possible_truncation)]
+    let mut response = vec![
+        VERSION << 6 | TYPE_ACK << 4 | request.token.len() as u8,
+        code,
+    ];
+    response.extend(request.message_id.to_be_bytes());
+    response.extend(request.token);
+    response
+}
+
+/// Normalizes the path to a leading-slash form without a trailing slash
+fn normalize_path(path: &str) -> String {
+    let mut result = String::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        result.push('/');
+        result.push_str(segment);
+    }
+    result
+}
+
+/// CoAP client
+///
+/// Edges are sent as non-confirmable POST requests with a single-byte payload.
+#[allow(clippy::module_name_repetitions)]
+pub struct CoapHeart {
+    socket: UdpSocket,
+    path: String,
+    message_id: AtomicU16,
+    next: AtomicBool,
+}
+
+impl CoapHeart {
+    /// creates a new CoAP client, the path is the resource URI path (e.g. "/heartbeat")
+    pub fn create<A: ToSocketAddrs>(addr: A, path: &str) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("unable to resolve the address"))?;
+        let socket = match addr {
+            SocketAddr::V4(_) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+            SocketAddr::V6(_) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?,
+        };
+        socket.connect(addr)?;
+        Ok(Self {
+            socket,
+            path: path.to_owned(),
+            message_id: AtomicU16::new(0),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for CoapHeart {
+    fn beat(&self) -> Result<()> {
+        let request = encode_request(
+            self.message_id.fetch_add(1, Ordering::Relaxed),
+            &self.path,
+            Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8,
+        )?;
+        self.socket.send(&request)?;
+        Ok(())
+    }
+}
+
+/// CoAP watchdog I/O
+///
+/// Accepts POST requests to the configured resource path, the first payload byte is the edge.
+/// Confirmable requests are acknowledged, so any CoAP client can be used as the heart. The
+/// retransmissions of the recent confirmable requests (the same peer and message ID) are
+/// acknowledged again but not converted to edges.
+#[allow(clippy::module_name_repetitions)]
+pub struct CoapIo {
+    socket: UdpSocket,
+    path: String,
+    timeout: Duration,
+    acknowledged: Mutex<VecDeque<(SocketAddr, u16, Vec<u8>)>>,
+}
+
+impl CoapIo {
+    /// creates a new CoAP watchdog I/O
+    pub fn create<A: ToSocketAddrs>(addr: A, path: &str, timeout: Duration) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let socket = UdpSocket::bind(addr)?;
+        Ok(Self {
+            socket,
+            path: normalize_path(path),
+            timeout,
+            acknowledged: <_>::default(),
+        })
+    }
+}
+
+impl WatchdogIo for CoapIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut buf = [0; MAX_MESSAGE_SIZE];
+        let start = Instant::now();
+        loop {
+            // ignored messages must not postpone the timeout, so each read waits for the remaining
+            // time only
+            let remaining = self.timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            let (len, peer) = self.socket.recv_from(&mut buf)?;
+            let Some(request) = decode_request(&buf[..len]) else {
+                continue;
+            };
+            if request.code != CODE_POST {
+                continue;
+            }
+            let found = request.path == self.path;
+            if request.kind == TYPE_CON {
+                let mut acknowledged = self.acknowledged.lock();
+                if let Some((_, _, response)) = acknowledged
+                    .iter()
+                    .find(|(p, id, _)| *p == peer && *id == request.message_id)
+                {
+                    // the acknowledgement has been lost, the request has already been processed
+                    self.socket.send_to(response, peer)?;
+                    continue;
+                }
+                // piggybacked response
+                let response =
+                    encode_response(&request, if found { CODE_CHANGED } else { CODE_NOT_FOUND });
+                self.socket.send_to(&response, peer)?;
+                if acknowledged.len() == DEDUP_CACHE_SIZE {
+                    acknowledged.pop_front();
+                }
+                acknowledged.push_back((peer, request.message_id, response));
+            }
+            if found {
+                if let Some(b) = request.payload.first() {
+                    return Ok(Edge::from(*b));
+                }
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.socket.set_nonblocking(true)?;
+        while self.socket.recv(&mut [0]).is_ok() {
+            thread::yield_now();
+        }
+        self.socket.set_nonblocking(false)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "/heart/beat";
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_encode_decode() {
+        let data = encode_request(0x1234, PATH, b'+').unwrap();
+        let request = decode_request(&data).unwrap();
+        assert_eq!(request.kind, TYPE_NON);
+        assert_eq!(request.code, CODE_POST);
+        assert_eq!(request.message_id, 0x1234);
+        assert!(request.token.is_empty());
+        assert_eq!(request.path, PATH);
+        assert_eq!(request.payload, b"+");
+        // extended option lengths
+        for len in [12, 13, 268] {
+            let path = format!("/{}/x", "a".repeat(len));
+            let data = encode_request(1, &path, b'.').unwrap();
+            let request = decode_request(&data).unwrap();
+            assert_eq!(request.path, path);
+            assert_eq!(request.payload, b".");
+        }
+        assert!(encode_request(1, &"a".repeat(269), b'.').is_err());
+        assert_eq!(
+            decode_request(&encode_request(1, "//a//b/", b'.').unwrap())
+                .unwrap()
+                .path,
+            normalize_path("a/b")
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode_request(&[]).is_none());
+        assert!(decode_request(&[VERSION << 6, CODE_POST, 0]).is_none());
+        // wrong version
+        assert!(decode_request(&[2 << 6, CODE_POST, 0, 0]).is_none());
+        // truncated token
+        assert!(decode_request(&[VERSION << 6 | 4, CODE_POST, 0, 0, 1]).is_none());
+        // truncated option value
+        assert!(decode_request(&[VERSION << 6, CODE_POST, 0, 0, 0xb5, b'a']).is_none());
+        // reserved option nibble
+        assert!(decode_request(&[VERSION << 6, CODE_POST, 0, 0, 0xf0]).is_none());
+    }
+
+    #[test]
+    fn test_response() {
+        let data = [
+            VERSION << 6 | TYPE_CON << 4 | 2,
+            CODE_POST,
+            0,
+            7,
+            0xab,
+            0xcd,
+        ];
+        let request = decode_request(&data).unwrap();
+        assert_eq!(request.token, [0xab, 0xcd]);
+        assert_eq!(
+            encode_response(&request, CODE_CHANGED),
+            [
+                VERSION << 6 | TYPE_ACK << 4 | 2,
+                CODE_CHANGED,
+                0,
+                7,
+                0xab,
+                0xcd
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let io = CoapIo::create("127.0.0.1:0", PATH, TIMEOUT).unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.set_read_timeout(Some(TIMEOUT)).unwrap();
+        client.connect(io.socket.local_addr().unwrap()).unwrap();
+        let mut request = encode_request(7, PATH, b'+').unwrap();
+        request[0] = VERSION << 6 | TYPE_CON << 4;
+        client.send(&request).unwrap();
+        // retransmission
+        client.send(&request).unwrap();
+        client
+            .send(&encode_request(8, PATH, b'.').unwrap())
+            .unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+        // both are acknowledged
+        let mut buf = [0; MAX_MESSAGE_SIZE];
+        for _ in 0..2 {
+            let len = client.recv(&mut buf).unwrap();
+            assert_eq!(
+                buf[..len],
+                [VERSION << 6 | TYPE_ACK << 4, CODE_CHANGED, 0, 7]
+            );
+        }
+    }
+
+    #[test]
+    fn test_ipv6() {
+        // IPv6 may be not available
+        let Ok(io) = CoapIo::create("[::1]:0", PATH, TIMEOUT) else {
+            return;
+        };
+        let heart = CoapHeart::create(io.socket.local_addr().unwrap(), PATH).unwrap();
+        heart.beat().unwrap();
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+    }
+}
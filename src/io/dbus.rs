@@ -0,0 +1,132 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::thread;
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::channel;
+use zbus::{
+    blocking::{Connection, MessageIterator},
+    message::Type,
+    MatchRule,
+};
+
+use super::WatchdogIo;
+
+/// Heartbeat D-Bus interface
+pub const INTERFACE: &str = "org.roboplc.Heartbeat";
+/// Heartbeat signal name, the signal has a single byte argument (edge)
+pub const SIGNAL: &str = "Beat";
+
+const BEAT_QUEUE_SIZE: usize = 16;
+
+/// D-Bus message bus
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Bus {
+    /// Session (user) bus
+    Session,
+    /// System bus
+    System,
+}
+
+impl Bus {
+    fn connect(self) -> Result<Connection> {
+        match self {
+            Bus::Session => Connection::session(),
+            Bus::System => Connection::system(),
+        }
+        .map_err(Error::failed)
+    }
+}
+
+/// D-Bus client
+///
+/// Edges are emitted as [`SIGNAL`] signals of [`INTERFACE`] on the given object path.
+#[allow(clippy::module_name_repetitions)]
+pub struct DbusHeart {
+    connection: Connection,
+    path: String,
+    next: AtomicBool,
+}
+
+impl DbusHeart {
+    /// creates a new D-Bus client, the path is the object path (e.g. "/org/roboplc/Heartbeat")
+    pub fn create(bus: Bus, path: &str) -> Result<Self> {
+        Self::with_connection(bus.connect()?, path)
+    }
+    /// creates a new D-Bus client with an existing connection
+    pub fn with_connection(connection: Connection, path: &str) -> Result<Self> {
+        Ok(Self {
+            connection,
+            path: path.to_owned(),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for DbusHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+        self.connection
+            .emit_signal(None::<&str>, self.path.as_str(), INTERFACE, SIGNAL, &edge)
+            .map_err(Error::failed)
+    }
+}
+
+/// D-Bus watchdog I/O
+///
+/// Listens for [`SIGNAL`] signals of [`INTERFACE`] on the given object path. The signals are
+/// received by a background thread.
+#[allow(clippy::module_name_repetitions)]
+pub struct DbusIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+}
+
+impl DbusIo {
+    /// creates a new D-Bus watchdog I/O
+    pub fn create(bus: Bus, path: &str, timeout: Duration) -> Result<Self> {
+        Self::with_connection(&bus.connect()?, path, timeout)
+    }
+    /// creates a new D-Bus watchdog I/O with an existing connection
+    pub fn with_connection(connection: &Connection, path: &str, timeout: Duration) -> Result<Self> {
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface(INTERFACE)
+            .and_then(|b| b.member(SIGNAL))
+            .and_then(|b| b.path(path))
+            .map_err(Error::failed)?
+            .build()
+            .to_owned();
+        let messages = MessageIterator::for_match_rule(rule, connection, Some(BEAT_QUEUE_SIZE))
+            .map_err(Error::failed)?;
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("dbus-watchdog".to_owned())
+            .spawn(move || {
+                for message in messages.flatten() {
+                    let Ok(edge) = message.body().deserialize::<u8>() else {
+                        continue;
+                    };
+                    // if the queue is full, the watchdog is not running
+                    if let Err(rtsc::Error::ChannelClosed) = tx.try_send(Edge::from(edge)) {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self { rx, timeout })
+    }
+}
+
+impl WatchdogIo for DbusIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
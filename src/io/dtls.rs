@@ -0,0 +1,303 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    path::Path,
+};
+
+use openssl::ssl::{ErrorCode, Ssl, SslContext, SslFiletype, SslMethod, SslStream, SslVerifyMode};
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+
+use super::WatchdogIo;
+
+const MTU: u32 = 1200;
+const MAX_DATAGRAM_SIZE: usize = 2048;
+const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+/// Sent (unprotected) by the watchdog to peers which send records out of a session (e.g. after the
+/// watchdog restart), asking them to perform a new handshake. Not a valid DTLS content type
+const RESET: u8 = 0;
+
+/// Builds a DTLS context which requires the peer to present a certificate signed by the CA
+fn context(ca: &Path, cert: &Path, key: &Path) -> Result<SslContext> {
+    let mut builder = SslContext::builder(SslMethod::dtls()).map_err(Error::failed)?;
+    builder
+        .set_ca_file(ca)
+        .and_then(|()| builder.set_certificate_chain_file(cert))
+        .and_then(|()| builder.set_private_key_file(key, SslFiletype::PEM))
+        .and_then(|()| builder.check_private_key())
+        .map_err(Error::failed)?;
+    builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    Ok(builder.build())
+}
+
+/// DTLS client and watchdog configuration (PEM files)
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone)]
+pub struct DtlsConfig {
+    context: SslContext,
+}
+
+impl DtlsConfig {
+    /// creates a new DTLS configuration: the CA certificate(s) to verify the peer, the own
+    /// certificate chain and private key. Both the heart and the watchdog verify each other
+    pub fn new<P: AsRef<Path>>(ca: P, cert: P, key: P) -> Result<Self> {
+        Ok(Self {
+            context: context(ca.as_ref(), cert.as_ref(), key.as_ref())?,
+        })
+    }
+}
+
+/// A connected UDP socket, used by the heart
+struct Connected(UdpSocket);
+
+impl Read for Connected {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.recv(buf)
+    }
+}
+
+impl Write for Connected {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.send(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// DTLS client
+///
+/// Each edge is sent as a separate DTLS record in a single datagram. The session is established
+/// on creation, if sending fails or the watchdog does not know the session (e.g. has been
+/// restarted), a new session is established on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct DtlsHeart {
+    addr: SocketAddr,
+    server_name: String,
+    config: DtlsConfig,
+    handshake_timeout: Duration,
+    stream: Mutex<Option<SslStream<Connected>>>,
+    next: AtomicBool,
+}
+
+impl DtlsHeart {
+    /// creates a new DTLS client, the server name must match the watchdog certificate. The
+    /// handshake timeout limits session (re-)establishing
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        server_name: &str,
+        config: DtlsConfig,
+        handshake_timeout: Duration,
+    ) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("no address resolved"))?;
+        let heart = Self {
+            addr,
+            server_name: server_name.to_owned(),
+            config,
+            handshake_timeout,
+            stream: Mutex::new(None),
+            next: AtomicBool::new(true),
+        };
+        heart.stream.lock().replace(heart.connect()?);
+        Ok(heart)
+    }
+    fn connect(&self) -> Result<SslStream<Connected>> {
+        let socket = UdpSocket::bind(if self.addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        })?;
+        socket.connect(self.addr)?;
+        socket.set_read_timeout(Some(self.handshake_timeout))?;
+        let mut ssl = Ssl::new(&self.config.context).map_err(Error::failed)?;
+        ssl.set_mtu(MTU).map_err(Error::failed)?;
+        ssl.set_hostname(&self.server_name)
+            .and_then(|()| ssl.param_mut().set_host(&self.server_name))
+            .map_err(Error::failed)?;
+        let mut stream = SslStream::new(ssl, Connected(socket)).map_err(Error::failed)?;
+        stream.connect().map_err(|e| {
+            if e.code() == ErrorCode::WANT_READ {
+                Error::Timeout
+            } else {
+                Error::failed(e)
+            }
+        })?;
+        stream.get_ref().0.set_nonblocking(true)?;
+        Ok(stream)
+    }
+}
+
+/// Checks if the watchdog has asked for a new handshake
+fn reset_requested(socket: &UdpSocket) -> bool {
+    let mut buf = [0; MAX_DATAGRAM_SIZE];
+    let mut reset = false;
+    while let Ok(len) = socket.recv(&mut buf) {
+        reset |= len == 1 && buf[0] == RESET;
+    }
+    reset
+}
+
+impl Heart for DtlsHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream
+            .as_ref()
+            .map_or(true, |s| reset_requested(&s.get_ref().0))
+        {
+            stream.replace(self.connect()?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_mut().unwrap().ssl_write(&[edge as u8]) {
+            stream.take();
+            return Err(Error::failed(e));
+        }
+        Ok(())
+    }
+}
+
+/// A datagram channel to a single peer of the watchdog socket. Received datagrams are fed by the
+/// watchdog I/O, as the socket is shared between all peers
+struct Peer {
+    socket: UdpSocket,
+    addr: SocketAddr,
+    datagram: Option<Vec<u8>>,
+}
+
+impl Read for Peer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(datagram) = self.datagram.take() else {
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        };
+        let len = datagram.len().min(buf.len());
+        buf[..len].copy_from_slice(&datagram[..len]);
+        Ok(len)
+    }
+}
+
+impl Write for Peer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.socket.send_to(buf, self.addr)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// DTLS watchdog I/O
+///
+/// Serves a single heart session at a time. A handshake from a new peer (e.g. the heart has been
+/// restarted) is performed in parallel and replaces the current session only when completed, so
+/// peers without a trusted certificate can not interrupt the heartbeats.
+#[allow(clippy::module_name_repetitions)]
+pub struct DtlsIo {
+    socket: UdpSocket,
+    config: DtlsConfig,
+    sessions: Mutex<Sessions>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    established: Option<SslStream<Peer>>,
+    handshake: Option<SslStream<Peer>>,
+}
+
+impl DtlsIo {
+    /// creates a new DTLS watchdog I/O
+    pub fn create<A: ToSocketAddrs>(addr: A, config: DtlsConfig, timeout: Duration) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(timeout))?;
+        Ok(Self {
+            socket,
+            config,
+            sessions: Mutex::new(Sessions::default()),
+        })
+    }
+    fn start_handshake(&self, addr: SocketAddr) -> Result<SslStream<Peer>> {
+        let mut ssl = Ssl::new(&self.config.context).map_err(Error::failed)?;
+        ssl.set_mtu(MTU).map_err(Error::failed)?;
+        let peer = Peer {
+            socket: self.socket.try_clone()?,
+            addr,
+            datagram: None,
+        };
+        SslStream::new(ssl, peer).map_err(Error::failed)
+    }
+    /// processes a datagram, returns the edge if a heartbeat record has been received
+    fn process(&self, sessions: &mut Sessions, addr: SocketAddr, datagram: &[u8]) -> Option<Edge> {
+        if let Some(stream) = sessions
+            .established
+            .as_mut()
+            .filter(|s| s.get_ref().addr == addr)
+        {
+            stream.get_mut().datagram = Some(datagram.to_vec());
+            let mut buf = [0; MAX_DATAGRAM_SIZE];
+            return match stream.ssl_read(&mut buf) {
+                Ok(n) if n > 0 => Some(Edge::from(buf[0])),
+                Err(e) if e.code() == ErrorCode::WANT_READ => None,
+                _ => {
+                    // the session has been closed or is broken
+                    sessions.established.take();
+                    None
+                }
+            };
+        }
+        if sessions
+            .handshake
+            .as_ref()
+            .map_or(true, |s| s.get_ref().addr != addr)
+        {
+            if datagram.first() != Some(&CONTENT_TYPE_HANDSHAKE) {
+                // a record of an unknown session
+                let _ = self.socket.send_to(&[RESET], addr);
+                return None;
+            }
+            sessions.handshake = Some(self.start_handshake(addr).ok()?);
+        }
+        let stream = sessions.handshake.as_mut()?;
+        stream.get_mut().datagram = Some(datagram.to_vec());
+        match stream.accept() {
+            Ok(()) => {
+                sessions.established = sessions.handshake.take();
+            }
+            Err(e) if e.code() == ErrorCode::WANT_READ => {}
+            Err(_) => {
+                // handshake failed (e.g. an untrusted certificate)
+                sessions.handshake.take();
+            }
+        }
+        None
+    }
+}
+
+impl WatchdogIo for DtlsIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let mut sessions = self.sessions.lock();
+        loop {
+            let (len, addr) = self.socket.recv_from(&mut buf)?;
+            if let Some(edge) = self.process(&mut sessions, addr, &buf[..len]) {
+                return Ok(edge);
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut buf = [0; MAX_DATAGRAM_SIZE];
+        let mut sessions = self.sessions.lock();
+        self.socket.set_nonblocking(true)?;
+        // handshake and session control datagrams must be processed
+        while let Ok((len, addr)) = self.socket.recv_from(&mut buf) {
+            self.process(&mut sessions, addr, &buf[..len]);
+        }
+        self.socket.set_nonblocking(false)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{Edge, Error, Result};
+use core::{future::Future, time::Duration};
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+
+use super::WatchdogIoAsync;
+
+/// CAN communication
+#[cfg(feature = "embassy-can")]
+pub mod can;
+
+/// RP2040 PIO hardware heartbeat output
+#[cfg(feature = "rp-pio")]
+pub mod rp_pio;
+
+/// Byte stream (embedded-io) communication
+#[cfg(feature = "embedded-io")]
+pub mod stream;
+
+/// UART communication
+#[cfg(feature = "embassy-uart")]
+pub mod uart;
+
+/// UDP communication (embassy-net)
+#[cfg(feature = "embassy-net")]
+pub mod udp;
+
+/// USB CDC-ACM communication
+#[cfg(feature = "embassy-usb")]
+pub mod usb;
+
+pub(crate) fn embassy_duration(d: Duration) -> embassy_time::Duration {
+    embassy_time::Duration::from_micros(d.as_micros().try_into().unwrap_or(u64::MAX))
+}
+
+/// An input pin which waits for edges with interrupts
+///
+/// The methods match `embassy_stm32::exti::ExtiInput`, so the trait is usually implemented for a
+/// newtype which forwards the calls:
+///
+/// ```ignore
+/// struct Pin(ExtiInput<'static>);
+///
+/// impl EdgeWait for Pin {
+///     fn wait_for_rising_edge(&mut self) -> impl Future<Output = ()> + Send {
+///         self.0.wait_for_rising_edge()
+///     }
+///     fn wait_for_falling_edge(&mut self) -> impl Future<Output = ()> + Send {
+///         self.0.wait_for_falling_edge()
+///     }
+/// }
+/// ```
+pub trait EdgeWait {
+    /// waits for a rising edge
+    fn wait_for_rising_edge(&mut self) -> impl Future<Output = ()> + Send;
+    /// waits for a falling edge
+    fn wait_for_falling_edge(&mut self) -> impl Future<Output = ()> + Send;
+}
+
+/// Interrupt-driven (EXTI) GPIO watchdog I/O
+///
+/// Waits for the expected edge with interrupts instead of polling the pin level.
+#[allow(clippy::module_name_repetitions)]
+pub struct ExtiIo<P: EdgeWait> {
+    pin: Mutex<CriticalSectionRawMutex, P>,
+    timeout: embassy_time::Duration,
+}
+
+impl<P: EdgeWait> ExtiIo<P> {
+    /// creates a new EXTI watchdog I/O
+    pub fn new(pin: P, timeout: Duration) -> Self {
+        Self {
+            pin: Mutex::new(pin),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl<P: EdgeWait + Send> WatchdogIoAsync for ExtiIo<P> {
+    async fn get(&self, expected: Edge) -> Result<Edge> {
+        let mut pin = self.pin.lock().await;
+        match expected {
+            Edge::Rising => {
+                embassy_time::with_timeout(self.timeout, pin.wait_for_rising_edge()).await
+            }
+            Edge::Falling => {
+                embassy_time::with_timeout(self.timeout, pin.wait_for_falling_edge()).await
+            }
+        }
+        .map_err(|_| Error::Timeout)?;
+        Ok(expected)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Generic async GPIO watchdog I/O
+///
+/// Waits for the expected edge on any input pin which implements
+/// [`embedded_hal_async::digital::Wait`]. Pin errors are reported as a timeout.
+#[cfg(feature = "embedded-hal-async")]
+#[allow(clippy::module_name_repetitions)]
+pub struct WaitIo<P: embedded_hal_async::digital::Wait> {
+    pin: Mutex<CriticalSectionRawMutex, P>,
+    timeout: embassy_time::Duration,
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<P: embedded_hal_async::digital::Wait> WaitIo<P> {
+    /// creates a new generic async GPIO watchdog I/O
+    pub fn new(pin: P, timeout: Duration) -> Self {
+        Self {
+            pin: Mutex::new(pin),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<P: embedded_hal_async::digital::Wait> WatchdogIoAsync for WaitIo<P> {
+    async fn get(&self, expected: Edge) -> Result<Edge> {
+        let mut pin = self.pin.lock().await;
+        let deadline = embassy_time::Instant::now() + self.timeout;
+        let res = match expected {
+            Edge::Rising => embassy_time::with_deadline(deadline, pin.wait_for_rising_edge()).await,
+            Edge::Falling => {
+                embassy_time::with_deadline(deadline, pin.wait_for_falling_edge()).await
+            }
+        };
+        match res {
+            Ok(Ok(())) => Ok(expected),
+            Ok(Err(_)) => {
+                // a failing pin may never yield, do not let the watchdog spin
+                embassy_time::Timer::at(deadline).await;
+                Err(Error::Timeout)
+            }
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+
+    async fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use crate::{Edge, Error, HeartAsync, Result};
+use core::{future::Future, time::Duration};
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embassy_time::Instant;
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+use portable_atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering};
+
+use super::{super::Sequence, super::Sequenced, embassy_duration, WatchdogIoAsync};
+
+/// CAN FD beat size: the edge, a reserved byte, the sequence number and the send time (padded to
+/// the nearest CAN FD data length)
+const FD_FRAME_SIZE: usize = 12;
+const NO_LATENCY: u64 = u64::MAX;
+
+/// encodes a CAN FD beat
+fn encode_fd(edge: Edge, seq: u16, sent: u64) -> [u8; FD_FRAME_SIZE] {
+    let mut data = [0; FD_FRAME_SIZE];
+    data[0] = edge as u8;
+    data[2..4].copy_from_slice(&seq.to_be_bytes());
+    data[4..].copy_from_slice(&sent.to_be_bytes());
+    data
+}
+
+/// decodes a beat: the edge and, for CAN FD beats, the sequence number and the send time.
+/// Returns `None` if the frame is not a beat
+fn decode(data: &[u8]) -> Option<(Edge, Option<(u16, u64)>)> {
+    let b = *data.first()?;
+    if b != Edge::Rising as u8 && b != Edge::Falling as u8 {
+        return None;
+    }
+    if data.len() < FD_FRAME_SIZE {
+        return Some((Edge::from(b), None));
+    }
+    let mut sent = [0; 8];
+    sent.copy_from_slice(&data[4..FD_FRAME_SIZE]);
+    Some((
+        Edge::from(b),
+        Some((
+            u16::from_be_bytes([data[2], data[3]]),
+            u64::from_be_bytes(sent),
+        )),
+    ))
+}
+
+/// builds the frame of a beat, the sequence number and the send time are sent with CAN FD only
+fn beat_frame<F: Frame>(config: &CanConfig, edge: Edge, seq: u16, sent: u64) -> Option<F> {
+    if config.fd {
+        F::new(config.id, &encode_fd(edge, seq, sent))
+    } else {
+        F::new(config.id, &[edge as u8])
+    }
+}
+
+/// checks a received beat, returns the result and the send time of CAN FD beats. Classic beats
+/// are corrected for the tolerated losses of the preceding CAN FD ones
+fn check_beat(
+    sequence: &mut Sequence,
+    fd: bool,
+    data: &[u8],
+    max_lost: u16,
+) -> Option<(Sequenced, Option<u64>)> {
+    let (edge, fields) = decode(data)?;
+    match fields.filter(|_| fd) {
+        Some((seq, sent)) => Some((sequence.check(edge, seq, max_lost, None), Some(sent))),
+        None => Some((
+            Sequenced::Beat {
+                edge: sequence.apply(edge),
+                lost: 0,
+            },
+            None,
+        )),
+    }
+}
+
+/// CAN transport configuration, the heart and the watchdog I/O must be configured the same way
+///
+/// ```ignore
+/// let heart = CanHeart::new(tx, CanConfig::new(id).with_fd(true));
+/// let io = CanIo::create(rx, CanConfig::new(id).with_fd(true), timeout)?;
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct CanConfig {
+    id: Id,
+    fd: bool,
+}
+
+impl CanConfig {
+    /// creates a new configuration with the heartbeat ID (classic CAN frames)
+    pub fn new(id: impl Into<Id>) -> Self {
+        Self {
+            id: id.into(),
+            fd: false,
+        }
+    }
+    /// uses CAN FD frames: each beat carries a sequence number and the send time (microseconds
+    /// of the heart time base), so the watchdog I/O detects lost beats and measures the latency
+    /// (the time bases must be synchronized). The HAL frame type and the bus must support CAN FD
+    pub fn with_fd(mut self, fd: bool) -> Self {
+        self.fd = fd;
+        self
+    }
+    /// the heartbeat ID
+    pub fn id(&self) -> Id {
+        self.id
+    }
+    /// are CAN FD frames used
+    pub fn fd(&self) -> bool {
+        self.fd
+    }
+}
+
+impl From<Id> for CanConfig {
+    fn from(id: Id) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<StandardId> for CanConfig {
+    fn from(id: StandardId) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<ExtendedId> for CanConfig {
+    fn from(id: ExtendedId) -> Self {
+        Self::new(id)
+    }
+}
+
+/// A CAN transmitter
+///
+/// Usually implemented for a newtype over a HAL transmitter (e.g. `embassy_stm32::can::CanTx` for
+/// bxCAN or FDCAN):
+///
+/// ```ignore
+/// struct Tx(CanTx<'static>);
+///
+/// impl CanTransmit for Tx {
+///     type Frame = embassy_stm32::can::Frame;
+///     type Error = ();
+///     async fn transmit(&mut self, frame: &Self::Frame) -> Result<(), ()> {
+///         self.0.write(frame).await;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub trait CanTransmit {
+    /// CAN frame type
+    type Frame: Frame;
+    /// transmit error
+    type Error;
+    /// transmits a frame
+    fn transmit(
+        &mut self,
+        frame: &Self::Frame,
+    ) -> impl Future<Output = core::result::Result<(), Self::Error>>;
+}
+
+/// A CAN receiver
+///
+/// Usually implemented for a newtype over a HAL receiver (e.g. `embassy_stm32::can::CanRx`).
+#[allow(clippy::module_name_repetitions)]
+pub trait CanReceive {
+    /// CAN frame type
+    type Frame: Frame;
+    /// receive error (e.g. a bus error)
+    type Error;
+    /// receives a frame
+    fn receive(&mut self) -> impl Future<Output = core::result::Result<Self::Frame, Self::Error>>;
+    /// configures the hardware acceptance filters to receive frames with the heartbeat ID only,
+    /// called once when the watchdog I/O is created. If the filters are not configured (the
+    /// default), frames with other IDs are filtered by the watchdog I/O
+    fn set_filter(&mut self, _id: Id) -> core::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// CAN client
+///
+/// Each edge is sent as a frame with the heartbeat ID, the first data byte is the edge ("+" -
+/// rising, "." - falling). CAN FD frames (see [`CanConfig::with_fd`]) also carry the sequence
+/// number (bytes 2-3) and the send time in microseconds (bytes 4-11), big-endian.
+#[allow(clippy::module_name_repetitions)]
+pub struct CanHeart<T: CanTransmit> {
+    tx: Mutex<CriticalSectionRawMutex, T>,
+    config: CanConfig,
+    next: AtomicBool,
+    seq: AtomicU16,
+}
+
+impl<T: CanTransmit> CanHeart<T> {
+    /// creates a new CAN client with the heartbeat ID or the configuration
+    pub fn new(tx: T, config: impl Into<CanConfig>) -> Self {
+        Self {
+            tx: Mutex::new(tx),
+            config: config.into(),
+            next: AtomicBool::new(true),
+            seq: AtomicU16::new(0),
+        }
+    }
+}
+
+impl<T: CanTransmit> HeartAsync for CanHeart<T> {
+    async fn beat_async(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        let seq = if self.config.fd {
+            self.seq.fetch_add(1, Ordering::Relaxed)
+        } else {
+            0
+        };
+        let frame: T::Frame = beat_frame(&self.config, edge, seq, Instant::now().as_micros())
+            .ok_or_else(Error::failed)?;
+        self.tx
+            .lock()
+            .await
+            .transmit(&frame)
+            .await
+            .map_err(|_| Error::failed())
+    }
+}
+
+/// The receiver and the sequence state of CAN FD beats
+struct Receiver<R> {
+    rx: R,
+    sequence: Sequence,
+}
+
+/// CAN watchdog I/O
+///
+/// Frames with other IDs, remote frames and receive errors are skipped, so a broken bus is
+/// reported as a timeout.
+///
+/// With CAN FD (see [`CanConfig::with_fd`]), duplicate beats are ignored, if more than the allowed
+/// number of beats is lost (see [`CanIo::with_max_lost`]), [`Error::Lost`] is reported, the
+/// latency is reported to the watchdog statistics. Classic single-byte beats are still accepted
+/// (corrected for the tolerated losses of the preceding CAN FD beats).
+#[allow(clippy::module_name_repetitions)]
+pub struct CanIo<R: CanReceive> {
+    rx: Mutex<CriticalSectionRawMutex, Receiver<R>>,
+    config: CanConfig,
+    timeout: embassy_time::Duration,
+    max_lost: u16,
+    lost: AtomicU32,
+    latency: AtomicU64,
+}
+
+impl<R: CanReceive> CanIo<R> {
+    /// creates a new CAN watchdog I/O with the heartbeat ID or the configuration and configures
+    /// the receiver filters (see [`CanReceive::set_filter`])
+    pub fn create(mut rx: R, config: impl Into<CanConfig>, timeout: Duration) -> Result<Self> {
+        let config = config.into();
+        rx.set_filter(config.id).map_err(|_| Error::failed())?;
+        Ok(Self {
+            rx: Mutex::new(Receiver {
+                rx,
+                sequence: Sequence::default(),
+            }),
+            config,
+            timeout: embassy_duration(timeout),
+            max_lost: 0,
+            lost: AtomicU32::new(0),
+            latency: AtomicU64::new(NO_LATENCY),
+        })
+    }
+    /// sets the number of consecutive lost beats (CAN FD only) which are tolerated, the default is
+    /// 0
+    pub fn with_max_lost(mut self, max_lost: u16) -> Self {
+        self.max_lost = max_lost;
+        self
+    }
+}
+
+impl<R: CanReceive> WatchdogIoAsync for CanIo<R> {
+    async fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut rx = self.rx.lock().await;
+        let deadline = Instant::now() + self.timeout;
+        // the deadline is checked explicitly as a failing receiver may never yield
+        while Instant::now() < deadline {
+            let Ok(res) = embassy_time::with_deadline(deadline, rx.rx.receive()).await else {
+                break;
+            };
+            let Ok(frame) = res else {
+                continue;
+            };
+            if frame.id() != self.config.id || frame.is_remote_frame() {
+                continue;
+            }
+            let Some((res, sent)) = check_beat(
+                &mut rx.sequence,
+                self.config.fd,
+                frame.data(),
+                self.max_lost,
+            ) else {
+                continue;
+            };
+            let lost = match res {
+                Sequenced::Beat { lost, .. } | Sequenced::Lost(lost) => lost,
+                // a duplicate
+                Sequenced::Ignored => continue,
+            };
+            self.lost.store(u32::from(lost), Ordering::Relaxed);
+            if let Some(sent) = sent {
+                let latency = Instant::now().as_micros().saturating_sub(sent);
+                self.latency.store(latency, Ordering::Relaxed);
+            }
+            return match res {
+                Sequenced::Beat { edge, .. } => Ok(edge),
+                _ => Err(Error::Lost),
+            };
+        }
+        Err(Error::Timeout)
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        match self.latency.load(Ordering::Relaxed) {
+            NO_LATENCY => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    fn lost(&self) -> u32 {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut rx = self.rx.lock().await;
+        rx.sequence = Sequence::default();
+        self.lost.store(0, Ordering::Relaxed);
+        self.latency.store(NO_LATENCY, Ordering::Relaxed);
+        // the receiver is polled before the timer, so only the queued frames are read
+        while let Ok(Ok(_)) =
+            embassy_time::with_timeout(embassy_time::Duration::from_ticks(0), rx.rx.receive()).await
+        {
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A CAN FD frame
+    struct FdFrame {
+        id: Id,
+        data: [u8; 64],
+        dlc: usize,
+    }
+
+    impl Frame for FdFrame {
+        fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+            let mut frame = Self {
+                id: id.into(),
+                data: [0; 64],
+                dlc: data.len(),
+            };
+            frame.data.get_mut(..data.len())?.copy_from_slice(data);
+            Some(frame)
+        }
+        fn new_remote(_id: impl Into<Id>, _dlc: usize) -> Option<Self> {
+            None
+        }
+        fn is_extended(&self) -> bool {
+            matches!(self.id, Id::Extended(_))
+        }
+        fn is_remote_frame(&self) -> bool {
+            false
+        }
+        fn id(&self) -> Id {
+            self.id
+        }
+        fn dlc(&self) -> usize {
+            self.dlc
+        }
+        fn data(&self) -> &[u8] {
+            &self.data[..self.dlc]
+        }
+    }
+
+    fn beat(edge: Edge, lost: u16) -> Sequenced {
+        Sequenced::Beat { edge, lost }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let data = encode_fd(Edge::Falling, 0x1234, 0x0102_0304_0506_0708);
+        assert_eq!(
+            data,
+            [b'.', 0, 0x12, 0x34, 1, 2, 3, 4, 5, 6, 7, 8],
+            "unexpected CAN FD beat"
+        );
+        assert_eq!(
+            decode(&data),
+            Some((Edge::Falling, Some((0x1234, 0x0102_0304_0506_0708))))
+        );
+        assert_eq!(decode(b"+"), Some((Edge::Rising, None)));
+        assert_eq!(decode(b"x"), None);
+        assert_eq!(decode(&[]), None);
+        // a truncated CAN FD beat is decoded as a classic one
+        assert_eq!(decode(&data[..8]), Some((Edge::Falling, None)));
+    }
+
+    #[test]
+    fn test_fd_frame_round_trip() {
+        let config = CanConfig::new(StandardId::new(0x100).unwrap()).with_fd(true);
+        let mut sequence = Sequence::default();
+        for (seq, sent) in [(1, 1_000), (2, 2_000)] {
+            let frame: FdFrame = beat_frame(&config, Edge::Falling, seq, sent).unwrap();
+            assert_eq!(frame.id(), config.id());
+            assert_eq!(frame.dlc(), FD_FRAME_SIZE);
+            let (res, fd_sent) = check_beat(&mut sequence, config.fd(), frame.data(), 0).unwrap();
+            assert_eq!((res, fd_sent), (beat(Edge::Falling, 0), Some(sent)));
+        }
+        // the sequence number and the send time are not sent in classic frames
+        let config = config.with_fd(false);
+        let frame: FdFrame = beat_frame(&config, Edge::Rising, 3, 3_000).unwrap();
+        assert_eq!(frame.data(), b"+");
+    }
+
+    #[test]
+    fn test_classic_frames() {
+        let mut sequence = Sequence::default();
+        for edge in [Edge::Rising, Edge::Falling] {
+            let (res, sent) = check_beat(&mut sequence, true, &[edge as u8], 0).unwrap();
+            assert_eq!((res, sent), (beat(edge, 0), None));
+        }
+        // CAN FD beats are checked as classic ones if CAN FD is disabled
+        let data = encode_fd(Edge::Rising, 7, 0);
+        let (res, sent) = check_beat(&mut sequence, false, &data, 0).unwrap();
+        assert_eq!((res, sent), (beat(Edge::Rising, 0), None));
+    }
+
+    #[test]
+    fn test_fd_frames() {
+        let mut sequence = Sequence::default();
+        let mut check = |edge: Edge, seq: u16| {
+            check_beat(&mut sequence, true, &encode_fd(edge, seq, 100), 1)
+                .unwrap()
+                .0
+        };
+        assert_eq!(check(Edge::Rising, 65532), beat(Edge::Rising, 0));
+        assert_eq!(check(Edge::Rising, 65532), Sequenced::Ignored);
+        // a tolerated odd loss inverts the edges
+        assert_eq!(check(Edge::Rising, 65534), beat(Edge::Falling, 1));
+        assert_eq!(check(Edge::Falling, 65535), beat(Edge::Rising, 0));
+        // the counter wrap is not a restart, the edges are still inverted
+        assert_eq!(check(Edge::Rising, 0), beat(Edge::Falling, 0));
+        assert_eq!(check(Edge::Falling, 1), beat(Edge::Rising, 0));
+        assert_eq!(check(Edge::Falling, 5), Sequenced::Lost(3));
+        // the heart restart resets the phase
+        assert_eq!(check(Edge::Rising, 0), beat(Edge::Rising, 0));
+    }
+
+    #[test]
+    fn test_classic_after_fd() {
+        let mut sequence = Sequence::default();
+        for (edge, seq) in [(Edge::Rising, 1), (Edge::Rising, 3)] {
+            check_beat(&mut sequence, true, &encode_fd(edge, seq, 0), 1).unwrap();
+        }
+        let (res, _) = check_beat(&mut sequence, true, b".", 1).unwrap();
+        assert_eq!(res, beat(Edge::Rising, 0));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Heart, Result};
+use core::{cell::RefCell, time::Duration};
+
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use pio::{
+    Assembler, JmpCondition, MovDestination, MovOperation, MovSource, Program,
+    RP2040_MAX_PROGRAM_SIZE,
+};
+
+/// PIO cycles per program iteration besides the delay loop
+const PROGRAM_OVERHEAD: u128 = 5;
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Creates the heartbeat PIO program
+///
+/// Each word pulled from the TX FIFO produces a single edge on the OUT pin, followed by the delay
+/// of the word value (in PIO cycles). Until the FIFO is empty, the edges are timed by the PIO only.
+///
+/// ```text
+/// .wrap_target
+///     pull block
+///     mov x, osr
+///     mov y, ~y
+///     mov pins, y
+/// delay:
+///     jmp x-- delay
+/// .wrap
+/// ```
+pub fn program() -> Program<RP2040_MAX_PROGRAM_SIZE> {
+    let mut a = Assembler::<RP2040_MAX_PROGRAM_SIZE>::new();
+    let mut delay = a.label();
+    a.pull(false, true);
+    a.mov(MovDestination::X, MovOperation::None, MovSource::OSR);
+    a.mov(MovDestination::Y, MovOperation::Invert, MovSource::Y);
+    a.mov(MovDestination::PINS, MovOperation::None, MovSource::Y);
+    a.bind(&mut delay);
+    a.jmp(JmpCondition::XDecNonZero, &mut delay);
+    a.assemble_program()
+}
+
+/// A PIO state machine TX FIFO
+///
+/// Usually implemented for a newtype over a HAL state machine (e.g. `embassy_rp::pio::StateMachine`):
+///
+/// ```ignore
+/// struct Tx(StateMachine<'static, PIO0, 0>);
+///
+/// impl PioTx for Tx {
+///     fn try_push(&mut self, word: u32) -> bool {
+///         self.0.tx().try_push(word)
+///     }
+/// }
+/// ```
+pub trait PioTx {
+    /// pushes a word to the FIFO, returns false if the FIFO is full
+    fn try_push(&mut self, word: u32) -> bool;
+}
+
+/// RP2040 PIO client
+///
+/// A PIO state machine generates the heartbeat square wave, so the edges are timed independently
+/// of the CPU. Each beat provides the state machine with a single edge, the client keeps a backlog
+/// of edges in the FIFO (set on creation), which covers the beat jitter. If the firmware locks up,
+/// the wave stops when the backlog is exhausted.
+///
+/// The state machine must run [`program`] with the heartbeat pin as the OUT pin, joining the FIFOs
+/// (TX only) is recommended. The beats must be sent every interval.
+#[allow(clippy::module_name_repetitions)]
+pub struct PioHeart<T: PioTx> {
+    tx: Mutex<CriticalSectionRawMutex, RefCell<T>>,
+    word: u32,
+}
+
+impl<T: PioTx> PioHeart<T> {
+    /// creates a new PIO client, the interval is the watchdog interval, the clock is the state
+    /// machine clock frequency (the system clock divided by the state machine clock divider). The
+    /// FIFO is filled with the backlog edges, so the wave is started immediately
+    pub fn new(mut tx: T, interval: Duration, clock_hz: u32, backlog: usize) -> Self {
+        let cycles = interval.as_nanos() * u128::from(clock_hz) / NANOS_PER_SEC;
+        let word = u32::try_from(cycles.saturating_sub(PROGRAM_OVERHEAD)).unwrap_or(u32::MAX);
+        for _ in 0..backlog {
+            if !tx.try_push(word) {
+                break;
+            }
+        }
+        Self {
+            tx: Mutex::new(RefCell::new(tx)),
+            word,
+        }
+    }
+}
+
+impl<T: PioTx> Heart for PioHeart<T> {
+    fn beat(&self) -> Result<()> {
+        // if the FIFO is full, the backlog is larger than the FIFO depth
+        self.tx.lock(|tx| tx.borrow_mut().try_push(self.word));
+        Ok(())
+    }
+}
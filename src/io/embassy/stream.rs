@@ -0,0 +1,228 @@
+use crate::{io::WatchdogIo, Edge, Error, Heart, HeartAsync, Result};
+use core::{cell::RefCell, time::Duration};
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embassy_time::Instant;
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::{embassy_duration, WatchdogIoAsync};
+
+const FRAME_START: u8 = 0x02;
+const POLL_INTERVAL: embassy_time::Duration = embassy_time::Duration::from_millis(1);
+
+/// Frames an edge: the frame start byte, the edge byte and its bitwise complement
+fn frame(edge: Edge) -> [u8; 3] {
+    [FRAME_START, edge as u8, !(edge as u8)]
+}
+
+/// Sliding window frame decoder, resynchronizes on the next frame start after garbage
+#[derive(Default)]
+struct Decoder {
+    window: [u8; 3],
+}
+
+impl Decoder {
+    fn push(&mut self, b: u8) -> Option<Edge> {
+        self.window = [self.window[1], self.window[2], b];
+        let [start, edge, check] = self.window;
+        if start == FRAME_START
+            && (edge == Edge::Rising as u8 || edge == Edge::Falling as u8)
+            && check == !edge
+        {
+            self.window = [0; 3];
+            Some(Edge::from(edge))
+        } else {
+            None
+        }
+    }
+}
+
+struct Receiver<R> {
+    rx: R,
+    decoder: Decoder,
+}
+
+/// Byte stream client
+///
+/// Each edge is sent as a 3-byte frame over any blocking writer which implements
+/// [`embedded_io::Write`] (UART, USB serial, a socket etc.). The client is not `Sync`, it must be
+/// used from a single execution context.
+#[allow(clippy::module_name_repetitions)]
+pub struct StreamHeart<W: embedded_io::Write> {
+    tx: RefCell<W>,
+    next: AtomicBool,
+}
+
+impl<W: embedded_io::Write> StreamHeart<W> {
+    /// creates a new byte stream client
+    pub fn new(tx: W) -> Self {
+        Self {
+            tx: RefCell::new(tx),
+            next: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<W: embedded_io::Write> Heart for StreamHeart<W> {
+    fn beat(&self) -> Result<()> {
+        let mut tx = self.tx.borrow_mut();
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        tx.write_all(&frame(edge)).map_err(|_| Error::failed())?;
+        tx.flush().map_err(|_| Error::failed())
+    }
+}
+
+/// Byte stream watchdog I/O
+///
+/// Reads frames sent by [`StreamHeart`] or [`StreamHeartAsync`] from any blocking reader which
+/// implements [`embedded_io::Read`] and [`embedded_io::ReadReady`]. The reader is polled until
+/// the timeout expires. Bytes outside of valid frames and receive errors are skipped, so a broken
+/// link is reported as a timeout. The I/O is not `Sync`, it must be used from a single execution
+/// context.
+#[allow(clippy::module_name_repetitions)]
+pub struct StreamIo<R: embedded_io::Read + embedded_io::ReadReady> {
+    receiver: RefCell<Receiver<R>>,
+    timeout: embassy_time::Duration,
+}
+
+impl<R: embedded_io::Read + embedded_io::ReadReady> StreamIo<R> {
+    /// creates a new byte stream watchdog I/O, the timeout is used as the read timeout (usually
+    /// [`crate::WatchdogConfig::io_timeout`])
+    pub fn new(rx: R, timeout: Duration) -> Self {
+        Self {
+            receiver: RefCell::new(Receiver {
+                rx,
+                decoder: Decoder::default(),
+            }),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl<R: embedded_io::Read + embedded_io::ReadReady> WatchdogIo for StreamIo<R> {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut receiver = self.receiver.borrow_mut();
+        let Receiver { rx, decoder } = &mut *receiver;
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0];
+        while Instant::now() < deadline {
+            if !matches!(rx.read_ready(), Ok(true)) {
+                embassy_time::block_for(POLL_INTERVAL);
+                continue;
+            }
+            if matches!(rx.read(&mut buf), Ok(1)) {
+                if let Some(edge) = decoder.push(buf[0]) {
+                    return Ok(edge);
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut receiver = self.receiver.borrow_mut();
+        let mut buf = [0; 16];
+        while matches!(receiver.rx.read_ready(), Ok(true)) {
+            if !matches!(receiver.rx.read(&mut buf), Ok(n) if n > 0) {
+                break;
+            }
+        }
+        receiver.decoder = Decoder::default();
+        Ok(())
+    }
+}
+
+/// Byte stream async client
+///
+/// Each edge is sent as a 3-byte frame over any writer which implements
+/// [`embedded_io_async::Write`].
+#[allow(clippy::module_name_repetitions)]
+pub struct StreamHeartAsync<W: embedded_io_async::Write> {
+    tx: Mutex<CriticalSectionRawMutex, W>,
+    next: AtomicBool,
+}
+
+impl<W: embedded_io_async::Write> StreamHeartAsync<W> {
+    /// creates a new byte stream async client
+    pub fn new(tx: W) -> Self {
+        Self {
+            tx: Mutex::new(tx),
+            next: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<W: embedded_io_async::Write> HeartAsync for StreamHeartAsync<W> {
+    async fn beat_async(&self) -> Result<()> {
+        let mut tx = self.tx.lock().await;
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        tx.write_all(&frame(edge))
+            .await
+            .map_err(|_| Error::failed())?;
+        tx.flush().await.map_err(|_| Error::failed())
+    }
+}
+
+/// Byte stream async watchdog I/O
+///
+/// Reads frames sent by [`StreamHeart`] or [`StreamHeartAsync`] from any reader which implements
+/// [`embedded_io_async::Read`]. Bytes outside of valid frames and receive errors are skipped, so a
+/// broken link is reported as a timeout.
+#[allow(clippy::module_name_repetitions)]
+pub struct StreamIoAsync<R: embedded_io_async::Read> {
+    receiver: Mutex<CriticalSectionRawMutex, Receiver<R>>,
+    timeout: embassy_time::Duration,
+}
+
+impl<R: embedded_io_async::Read> StreamIoAsync<R> {
+    /// creates a new byte stream async watchdog I/O, the timeout is used as the read timeout
+    /// (usually [`crate::WatchdogConfig::io_timeout`])
+    pub fn new(rx: R, timeout: Duration) -> Self {
+        Self {
+            receiver: Mutex::new(Receiver {
+                rx,
+                decoder: Decoder::default(),
+            }),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl<R: embedded_io_async::Read> WatchdogIoAsync for StreamIoAsync<R> {
+    async fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut receiver = self.receiver.lock().await;
+        let Receiver { rx, decoder } = &mut *receiver;
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0];
+        // the deadline is checked explicitly as a failing reader may never yield
+        while Instant::now() < deadline {
+            let Ok(res) = embassy_time::with_deadline(deadline, rx.read(&mut buf)).await else {
+                break;
+            };
+            if matches!(res, Ok(1)) {
+                if let Some(edge) = decoder.push(buf[0]) {
+                    return Ok(edge);
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut receiver = self.receiver.lock().await;
+        let mut buf = [0; 16];
+        // the reader is polled before the timer, so only the buffered data is read
+        while let Ok(Ok(n)) = embassy_time::with_timeout(
+            embassy_time::Duration::from_ticks(0),
+            receiver.rx.read(&mut buf),
+        )
+        .await
+        {
+            if n == 0 {
+                break;
+            }
+        }
+        receiver.decoder = Decoder::default();
+        Ok(())
+    }
+}
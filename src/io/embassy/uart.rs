@@ -0,0 +1,97 @@
+use crate::{Edge, Error, HeartAsync, Result};
+use core::time::Duration;
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embassy_time::Instant;
+use embedded_io_async::{Read, Write};
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::{embassy_duration, WatchdogIoAsync};
+
+/// UART client
+///
+/// Each edge is sent as a single byte ("+" - rising, "." - falling), e.g. with the transmitter
+/// half of an embassy UART (any writer which implements [`embedded_io_async::Write`] can be
+/// used).
+#[allow(clippy::module_name_repetitions)]
+pub struct UartHeart<W: Write> {
+    tx: Mutex<CriticalSectionRawMutex, W>,
+    next: AtomicBool,
+}
+
+impl<W: Write> UartHeart<W> {
+    /// creates a new UART client
+    pub fn new(tx: W) -> Self {
+        Self {
+            tx: Mutex::new(tx),
+            next: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<W: Write> HeartAsync for UartHeart<W> {
+    async fn beat_async(&self) -> Result<()> {
+        let mut tx = self.tx.lock().await;
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        tx.write_all(&[edge as u8])
+            .await
+            .map_err(|_| Error::failed())?;
+        tx.flush().await.map_err(|_| Error::failed())
+    }
+}
+
+/// UART watchdog I/O
+///
+/// Bytes other than the edge ones (e.g. line noise after the peer reset) and receive errors are
+/// skipped, so a broken link is reported as a timeout.
+#[allow(clippy::module_name_repetitions)]
+pub struct UartIo<R: Read> {
+    rx: Mutex<CriticalSectionRawMutex, R>,
+    timeout: embassy_time::Duration,
+}
+
+impl<R: Read> UartIo<R> {
+    /// creates a new UART watchdog I/O, the timeout is used as the read timeout (usually
+    /// [`crate::WatchdogConfig::io_timeout`])
+    pub fn new(rx: R, timeout: Duration) -> Self {
+        Self {
+            rx: Mutex::new(rx),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl<R: Read> WatchdogIoAsync for UartIo<R> {
+    async fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut rx = self.rx.lock().await;
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0];
+        // the deadline is checked explicitly as a failing reader may never yield
+        while Instant::now() < deadline {
+            let Ok(res) = embassy_time::with_deadline(deadline, rx.read(&mut buf)).await else {
+                break;
+            };
+            if matches!(res, Ok(1))
+                && (buf[0] == Edge::Rising as u8 || buf[0] == Edge::Falling as u8)
+            {
+                return Ok(Edge::from(buf[0]));
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut rx = self.rx.lock().await;
+        let mut buf = [0; 16];
+        // the reader is polled before the timer, so only the buffered data is read
+        while let Ok(Ok(n)) =
+            embassy_time::with_timeout(embassy_time::Duration::from_ticks(0), rx.read(&mut buf))
+                .await
+        {
+            if n == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{Edge, Error, HeartAsync, Result};
+use core::time::Duration;
+
+use embassy_net::{udp::UdpSocket, IpEndpoint};
+use embassy_time::Instant;
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::{embassy_duration, WatchdogIoAsync};
+
+/// UDP client
+///
+/// Sends single-byte (v1) datagrams over an [`embassy_net`] stack (Ethernet, Wi-Fi etc.), so the
+/// heart is compatible with the `std` UDP watchdog I/O.
+#[allow(clippy::module_name_repetitions)]
+pub struct UdpHeart<'a> {
+    socket: UdpSocket<'a>,
+    remote: IpEndpoint,
+    next: AtomicBool,
+}
+
+impl<'a> UdpHeart<'a> {
+    /// creates a new UDP client, the socket must be bound (e.g. to port 0)
+    pub fn new(socket: UdpSocket<'a>, remote: IpEndpoint) -> Self {
+        Self {
+            socket,
+            remote,
+            next: AtomicBool::new(true),
+        }
+    }
+}
+
+impl HeartAsync for UdpHeart<'_> {
+    async fn beat_async(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        self.socket
+            .send_to(&[edge as u8], self.remote)
+            .await
+            .map_err(|_| Error::failed())
+    }
+}
+
+/// UDP watchdog I/O
+///
+/// Accepts datagrams sent by both v1 and v2 (sequenced) hearts, sequence numbers are ignored.
+#[allow(clippy::module_name_repetitions)]
+pub struct UdpIo<'a> {
+    socket: UdpSocket<'a>,
+    timeout: embassy_time::Duration,
+}
+
+impl<'a> UdpIo<'a> {
+    /// creates a new UDP watchdog I/O, the socket must be bound to the watchdog port, the timeout
+    /// is used as the receive timeout (usually [`crate::WatchdogConfig::io_timeout`])
+    pub fn new(socket: UdpSocket<'a>, timeout: Duration) -> Self {
+        Self {
+            socket,
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl WatchdogIoAsync for UdpIo<'_> {
+    async fn get(&self, _expected: Edge) -> Result<Edge> {
+        let deadline = Instant::now() + self.timeout;
+        let mut buf = [0; 3];
+        while Instant::now() < deadline {
+            let Ok(res) =
+                embassy_time::with_deadline(deadline, self.socket.recv_from(&mut buf)).await
+            else {
+                break;
+            };
+            // truncated datagrams are skipped
+            if let Ok((n, _)) = res {
+                if n > 0 {
+                    return Ok(Edge::from(buf[0]));
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut buf = [0; 3];
+        // the socket is polled before the timer, so only the buffered datagrams are read
+        while embassy_time::with_timeout(
+            embassy_time::Duration::from_ticks(0),
+            self.socket.recv_from(&mut buf),
+        )
+        .await
+        .is_ok()
+        {}
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Edge, Error, HeartAsync, Result};
+use core::time::Duration;
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embassy_time::Instant;
+use embassy_usb::{
+    class::cdc_acm::{Receiver, Sender},
+    driver::{Driver, EndpointError},
+};
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::{embassy_duration, WatchdogIoAsync};
+
+/// The maximum packet size of a full-speed bulk endpoint
+const MAX_PACKET_SIZE: usize = 64;
+
+/// USB CDC-ACM client
+///
+/// Each edge is sent as a single byte packet ("+" - rising, "." - falling). The host receives the
+/// edges as a serial port, so the client can be supervised with `io::serial::SerialIo` (`std`).
+#[allow(clippy::module_name_repetitions)]
+pub struct UsbCdcHeart<'d, D: Driver<'d>> {
+    tx: Mutex<CriticalSectionRawMutex, Sender<'d, D>>,
+    next: AtomicBool,
+}
+
+impl<'d, D: Driver<'d>> UsbCdcHeart<'d, D> {
+    /// creates a new USB CDC-ACM client from the class sender half
+    pub fn new(tx: Sender<'d, D>) -> Self {
+        Self {
+            tx: Mutex::new(tx),
+            next: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<'d, D: Driver<'d>> HeartAsync for UsbCdcHeart<'d, D> {
+    async fn beat_async(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        self.tx
+            .lock()
+            .await
+            .write_packet(&[edge as u8])
+            .await
+            .map_err(|_| Error::failed())
+    }
+}
+
+/// USB CDC-ACM watchdog I/O
+///
+/// Supervises a host application over the USB cable, the host sends the edges to the serial port
+/// (e.g. with `io::serial::SerialHeart`). If the host is disconnected, a timeout is
+/// reported. The class must be created with the max packet size up to 64 bytes.
+#[allow(clippy::module_name_repetitions)]
+pub struct UsbCdcIo<'d, D: Driver<'d>> {
+    rx: Mutex<CriticalSectionRawMutex, Packets<'d, D>>,
+    timeout: embassy_time::Duration,
+}
+
+/// Received packets, a packet may contain several edges
+struct Packets<'d, D: Driver<'d>> {
+    rx: Receiver<'d, D>,
+    buf: [u8; MAX_PACKET_SIZE],
+    pos: usize,
+    len: usize,
+}
+
+impl<'d, D: Driver<'d>> UsbCdcIo<'d, D> {
+    /// creates a new USB CDC-ACM watchdog I/O from the class receiver half
+    pub fn new(rx: Receiver<'d, D>, timeout: Duration) -> Self {
+        Self {
+            rx: Mutex::new(Packets {
+                rx,
+                buf: [0; MAX_PACKET_SIZE],
+                pos: 0,
+                len: 0,
+            }),
+            timeout: embassy_duration(timeout),
+        }
+    }
+}
+
+impl<'d, D: Driver<'d>> WatchdogIoAsync for UsbCdcIo<'d, D> {
+    async fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut packets = self.rx.lock().await;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            while packets.pos < packets.len {
+                let b = packets.buf[packets.pos];
+                packets.pos += 1;
+                if b == Edge::Rising as u8 || b == Edge::Falling as u8 {
+                    return Ok(Edge::from(b));
+                }
+            }
+            let Packets { rx, buf, .. } = &mut *packets;
+            match embassy_time::with_deadline(deadline, rx.read_packet(buf)).await {
+                Ok(Ok(len)) => {
+                    packets.pos = 0;
+                    packets.len = len;
+                }
+                Ok(Err(EndpointError::Disabled)) => {
+                    // the host is disconnected
+                    embassy_time::with_deadline(deadline, rx.wait_connection())
+                        .await
+                        .map_err(|_| Error::Timeout)?;
+                }
+                Ok(Err(EndpointError::BufferOverflow)) => {}
+                Err(_) => return Err(Error::Timeout),
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut packets = self.rx.lock().await;
+        let Packets { rx, buf, .. } = &mut *packets;
+        // the endpoint is polled before the timer, so only the received packets are read
+        while let Ok(Ok(_)) =
+            embassy_time::with_timeout(embassy_time::Duration::from_ticks(0), rx.read_packet(buf))
+                .await
+        {}
+        packets.pos = 0;
+        packets.len = 0;
+        Ok(())
+    }
+}
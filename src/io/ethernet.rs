@@ -0,0 +1,178 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    ffi::CString,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::WatchdogIo;
+
+/// Default EtherType (IEEE 802 local experimental EtherType 1)
+pub const DEFAULT_ETHERTYPE: u16 = 0x88b5;
+/// Broadcast MAC address
+pub const BROADCAST: [u8; 6] = [0xff; 6];
+
+// Ethernet frames are padded to the minimum size, so the buffer must fit the minimum payload
+const MAX_FRAME_PAYLOAD: usize = 1500;
+
+/// Opens a packet socket bound to the interface, the link-layer header is handled by the kernel
+fn open(interface: &str, ethertype: u16) -> Result<(OwnedFd, libc::sockaddr_ll)> {
+    let name = CString::new(interface).map_err(Error::failed)?;
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_PACKET,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::c_int::from(ethertype.to_be()),
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let mut addr: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+    addr.sll_family = libc::sa_family_t::try_from(libc::AF_PACKET).map_err(Error::failed)?;
+    addr.sll_protocol = ethertype.to_be();
+    addr.sll_ifindex = libc::c_int::try_from(index).map_err(Error::failed)?;
+    #[allow(clippy::cast_possible_truncation)]
+    let res = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            core::ptr::addr_of!(addr).cast(),
+            core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        )
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok((socket, addr))
+}
+
+/// Raw Ethernet client
+///
+/// Edges are sent as layer-2 frames with the configured EtherType, the first payload byte is the
+/// edge. Requires CAP_NET_RAW.
+#[allow(clippy::module_name_repetitions)]
+pub struct EthernetHeart {
+    socket: OwnedFd,
+    destination: libc::sockaddr_ll,
+    next: AtomicBool,
+}
+
+impl EthernetHeart {
+    /// creates a new raw Ethernet client, sending frames from the interface (e.g. "eth1") to the
+    /// destination MAC address (use [`BROADCAST`] if the watchdog address is unknown)
+    pub fn create(interface: &str, destination: [u8; 6], ethertype: u16) -> Result<Self> {
+        let (socket, mut addr) = open(interface, ethertype)?;
+        addr.sll_halen = 6;
+        addr.sll_addr[..6].copy_from_slice(&destination);
+        Ok(Self {
+            socket,
+            destination: addr,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for EthernetHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+        #[allow(clippy::cast_possible_truncation)]
+        let res = unsafe {
+            libc::sendto(
+                self.socket.as_raw_fd(),
+                core::ptr::addr_of!(edge).cast(),
+                1,
+                0,
+                core::ptr::addr_of!(self.destination).cast(),
+                core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+/// Raw Ethernet watchdog I/O
+///
+/// Receives frames with the configured EtherType on the interface, frames sent by the host itself
+/// are ignored. Requires CAP_NET_RAW.
+#[allow(clippy::module_name_repetitions)]
+pub struct EthernetIo {
+    socket: OwnedFd,
+}
+
+impl EthernetIo {
+    /// creates a new raw Ethernet watchdog I/O
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn create(interface: &str, ethertype: u16, timeout: Duration) -> Result<Self> {
+        let (socket, _) = open(interface, ethertype)?;
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: libc::suseconds_t::from(timeout.subsec_micros()),
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let res = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                core::ptr::addr_of!(tv).cast(),
+                core::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { socket })
+    }
+    /// receives a frame payload, returns the first byte or None for outgoing/empty frames
+    fn recv(&self, flags: libc::c_int) -> Result<Option<u8>> {
+        let mut buf = [0u8; MAX_FRAME_PAYLOAD];
+        let mut addr: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+        #[allow(clippy::cast_possible_truncation)]
+        let mut addr_len = core::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+        let res = unsafe {
+            libc::recvfrom(
+                self.socket.as_raw_fd(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                flags,
+                core::ptr::addr_of_mut!(addr).cast(),
+                &mut addr_len,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        if res == 0 || addr.sll_pkttype == libc::PACKET_OUTGOING {
+            return Ok(None);
+        }
+        Ok(Some(buf[0]))
+    }
+}
+
+impl WatchdogIo for EthernetIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        loop {
+            match self.recv(0) {
+                Ok(Some(b)) => return Ok(Edge::from(b)),
+                Ok(None) => {}
+                Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.recv(libc::MSG_DONTWAIT).is_ok() {}
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+
+use super::WatchdogIo;
+
+/// eventfd client
+///
+/// Each beat increments the eventfd counter. The client is usually obtained with
+/// [`EventFdIo::heart`] or created from an inherited descriptor.
+#[allow(clippy::module_name_repetitions)]
+pub struct EventFdHeart {
+    fd: OwnedFd,
+}
+
+impl EventFdHeart {
+    /// creates a new eventfd client from an existing eventfd descriptor
+    pub fn from_fd(fd: OwnedFd) -> Self {
+        Self { fd }
+    }
+}
+
+impl Heart for EventFdHeart {
+    fn beat(&self) -> Result<()> {
+        let value: u64 = 1;
+        let res = unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                core::ptr::addr_of!(value).cast(),
+                core::mem::size_of::<u64>(),
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+/// eventfd watchdog I/O
+///
+/// As eventfd carries no edge information, every received beat is reported as the expected edge,
+/// so only timeouts and window violations are detected.
+#[allow(clippy::module_name_repetitions)]
+pub struct EventFdIo {
+    fd: OwnedFd,
+    timeout: libc::timespec,
+}
+
+impl EventFdIo {
+    /// creates a new eventfd watchdog I/O
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn create(timeout: Duration) -> Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            timeout: libc::timespec {
+                tv_sec: timeout.as_secs() as libc::time_t,
+                tv_nsec: libc::c_long::from(timeout.subsec_nanos()),
+            },
+        })
+    }
+    /// creates a client for the watchdog (the descriptor is duplicated). For other processes the
+    /// descriptor can be inherited or passed via a Unix socket (see [`AsFd`])
+    pub fn heart(&self) -> Result<EventFdHeart> {
+        Ok(EventFdHeart::from_fd(self.fd.try_clone()?))
+    }
+    /// reads and resets the counter, returns false if there were no beats
+    fn read(&self) -> Result<bool> {
+        let mut value: u64 = 0;
+        let res = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                core::ptr::addr_of_mut!(value).cast(),
+                core::mem::size_of::<u64>(),
+            )
+        };
+        if res < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::WouldBlock {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        Ok(value > 0)
+    }
+}
+
+impl AsFd for EventFdIo {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl WatchdogIo for EventFdIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        let mut pfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            let res = unsafe {
+                libc::ppoll(
+                    core::ptr::addr_of_mut!(pfd),
+                    1,
+                    &self.timeout,
+                    core::ptr::null(),
+                )
+            };
+            if res < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(e.into());
+            }
+            if res == 0 {
+                return Err(Error::Timeout);
+            }
+            if self.read()? {
+                return Ok(expected);
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.read()?;
+        Ok(())
+    }
+}
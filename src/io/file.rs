@@ -0,0 +1,186 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    ffi::{CString, OsStr, OsString},
+    fs::{File, OpenOptions},
+    io::Read,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::{
+            ffi::OsStrExt,
+            fs::{FileExt, OpenOptionsExt},
+        },
+    },
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::WatchdogIo;
+
+const EVENT_HEADER_SIZE: usize = core::mem::size_of::<libc::inotify_event>();
+const EVENT_BUFFER_SIZE: usize = 4096;
+
+/// File client
+///
+/// The edge is written to the first byte of a file, so the heart and the watchdog can be located
+/// in different containers, sharing a volume.
+#[allow(clippy::module_name_repetitions)]
+pub struct FileHeart {
+    file: File,
+    next: AtomicBool,
+}
+
+impl FileHeart {
+    /// creates a new file client, the file is created if it does not exist
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o660)
+            .open(path)?;
+        Ok(Self {
+            file,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for FileHeart {
+    fn beat(&self) -> Result<()> {
+        self.file.write_all_at(
+            &[Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8],
+            0,
+        )?;
+        Ok(())
+    }
+}
+
+/// File watchdog I/O
+///
+/// Blocks on inotify events of the file directory instead of polling, so the file may be created
+/// (or replaced) after the I/O has been started.
+#[allow(clippy::module_name_repetitions)]
+pub struct FileIo {
+    inotify: OwnedFd,
+    path: PathBuf,
+    name: OsString,
+    timeout: Duration,
+}
+
+impl FileIo {
+    /// creates a new file watchdog I/O, the file directory must exist
+    pub fn create<P: AsRef<Path>>(path: P, timeout: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::failed("invalid file path"))?
+            .to_owned();
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let inotify = unsafe { OwnedFd::from_raw_fd(fd) };
+        let c_dir = CString::new(dir.as_os_str().as_bytes()).map_err(Error::failed)?;
+        if unsafe {
+            libc::inotify_add_watch(
+                inotify.as_raw_fd(),
+                c_dir.as_ptr(),
+                libc::IN_MODIFY | libc::IN_CLOSE_WRITE | libc::IN_CREATE | libc::IN_MOVED_TO,
+            )
+        } < 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self {
+            inotify,
+            path,
+            name,
+            timeout,
+        })
+    }
+    /// waits for events, returns true if the file has been changed
+    fn wait(&self, timeout: Duration) -> Result<bool> {
+        let mut pfd = libc::pollfd {
+            fd: self.inotify.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        let res = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+        if res < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        if res == 0 {
+            return Err(Error::Timeout);
+        }
+        self.read_events()
+    }
+    /// reads all pending events, returns true if any of them is related to the file
+    fn read_events(&self) -> Result<bool> {
+        let mut buf = [0u8; EVENT_BUFFER_SIZE];
+        let mut changed = false;
+        loop {
+            let res =
+                unsafe { libc::read(self.inotify.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+            if res < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::WouldBlock {
+                    return Ok(changed);
+                }
+                return Err(e.into());
+            }
+            let len = usize::try_from(res).map_err(Error::failed)?;
+            let mut pos = 0;
+            while pos + EVENT_HEADER_SIZE <= len {
+                let event: libc::inotify_event =
+                    unsafe { core::ptr::read_unaligned(buf[pos..].as_ptr().cast()) };
+                let name_len = event.len as usize;
+                let name = &buf[pos + EVENT_HEADER_SIZE..pos + EVENT_HEADER_SIZE + name_len];
+                // the name is padded with zeros
+                let name = name.split(|b| *b == 0).next().unwrap_or_default();
+                changed |= OsStr::from_bytes(name) == self.name;
+                pos += EVENT_HEADER_SIZE + name_len;
+            }
+        }
+    }
+    fn read_edge(&self) -> Option<Edge> {
+        let mut buf = [0];
+        let mut file = File::open(&self.path).ok()?;
+        (file.read(&mut buf).ok()? == 1).then(|| Edge::from(buf[0]))
+    }
+}
+
+impl WatchdogIo for FileIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        let now = Instant::now();
+        loop {
+            let remaining = self.timeout.saturating_sub(now.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::Timeout);
+            }
+            if self.wait(remaining)? {
+                if let Some(edge) = self.read_edge() {
+                    if edge == expected {
+                        return Ok(edge);
+                    }
+                }
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.read_events()?;
+        Ok(())
+    }
+}
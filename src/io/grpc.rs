@@ -0,0 +1,240 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::{channel, pi::Mutex};
+use tokio::{
+    runtime::{self, Runtime},
+    sync::mpsc,
+};
+use tonic::{
+    codec::{ProstCodec, Streaming},
+    codegen::{
+        http, tokio_stream::wrappers::ReceiverStream, Body, BoxFuture, Context, Poll, Service,
+        StdError,
+    },
+    server::{ClientStreamingService, NamedService},
+    transport::{server::TcpIncoming, Endpoint, Server},
+    Request, Response, Status,
+};
+
+use super::WatchdogIo;
+
+const SERVICE_NAME: &str = "heartbeat.Heartbeat";
+const BEAT_PATH: &str = "/heartbeat.Heartbeat/Beat";
+const BEAT_QUEUE_SIZE: usize = 16;
+
+/// A heartbeat message (see `proto/heartbeat.proto` in the crate sources)
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BeatMessage {
+    /// 1 - rising, 0 - falling
+    #[prost(uint32, tag = "1")]
+    pub edge: u32,
+}
+
+/// A heartbeat stream acknowledgement, sent when the stream is finished
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BeatAck {}
+
+fn runtime(name: &str) -> Result<Runtime> {
+    runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name(name)
+        .enable_all()
+        .build()
+        .map_err(Error::from)
+}
+
+/// gRPC client
+///
+/// The client maintains a client-streaming `Beat` call, each edge is sent as a separate message.
+/// If the call is finished (e.g. the connection is lost), the client tries to reconnect on the
+/// next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct GrpcHeart {
+    endpoint: Endpoint,
+    timeout: Duration,
+    tx: Mutex<Option<mpsc::Sender<BeatMessage>>>,
+    next: AtomicBool,
+    // must be dropped last, the calls are processed by the runtime
+    runtime: Runtime,
+}
+
+impl GrpcHeart {
+    /// creates a new gRPC client, the url is the watchdog service URL (e.g.
+    /// "http://10.0.0.1:50051"). The timeout is used as the connect and the keep-alive timeout
+    pub fn create(url: &str, timeout: Duration) -> Result<Self> {
+        let endpoint = Endpoint::from_shared(url.to_owned())
+            .map_err(Error::failed)?
+            .connect_timeout(timeout)
+            .http2_keep_alive_interval(timeout)
+            .keep_alive_timeout(timeout)
+            .tcp_nodelay(true);
+        let heart = Self {
+            endpoint,
+            timeout,
+            tx: Mutex::new(None),
+            next: AtomicBool::new(true),
+            runtime: runtime("grpc-heart")?,
+        };
+        heart.tx.lock().replace(heart.connect()?);
+        Ok(heart)
+    }
+    fn connect(&self) -> Result<mpsc::Sender<BeatMessage>> {
+        self.runtime.block_on(async {
+            let channel = tokio::time::timeout(self.timeout, self.endpoint.connect())
+                .await
+                .map_err(|_| Error::Timeout)?
+                .map_err(Error::failed)?;
+            let mut client = tonic::client::Grpc::new(channel);
+            let (tx, rx) = mpsc::channel(BEAT_QUEUE_SIZE);
+            tokio::spawn(async move {
+                // the receiver is dropped when the call is finished
+                if client.ready().await.is_ok() {
+                    let _ = client
+                        .client_streaming::<_, _, BeatAck, _>(
+                            Request::new(ReceiverStream::new(rx)),
+                            http::uri::PathAndQuery::from_static(BEAT_PATH),
+                            ProstCodec::default(),
+                        )
+                        .await;
+                }
+            });
+            Ok(tx)
+        })
+    }
+}
+
+impl Heart for GrpcHeart {
+    fn beat(&self) -> Result<()> {
+        let mut tx = self.tx.lock();
+        if tx.as_ref().map_or(true, mpsc::Sender::is_closed) {
+            tx.replace(self.connect()?);
+        }
+        let rising = self.next.fetch_xor(true, Ordering::Relaxed);
+        if let Err(e) = tx.as_ref().unwrap().try_send(BeatMessage {
+            edge: u32::from(rising),
+        }) {
+            tx.take();
+            return Err(Error::failed(e));
+        }
+        Ok(())
+    }
+}
+
+/// The `heartbeat.Heartbeat` service, feeds the received edges to the watchdog I/O
+#[derive(Clone)]
+struct HeartbeatService {
+    tx: channel::Sender<Edge, RawMutex, Condvar>,
+}
+
+impl HeartbeatService {
+    async fn beat(
+        self,
+        mut stream: Streaming<BeatMessage>,
+    ) -> core::result::Result<Response<BeatAck>, Status> {
+        while let Some(message) = stream.message().await? {
+            // if the queue is full, the watchdog is not running
+            if let Err(rtsc::Error::ChannelClosed) = self.tx.try_send(Edge::from(message.edge != 0))
+            {
+                return Err(Status::unavailable("watchdog stopped"));
+            }
+        }
+        Ok(Response::new(BeatAck {}))
+    }
+}
+
+impl NamedService for HeartbeatService {
+    const NAME: &'static str = SERVICE_NAME;
+}
+
+impl<B> Service<http::Request<B>> for HeartbeatService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = core::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<core::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        if req.uri().path() != BEAT_PATH {
+            return Box::pin(async { Ok(Status::unimplemented("").into_http()) });
+        }
+        let call = BeatCall(self.clone());
+        Box::pin(async move {
+            let mut grpc = tonic::server::Grpc::new(ProstCodec::default());
+            Ok(grpc.client_streaming(call, req).await)
+        })
+    }
+}
+
+/// The `Beat` call handler
+struct BeatCall(HeartbeatService);
+
+impl ClientStreamingService<BeatMessage> for BeatCall {
+    type Response = BeatAck;
+    type Future = BoxFuture<Response<BeatAck>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<BeatMessage>>) -> Self::Future {
+        Box::pin(self.0.clone().beat(request.into_inner()))
+    }
+}
+
+/// gRPC watchdog I/O
+///
+/// Runs a gRPC server with the `heartbeat.Heartbeat` service (see `proto/heartbeat.proto` in the
+/// crate sources). Beat streams are processed by a background runtime.
+#[allow(clippy::module_name_repetitions)]
+pub struct GrpcIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+    // the server is stopped when the I/O is dropped
+    _runtime: Runtime,
+}
+
+impl GrpcIo {
+    /// creates a new gRPC watchdog I/O
+    pub fn create<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<Self> {
+        let addr: SocketAddr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("no address resolved"))?;
+        let runtime = runtime("grpc-watchdog")?;
+        let incoming = runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            TcpIncoming::from_listener(listener, true, None).map_err(Error::failed)
+        })?;
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        let server = Server::builder()
+            .http2_keepalive_interval(Some(timeout))
+            .http2_keepalive_timeout(Some(timeout))
+            .add_service(HeartbeatService { tx })
+            .serve_with_incoming(incoming);
+        runtime.spawn(server);
+        Ok(Self {
+            rx,
+            timeout,
+            _runtime: runtime,
+        })
+    }
+}
+
+impl WatchdogIo for GrpcIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
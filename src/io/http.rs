@@ -0,0 +1,654 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::{channel, pi::Mutex};
+use socket2::SockRef;
+
+use super::WatchdogIo;
+
+const MAX_HEADERS: usize = 64;
+const MAX_LINE: usize = 8192;
+const MAX_BODY: usize = 64;
+const MAX_CONNECTIONS: usize = 16;
+const BEAT_QUEUE_SIZE: usize = 16;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// HTTP message head
+struct Head {
+    start_line: String,
+    content_length: usize,
+    transfer_encoding: Option<String>,
+}
+
+impl Head {
+    /// Is the body chunked, only the chunked transfer encoding is supported
+    fn chunked(&self) -> Result<bool> {
+        match self.transfer_encoding {
+            None => Ok(false),
+            Some(ref v) if v.eq_ignore_ascii_case("chunked") => Ok(true),
+            Some(_) => Err(Error::failed("unsupported HTTP transfer encoding")),
+        }
+    }
+}
+
+/// Reads a line of up to [`MAX_LINE`] bytes, returns 0 if the connection is closed
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<usize> {
+    let n = reader.by_ref().take(MAX_LINE as u64).read_line(line)?;
+    if n == MAX_LINE && !line.ends_with('\n') {
+        return Err(Error::failed("HTTP line too long"));
+    }
+    Ok(n)
+}
+
+/// Reads the HTTP message head (start line and headers)
+fn read_head<R: BufRead>(reader: &mut R) -> Result<Option<Head>> {
+    let mut start_line = String::new();
+    if read_line(reader, &mut start_line)? == 0 {
+        return Ok(None);
+    }
+    let mut content_length = 0;
+    let mut transfer_encoding = None;
+    for _ in 0..MAX_HEADERS {
+        let mut line = String::new();
+        if read_line(reader, &mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            return Ok(Some(Head {
+                start_line: start_line.trim_end().to_owned(),
+                content_length,
+                transfer_encoding,
+            }));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(Error::failed)?;
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                transfer_encoding = Some(value.trim().to_owned());
+            }
+        }
+    }
+    Err(Error::failed("too many HTTP headers"))
+}
+
+/// Reads a chunked message body, returns `false` if the body exceeds the limit (the rest of the
+/// body is not read)
+fn read_chunked<R: BufRead>(reader: &mut R, body: &mut Vec<u8>, limit: usize) -> Result<bool> {
+    loop {
+        let mut line = String::new();
+        read_line(reader, &mut line)?;
+        let size = line.trim_end().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size.trim(), 16).map_err(Error::failed)?;
+        if size == 0 {
+            // skip the trailers
+            loop {
+                line.clear();
+                if read_line(reader, &mut line)? == 0 || line.trim_end().is_empty() {
+                    return Ok(true);
+                }
+            }
+        }
+        if size > limit - body.len() {
+            return Ok(false);
+        }
+        if reader.by_ref().take(size as u64).read_to_end(body)? < size {
+            return Err(Error::failed("HTTP chunk truncated"));
+        }
+        line.clear();
+        read_line(reader, &mut line)?;
+    }
+}
+
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Kept-alive client connection
+struct Connection {
+    reader: BufReader<Box<dyn Stream>>,
+    // the underlying socket, used to check if the connection has been closed by the server
+    sock: TcpStream,
+}
+
+impl Connection {
+    fn is_closed(&self) -> bool {
+        if self.sock.set_nonblocking(true).is_err() {
+            return true;
+        }
+        let closed = match self.sock.peek(&mut [0]) {
+            Ok(n) => n == 0,
+            Err(e) => e.kind() != std::io::ErrorKind::WouldBlock,
+        };
+        self.sock.set_nonblocking(false).is_err() || closed
+    }
+}
+
+/// Minimal HTTP/1.1 client, the connection is kept alive between requests
+pub(crate) struct HttpClient {
+    host: String,
+    path: String,
+    tls: bool,
+    #[cfg(feature = "tls")]
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+    headers: String,
+    timeout: Duration,
+    conn: Mutex<Option<Connection>>,
+}
+
+impl HttpClient {
+    /// creates a new HTTP client, the timeout is used for both connection and requests. Only
+    /// plain-text "http://" URLs are supported
+    pub(crate) fn create(url: &str, timeout: Duration) -> Result<Self> {
+        Self::parse(url, timeout, false)
+    }
+    /// creates a new HTTP client which also supports "https://" URLs (the `tls` feature and a TLS
+    /// configuration are required, see [`HttpClient::set_tls_config`])
+    #[cfg(feature = "webhook")]
+    pub(crate) fn create_https(url: &str, timeout: Duration) -> Result<Self> {
+        Self::parse(url, timeout, true)
+    }
+    fn parse(url: &str, timeout: Duration, https: bool) -> Result<Self> {
+        let (rest, tls) = if let Some(rest) = url.strip_prefix("http://") {
+            (rest, false)
+        } else if let Some(rest) = url.strip_prefix("https://").filter(|_| https) {
+            if cfg!(not(feature = "tls")) {
+                return Err(Error::failed("HTTPS requires the tls feature"));
+            }
+            (rest, true)
+        } else {
+            return Err(Error::failed("unsupported URL scheme"));
+        };
+        let (host, path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, "/"),
+        };
+        Ok(Self {
+            host: host.to_owned(),
+            path: path.to_owned(),
+            tls,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            headers: String::new(),
+            timeout,
+            conn: Mutex::new(None),
+        })
+    }
+    /// sets the TLS configuration for "https://" URLs
+    #[cfg(feature = "tls")]
+    #[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+    pub(crate) fn set_tls_config(&mut self, config: Arc<rustls::ClientConfig>) {
+        self.tls_config = Some(config);
+    }
+    /// adds a header, which is sent with every request
+    #[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+    pub(crate) fn add_header(&mut self, name: &str, value: &str) {
+        use std::fmt::Write as _;
+        let _ = write!(self.headers, "{}: {}\r\n", name, value);
+    }
+    fn connect(&self) -> Result<Connection> {
+        let addr = if self.host.contains(':') {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, if self.tls { 443 } else { 80 })
+        };
+        let mut result = Err(Error::failed("unable to resolve the host"));
+        for addr in addr.to_socket_addrs()? {
+            result = TcpStream::connect_timeout(&addr, self.timeout).map_err(Into::into);
+            if result.is_ok() {
+                break;
+            }
+        }
+        let stream = result?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        stream.set_nodelay(true)?;
+        let sock = stream.try_clone()?;
+        #[cfg(feature = "tls")]
+        if self.tls {
+            let config = self
+                .tls_config
+                .clone()
+                .ok_or_else(|| Error::failed("HTTPS requires a TLS configuration"))?;
+            let server_name = self.host.split(':').next().unwrap_or_default().to_owned();
+            let server_name =
+                rustls::pki_types::ServerName::try_from(server_name).map_err(Error::failed)?;
+            let conn = rustls::ClientConnection::new(config, server_name).map_err(Error::failed)?;
+            return Ok(Connection {
+                reader: BufReader::new(Box::new(rustls::StreamOwned::new(conn, stream))),
+                sock,
+            });
+        }
+        Ok(Connection {
+            reader: BufReader::new(Box::new(stream)),
+            sock,
+        })
+    }
+    fn send(&self, conn: &mut Connection, content_type: &str, body: &[u8]) -> Result<()> {
+        let head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}\r\n",
+            self.path,
+            self.host,
+            content_type,
+            body.len(),
+            self.headers
+        );
+        let stream = conn.reader.get_mut();
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+        Ok(())
+    }
+    fn receive(conn: &mut Connection) -> Result<Vec<u8>> {
+        let stream = &mut conn.reader;
+        let head = read_head(stream)?.ok_or_else(|| Error::failed("connection closed"))?;
+        let mut response = Vec::new();
+        if head.chunked()? {
+            read_chunked(stream, &mut response, usize::MAX)?;
+        } else {
+            stream
+                .by_ref()
+                .take(head.content_length as u64)
+                .read_to_end(&mut response)?;
+        }
+        let status = head
+            .start_line
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| Error::failed("invalid HTTP response"))?;
+        if !status.starts_with('2') {
+            return Err(Error::failed(format!("HTTP error {}", status)));
+        }
+        Ok(response)
+    }
+    /// sends a POST request, returns the response body
+    ///
+    /// The request is sent again with a new connection only if the kept-alive one has failed
+    /// before the request has been written, a request which may have been delivered is never
+    /// repeated
+    pub(crate) fn post(&self, content_type: &str, body: &[u8]) -> Result<Vec<u8>> {
+        let mut conn = self.conn.lock();
+        let mut sent = false;
+        if let Some(c) = conn.as_mut() {
+            sent = !c.is_closed() && self.send(c, content_type, body).is_ok();
+        }
+        if !sent {
+            conn.take();
+            let mut c = self.connect()?;
+            self.send(&mut c, content_type, body)?;
+            conn.replace(c);
+        }
+        let result = Self::receive(conn.as_mut().unwrap());
+        if result.is_err() {
+            conn.take();
+        }
+        result
+    }
+}
+
+/// HTTP client
+///
+/// Edges are sent as POST requests with a single-byte body, the connection is kept alive between
+/// beats. Only plain-text "http://" URLs are supported.
+#[allow(clippy::module_name_repetitions)]
+pub struct HttpHeart {
+    client: HttpClient,
+    next: AtomicBool,
+}
+
+impl HttpHeart {
+    /// creates a new HTTP client, the timeout is used for both connection and requests
+    pub fn create(url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: HttpClient::create(url, timeout)?,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for HttpHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        self.client.post("text/plain", &[edge as u8])?;
+        Ok(())
+    }
+}
+
+/// HTTP watchdog I/O
+///
+/// Runs a minimal embedded HTTP server in background threads. POST requests to the configured path
+/// with "+" (rising) or "." (falling) body are converted to edges, both "Content-Length" and
+/// chunked request bodies are supported.
+///
+/// Up to 16 connections are served at a time, when a new one is accepted, the oldest one is closed
+/// (e.g. left by a heart which has reconnected). The server and the connections are closed when the
+/// I/O is dropped.
+#[allow(clippy::module_name_repetitions)]
+pub struct HttpIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+    listener: TcpListener,
+    stop: Arc<AtomicBool>,
+    connections: Arc<Mutex<VecDeque<(u64, TcpStream)>>>,
+    server: Option<thread::JoinHandle<()>>,
+}
+
+impl HttpIo {
+    /// creates a new HTTP watchdog I/O, listening on the given address
+    pub fn create<A: ToSocketAddrs>(addr: A, path: &str, timeout: Duration) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let server_listener = listener.try_clone()?;
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        let path = path.to_owned();
+        let stop = Arc::new(AtomicBool::new(false));
+        let server_stop = stop.clone();
+        let connections: Arc<Mutex<VecDeque<(u64, TcpStream)>>> = <_>::default();
+        let server_connections = connections.clone();
+        let server = thread::Builder::new()
+            .name("http-watchdog".to_owned())
+            .spawn(move || {
+                for (id, stream) in (0..).zip(server_listener.incoming()) {
+                    if server_stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok((stream, sock)) = stream.and_then(|s| {
+                        let sock = s.try_clone()?;
+                        Ok((s, sock))
+                    }) else {
+                        continue;
+                    };
+                    {
+                        let mut active = server_connections.lock();
+                        if active.len() >= MAX_CONNECTIONS {
+                            if let Some((_, oldest)) = active.pop_front() {
+                                let _ = oldest.shutdown(Shutdown::Both);
+                            }
+                        }
+                        active.push_back((id, sock));
+                    }
+                    let tx = tx.clone();
+                    let path = path.clone();
+                    let active = server_connections.clone();
+                    let close = move || active.lock().retain(|(i, _)| *i != id);
+                    let close_failed = close.clone();
+                    if thread::Builder::new()
+                        .name("http-watchdog-c".to_owned())
+                        .spawn(move || {
+                            let _ = handle_connection(stream, &path, &tx);
+                            close();
+                        })
+                        .is_err()
+                    {
+                        close_failed();
+                    }
+                }
+            })?;
+        Ok(Self {
+            rx,
+            timeout,
+            listener,
+            stop,
+            connections,
+            server: Some(server),
+        })
+    }
+}
+
+impl Drop for HttpIo {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // shutting the listener down wakes up the accept loop on Linux, otherwise the loop is woken
+        // up with a connection
+        let mut woken = SockRef::from(&self.listener)
+            .shutdown(Shutdown::Both)
+            .is_ok();
+        if !woken {
+            if let Ok(mut addr) = self.listener.local_addr() {
+                if addr.ip().is_unspecified() {
+                    addr.set_ip(if addr.is_ipv4() {
+                        Ipv4Addr::LOCALHOST.into()
+                    } else {
+                        Ipv6Addr::LOCALHOST.into()
+                    });
+                }
+                woken = TcpStream::connect_timeout(&addr, self.timeout).is_ok();
+            }
+        }
+        if woken {
+            if let Some(server) = self.server.take() {
+                let _ = server.join();
+            }
+        }
+        for (_, sock) in self.connections.lock().drain(..) {
+            let _ = sock.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    path: &str,
+    tx: &channel::Sender<Edge, RawMutex, Condvar>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream);
+    while let Some(head) = read_head(&mut reader)? {
+        let mut body = Vec::new();
+        let complete = match head.chunked() {
+            Ok(true) => read_chunked(&mut reader, &mut body, MAX_BODY)?,
+            Ok(false) if head.content_length <= MAX_BODY => {
+                body.resize(head.content_length, 0);
+                reader.read_exact(&mut body)?;
+                true
+            }
+            Ok(false) => false,
+            Err(_) => {
+                reader.get_mut().write_all(
+                    b"HTTP/1.1 501 Not Implemented\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )?;
+                return Ok(());
+            }
+        };
+        if !complete {
+            reader.get_mut().write_all(
+                b"HTTP/1.1 413 Payload Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )?;
+            return Ok(());
+        }
+        let mut parts = head.start_line.split_whitespace();
+        let response: &[u8] = match (parts.next(), parts.next()) {
+            (Some("POST"), Some(p)) if p == path => match body.first() {
+                Some(b) => {
+                    // if the queue is full, the watchdog is not running
+                    let _ = tx.try_send(Edge::from(*b));
+                    b"HTTP/1.1 204 No Content\r\n\r\n"
+                }
+                None => b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n",
+            },
+            (Some(_), Some(p)) if p == path => {
+                b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n"
+            }
+            _ => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+        };
+        reader.get_mut().write_all(response)?;
+    }
+    Ok(())
+}
+
+impl WatchdogIo for HttpIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    const PATH: &str = "/beat";
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn head(s: &str) -> Result<Option<Head>> {
+        read_head(&mut Cursor::new(s.as_bytes()))
+    }
+
+    fn chunked(s: &str, limit: usize) -> Result<(bool, Vec<u8>)> {
+        let mut body = Vec::new();
+        let complete = read_chunked(&mut Cursor::new(s.as_bytes()), &mut body, limit)?;
+        Ok((complete, body))
+    }
+
+    fn connect(io: &HttpIo) -> BufReader<TcpStream> {
+        let stream = TcpStream::connect(io.listener.local_addr().unwrap()).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        BufReader::new(stream)
+    }
+
+    fn request(conn: &mut BufReader<TcpStream>, request: &str) -> String {
+        conn.get_mut().write_all(request.as_bytes()).unwrap();
+        read_head(conn).unwrap().unwrap().start_line
+    }
+
+    #[test]
+    fn test_read_head() {
+        let h = head("POST /beat HTTP/1.1\r\nHost: x\r\ncontent-length: 1\r\n\r\n+")
+            .unwrap()
+            .unwrap();
+        assert_eq!(h.start_line, "POST /beat HTTP/1.1");
+        assert_eq!(h.content_length, 1);
+        assert!(!h.chunked().unwrap());
+        let h = head("HTTP/1.1 200 OK\nTransfer-Encoding: Chunked\n\n")
+            .unwrap()
+            .unwrap();
+        assert!(h.chunked().unwrap());
+        let h = head("POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n")
+            .unwrap()
+            .unwrap();
+        assert!(h.chunked().is_err());
+    }
+
+    #[test]
+    fn test_read_head_invalid() {
+        // the connection is closed
+        assert!(head("").unwrap().is_none());
+        assert!(head("POST / HTTP/1.1\r\nHost: x\r\n").unwrap().is_none());
+        assert!(head("POST / HTTP/1.1\r\nContent-Length: x\r\n\r\n").is_err());
+        assert!(head(&format!("POST / HTTP/1.1\r\n{}\r\n", "x".repeat(MAX_LINE))).is_err());
+        let headers = "X-Header: 1\r\n".repeat(MAX_HEADERS);
+        assert!(head(&format!("POST / HTTP/1.1\r\n{}\r\n", headers)).is_err());
+    }
+
+    #[test]
+    fn test_read_chunked() {
+        assert_eq!(
+            chunked("1\r\n+\r\n0\r\n\r\n", MAX_BODY).unwrap(),
+            (true, b"+".to_vec())
+        );
+        assert_eq!(
+            chunked(
+                "3;ext=1\r\nabc\r\n2\r\nde\r\n0\r\nTrailer: x\r\n\r\n",
+                MAX_BODY
+            )
+            .unwrap(),
+            (true, b"abcde".to_vec())
+        );
+        assert_eq!(
+            chunked("a\r\n0123456789\r\n0\r\n\r\n", MAX_BODY).unwrap(),
+            (true, b"0123456789".to_vec())
+        );
+        assert!(!chunked("41\r\n", MAX_BODY).unwrap().0);
+        assert!(!chunked("3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n", 4).unwrap().0);
+        assert!(chunked("x\r\n", MAX_BODY).is_err());
+        assert!(chunked("", MAX_BODY).is_err());
+        assert!(chunked("5\r\nab", MAX_BODY).is_err());
+    }
+
+    #[test]
+    fn test_server() {
+        let io = HttpIo::create("127.0.0.1:0", PATH, TIMEOUT).unwrap();
+        let mut conn = connect(&io);
+        let post = |body: &str| {
+            format!(
+                "POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                PATH,
+                body.len(),
+                body
+            )
+        };
+        assert_eq!(request(&mut conn, &post("+")), "HTTP/1.1 204 No Content");
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(
+            request(
+                &mut conn,
+                &format!(
+                    "POST {} HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n1\r\n.\r\n0\r\n\r\n",
+                    PATH
+                )
+            ),
+            "HTTP/1.1 204 No Content"
+        );
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+        assert_eq!(request(&mut conn, &post("")), "HTTP/1.1 400 Bad Request");
+        assert_eq!(
+            request(&mut conn, "GET /beat HTTP/1.1\r\n\r\n"),
+            "HTTP/1.1 405 Method Not Allowed"
+        );
+        assert_eq!(
+            request(&mut conn, "POST / HTTP/1.1\r\nContent-Length: 1\r\n\r\n+"),
+            "HTTP/1.1 404 Not Found"
+        );
+        assert_eq!(
+            request(
+                &mut conn,
+                &format!("POST {} HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n", PATH)
+            ),
+            "HTTP/1.1 501 Not Implemented"
+        );
+        let mut conn = connect(&io);
+        assert_eq!(
+            request(&mut conn, &post(&"+".repeat(MAX_BODY + 1))),
+            "HTTP/1.1 413 Payload Too Large"
+        );
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let io = HttpIo::create("127.0.0.1:0", PATH, TIMEOUT).unwrap();
+        let post = format!("POST {} HTTP/1.1\r\nContent-Length: 1\r\n\r\n+", PATH);
+        let mut connections: Vec<_> = (0..=MAX_CONNECTIONS).map(|_| connect(&io)).collect();
+        // the newest connection is served, the oldest one is closed
+        assert_eq!(
+            request(connections.last_mut().unwrap(), &post),
+            "HTTP/1.1 204 No Content"
+        );
+        let _ = connections[0].get_mut().write_all(post.as_bytes());
+        assert!(read_head(&mut connections[0]).map_or(true, |h| h.is_none()));
+        assert_eq!(
+            request(&mut connections[1], &post),
+            "HTTP/1.1 204 No Content"
+        );
+        drop(io);
+        // the connections are closed with the server
+        assert!(read_head(&mut connections[1]).map_or(true, |h| h.is_none()));
+    }
+}
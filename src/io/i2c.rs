@@ -0,0 +1,194 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    fs::{File, OpenOptions},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::time::interval;
+
+use super::WatchdogIo;
+
+const I2C_RDWR: libc::Ioctl = 0x0707;
+const I2C_M_RD: u16 = 0x0001;
+
+/// i2c_msg (linux/i2c.h)
+#[repr(C)]
+struct I2cMsg {
+    addr: u16,
+    flags: u16,
+    len: u16,
+    buf: *mut u8,
+}
+
+/// i2c_rdwr_ioctl_data (linux/i2c-dev.h)
+#[repr(C)]
+struct I2cRdwrData {
+    msgs: *mut I2cMsg,
+    nmsgs: u32,
+}
+
+/// i2c-dev bus device with combined (repeated start) transfers
+struct Bus {
+    file: File,
+    address: u16,
+}
+
+impl Bus {
+    fn open(path: &Path, address: u16) -> Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self { file, address })
+    }
+    fn transfer(&self, msgs: &mut [I2cMsg]) -> Result<()> {
+        let mut data = I2cRdwrData {
+            msgs: msgs.as_mut_ptr(),
+            nmsgs: u32::try_from(msgs.len()).map_err(Error::failed)?,
+        };
+        let res = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                I2C_RDWR,
+                core::ptr::addr_of_mut!(data),
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+    fn write_register(&self, register: u8, value: u8) -> Result<()> {
+        let mut buf = [register, value];
+        self.transfer(&mut [I2cMsg {
+            addr: self.address,
+            flags: 0,
+            len: 2,
+            buf: buf.as_mut_ptr(),
+        }])
+    }
+    fn read_register(&self, register: u8) -> Result<u8> {
+        let mut reg = [register];
+        let mut value = [0];
+        self.transfer(&mut [
+            I2cMsg {
+                addr: self.address,
+                flags: 0,
+                len: 1,
+                buf: reg.as_mut_ptr(),
+            },
+            I2cMsg {
+                addr: self.address,
+                flags: I2C_M_RD,
+                len: 1,
+                buf: value.as_mut_ptr(),
+            },
+        ])?;
+        Ok(value[0])
+    }
+}
+
+/// I2C client
+///
+/// Writes edges to a device register (1 - rising, 0 - falling).
+#[allow(clippy::module_name_repetitions)]
+pub struct I2cHeart {
+    bus: Bus,
+    register: u8,
+    next: AtomicBool,
+}
+
+impl I2cHeart {
+    /// creates a new I2C client, the bus is an i2c-dev device (e.g. "/dev/i2c-1"), the address is
+    /// a 7-bit device address
+    pub fn create<P: AsRef<Path>>(bus: P, address: u16, register: u8) -> Result<Self> {
+        Ok(Self {
+            bus: Bus::open(bus.as_ref(), address)?,
+            register,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for I2cHeart {
+    fn beat(&self) -> Result<()> {
+        self.bus.write_register(
+            self.register,
+            u8::from(self.next.fetch_xor(true, Ordering::Relaxed)),
+        )
+    }
+}
+
+/// I2C watchdog I/O configuration
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone)]
+pub struct I2cConfig {
+    bus: PathBuf,
+    address: u16,
+    register: u8,
+    pull_interval: Duration,
+}
+
+impl I2cConfig {
+    /// creates a new I2C watchdog I/O configuration
+    pub fn new<P: AsRef<Path>>(
+        bus: P,
+        address: u16,
+        register: u8,
+        pull_interval: Duration,
+    ) -> Self {
+        Self {
+            bus: bus.as_ref().to_path_buf(),
+            address,
+            register,
+            pull_interval,
+        }
+    }
+}
+
+/// I2C watchdog I/O
+///
+/// Polls a device register, any non-zero value is considered as the rising edge. Bus errors (e.g.
+/// the device does not respond) are reported as timeouts.
+#[allow(clippy::module_name_repetitions)]
+pub struct I2cIo {
+    bus: Bus,
+    register: u8,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl I2cIo {
+    /// creates a new I2C watchdog I/O
+    pub fn create(config: &I2cConfig, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            bus: Bus::open(&config.bus, config.address)?,
+            register: config.register,
+            timeout,
+            pull_interval: config.pull_interval,
+        })
+    }
+}
+
+impl WatchdogIo for I2cIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        let now = Instant::now();
+        for _ in interval(self.pull_interval) {
+            if now.elapsed() > self.timeout {
+                break;
+            }
+            if let Ok(value) = self.bus.read_register(self.register) {
+                let edge = Edge::from(value != 0);
+                if edge == expected {
+                    return Ok(edge);
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
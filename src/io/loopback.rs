@@ -0,0 +1,89 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::channel;
+
+use super::WatchdogIo;
+
+const BEAT_QUEUE_SIZE: usize = 16;
+
+/// Creates a connected in-process client and watchdog I/O pair, e.g. to supervise threads inside
+/// a single binary or to test watchdog configurations without sockets
+pub fn channel_pair(timeout: Duration) -> (LoopbackHeart, LoopbackIo) {
+    let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+    (
+        LoopbackHeart {
+            tx,
+            next: AtomicBool::new(true),
+        },
+        LoopbackIo { rx, timeout },
+    )
+}
+
+/// Loopback client
+///
+/// Edges are sent to the in-process queue. If the queue is full (the watchdog is not reading), the
+/// edge is dropped.
+#[allow(clippy::module_name_repetitions)]
+pub struct LoopbackHeart {
+    tx: channel::Sender<Edge, RawMutex, Condvar>,
+    next: AtomicBool,
+}
+
+impl Heart for LoopbackHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        match self.tx.try_send(edge) {
+            Ok(()) | Err(rtsc::Error::ChannelFull) => Ok(()),
+            Err(e) => Err(Error::failed(e)),
+        }
+    }
+}
+
+/// Loopback watchdog I/O
+#[allow(clippy::module_name_repetitions)]
+pub struct LoopbackIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+}
+
+impl WatchdogIo for LoopbackIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_beats() {
+        let (heart, io) = channel_pair(TIMEOUT);
+        heart.beat().unwrap();
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (heart, io) = channel_pair(TIMEOUT);
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Timeout)));
+        // pending beats are dropped when the I/O is cleared
+        heart.beat().unwrap();
+        io.clear().unwrap();
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Timeout)));
+    }
+}
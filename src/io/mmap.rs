@@ -0,0 +1,105 @@
+use crate::{Edge, Heart, Result};
+use core::time::Duration;
+use std::{
+    fs::OpenOptions,
+    os::{fd::OwnedFd, unix::fs::OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::{poll_until, shm::Segment, WatchdogIo};
+
+/// Opens (creates if required) and maps the file
+fn open<P: AsRef<Path>>(path: P) -> Result<Segment> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .mode(0o660)
+        .open(path)?;
+    Segment::map(OwnedFd::from(file))
+}
+
+/// Memory-mapped file client
+///
+/// The edge is stored in a small file which is mapped into memory, so the heart and the watchdog
+/// can be located in different containers, sharing a volume.
+#[allow(clippy::module_name_repetitions)]
+pub struct MmapHeart {
+    segment: Segment,
+    next: AtomicBool,
+}
+
+impl MmapHeart {
+    /// creates a new memory-mapped file client, the file is created if it does not exist
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self {
+            segment: open(path)?,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for MmapHeart {
+    fn beat(&self) -> Result<()> {
+        self.segment.edge().store(
+            Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8,
+            Ordering::Release,
+        );
+        Ok(())
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+/// Memory-mapped file watchdog I/O configuration
+#[derive(Debug, Clone)]
+pub struct MmapConfig {
+    path: PathBuf,
+    pull_interval: Duration,
+}
+
+impl MmapConfig {
+    /// creates a new memory-mapped file watchdog I/O configuration
+    pub fn new<P: AsRef<Path>>(path: P, pull_interval: Duration) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            pull_interval,
+        }
+    }
+}
+
+/// Memory-mapped file watchdog I/O
+///
+/// The file is created if it does not exist and is kept when the I/O is dropped.
+#[allow(clippy::module_name_repetitions)]
+pub struct MmapIo {
+    segment: Segment,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl MmapIo {
+    /// creates a new memory-mapped file watchdog I/O
+    pub fn create(config: &MmapConfig, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            segment: open(&config.path)?,
+            timeout,
+            pull_interval: config.pull_interval,
+        })
+    }
+}
+
+impl WatchdogIo for MmapIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |_| {
+            let edge = Edge::from(self.segment.edge().load(Ordering::Acquire));
+            (edge == expected).then_some(edge)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,696 @@
+use crate::{
+    parse_duration, Edge, Error, Heart, History, HistoryEntry, MockClock, Result, StateEvent,
+};
+use core::time::Duration;
+use std::{collections::VecDeque, path::Path, sync::Arc, time::Instant};
+
+use portable_atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use rtsc::pi::Mutex;
+
+use super::WatchdogIo;
+
+/// The time the I/O waits when the script is finished
+const IDLE_TIMEOUT: Duration = Duration::from_millis(10);
+
+enum Step {
+    Edge(Edge),
+    Delay(Duration),
+    Timeout,
+    Lost,
+    Failed(String),
+}
+
+/// Scriptable watchdog I/O for tests
+///
+/// The I/O replays a queue of edges, delays and errors. Steps can be added while the watchdog is
+/// running. When the script is finished, the I/O reports timeouts. Delays are real sleeps unless
+/// a virtual clock is set with [`MockIo::with_clock`], which is advanced instead, so tests are
+/// deterministic:
+///
+/// ```rust,ignore
+/// let clock = Arc::new(MockClock::new());
+/// let io = MockIo::new().with_clock(clock.clone());
+/// io.beats(10, Duration::from_millis(100)).delay(Duration::from_millis(50)).beat();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Default)]
+pub struct MockIo {
+    script: Mutex<VecDeque<Step>>,
+    next: AtomicBool,
+    clock: Option<Arc<MockClock>>,
+    timeout: Option<Duration>,
+    clears: AtomicU32,
+    resets: AtomicU32,
+}
+
+impl MockIo {
+    /// Create a new mock I/O with an empty script
+    pub fn new() -> Self {
+        Self {
+            next: AtomicBool::new(true),
+            ..Self::default()
+        }
+    }
+    /// Advance the virtual clock instead of sleeping on delays
+    pub fn with_clock(mut self, clock: Arc<MockClock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+    fn push(&self, step: Step) -> &Self {
+        self.script.lock().push_back(step);
+        self
+    }
+    /// Set the I/O timeout: delays longer than the timeout are reported as timeouts (usually
+    /// [`crate::WatchdogConfig::io_timeout`]), as real I/Os do
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Add an edge
+    pub fn edge(&self, edge: Edge) -> &Self {
+        self.push(Step::Edge(edge))
+    }
+    /// Add the next edge (edges alternate, starting with the rising one)
+    pub fn beat(&self) -> &Self {
+        self.edge(Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)))
+    }
+    /// Add beats, each one is preceded by the interval delay
+    pub fn beats(&self, n: usize, interval: Duration) -> &Self {
+        for _ in 0..n {
+            self.delay(interval).beat();
+        }
+        self
+    }
+    /// Add a delay
+    pub fn delay(&self, d: Duration) -> &Self {
+        self.push(Step::Delay(d))
+    }
+    /// Add a timeout error
+    pub fn timeout(&self) -> &Self {
+        self.push(Step::Timeout)
+    }
+    /// Add a lost heartbeats error
+    pub fn lost(&self) -> &Self {
+        self.push(Step::Lost)
+    }
+    /// Add an I/O failure
+    pub fn error(&self, msg: &str) -> &Self {
+        self.push(Step::Failed(msg.to_owned()))
+    }
+    /// Add steps from a simulation scenario, one command per line (or separated with `;`),
+    /// `#` starts a comment:
+    ///
+    /// ```text
+    /// beat every 100ms for 5s   # beats with the interval for the duration
+    /// beat every 100ms x 10     # the number of beats with the interval
+    /// silence 1s                # no beats
+    /// beat                      # a single beat
+    /// edge rising               # a single edge (rising/falling), e.g. to break the order
+    /// timeout                   # a timeout error
+    /// lost                      # a lost heartbeats error
+    /// error link down           # an I/O failure
+    /// ```
+    ///
+    /// A CSV heartbeat trace (`time,edge` lines, the time is relative to the trace start, e.g.
+    /// `100ms,+`) is accepted as well. Set the I/O timeout with [`MockIo::with_timeout`] to get
+    /// silences reported as timeouts
+    pub fn scenario(&self, scenario: &str) -> Result<&Self> {
+        let mut trace_time = Duration::ZERO;
+        for line in scenario.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            for cmd in line.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+                if let Some((time, edge)) = cmd.split_once(',') {
+                    let time = parse_scenario_duration(time)?;
+                    self.delay(time.saturating_sub(trace_time))
+                        .edge(parse_edge(edge)?);
+                    trace_time = time;
+                } else {
+                    self.command(cmd)?;
+                }
+            }
+        }
+        Ok(self)
+    }
+    /// Add steps from a simulation scenario file, see [`MockIo::scenario`]
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<&Self> {
+        self.scenario(&std::fs::read_to_string(path)?)
+    }
+    fn command(&self, cmd: &str) -> Result<()> {
+        let invalid = || Error::failed(format!("invalid scenario command: {}", cmd));
+        let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+        let args: Vec<&str> = args.split_whitespace().filter(|a| *a != "every").collect();
+        match (name, args.as_slice()) {
+            ("beat", []) => {
+                self.beat();
+            }
+            ("beat", [interval, "for", duration]) => {
+                let interval = parse_scenario_duration(interval)?;
+                let duration = parse_scenario_duration(duration)?;
+                if interval.is_zero() {
+                    return Err(invalid());
+                }
+                let n = duration.as_nanos() / interval.as_nanos();
+                self.beats(usize::try_from(n).map_err(|_| invalid())?, interval);
+            }
+            ("beat", [interval, "x", n]) => {
+                self.beats(
+                    n.parse().map_err(|_| invalid())?,
+                    parse_scenario_duration(interval)?,
+                );
+            }
+            ("silence", [duration]) => {
+                self.delay(parse_scenario_duration(duration)?);
+            }
+            ("edge", [edge]) => {
+                self.edge(parse_edge(edge)?);
+            }
+            ("timeout", []) => {
+                self.timeout();
+            }
+            ("lost", []) => {
+                self.lost();
+            }
+            ("error", msg) => {
+                self.error(&msg.join(" "));
+            }
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+    /// Get the number of remaining script steps
+    pub fn remaining(&self) -> usize {
+        self.script.lock().len()
+    }
+    /// Is the script finished
+    pub fn is_done(&self) -> bool {
+        self.remaining() == 0
+    }
+    /// Wait until the script is finished, returns false on timeout
+    pub fn wait_done(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while !self.is_done() {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+    /// Get the number of I/O clears (after faults)
+    pub fn clears(&self) -> u32 {
+        self.clears.load(Ordering::Relaxed)
+    }
+    /// Get the number of I/O resets (before retries after I/O failures)
+    pub fn resets(&self) -> u32 {
+        self.resets.load(Ordering::Relaxed)
+    }
+    fn wait(&self, d: Duration) {
+        if let Some(ref clock) = self.clock {
+            clock.advance(d);
+        } else {
+            std::thread::sleep(d);
+        }
+    }
+}
+
+impl WatchdogIo for MockIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        loop {
+            // the lock is released before waiting
+            let step = self.script.lock().pop_front();
+            match step {
+                Some(Step::Edge(edge)) => return Ok(edge),
+                Some(Step::Delay(d)) => match self.timeout {
+                    Some(timeout) if d > timeout => {
+                        self.wait(timeout);
+                        self.script.lock().push_front(Step::Delay(d - timeout));
+                        return Err(Error::Timeout);
+                    }
+                    _ => self.wait(d),
+                },
+                Some(Step::Timeout) => return Err(Error::Timeout),
+                Some(Step::Lost) => return Err(Error::Lost),
+                Some(Step::Failed(msg)) => return Err(Error::failed(msg)),
+                None => {
+                    std::thread::sleep(IDLE_TIMEOUT);
+                    return Err(Error::Timeout);
+                }
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        // the script is not cleared, it contains the future steps
+        self.clears.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.resets.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// scenario errors contain the invalid value
+fn parse_scenario_duration(s: &str) -> Result<Duration> {
+    parse_duration(s).map_err(|e| match e {
+        Error::Config(msg) => Error::failed(format!("{}: {}", msg, s.trim())),
+        e => e,
+    })
+}
+
+fn parse_edge(s: &str) -> Result<Edge> {
+    match s.trim() {
+        "+" | "1" | "rising" => Ok(Edge::Rising),
+        "." | "0" | "falling" => Ok(Edge::Falling),
+        v => Err(Error::failed(format!("invalid edge: {}", v))),
+    }
+}
+
+/// Recording client for tests
+///
+/// Counts the beats of the code under test, the next beats can be set to fail.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Default)]
+pub struct MockHeart {
+    beats: AtomicU64,
+    fail: AtomicU32,
+}
+
+impl MockHeart {
+    /// Create a new mock client
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Fail the next n beats
+    pub fn fail_next(&self, n: u32) {
+        self.fail.store(n, Ordering::Relaxed);
+    }
+    /// Get the number of successful beats
+    pub fn beats(&self) -> u64 {
+        self.beats.load(Ordering::Relaxed)
+    }
+    /// Assert the number of successful beats
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number does not match
+    pub fn assert_beats(&self, expected: u64) {
+        assert_eq!(self.beats(), expected, "unexpected number of beats");
+    }
+}
+
+impl Heart for MockHeart {
+    fn beat(&self) -> Result<()> {
+        if self
+            .fail
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return Err(Error::failed("mock heart failure"));
+        }
+        self.beats.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Assert the watchdog state events (see [`crate::Watchdog::history`]), oldest first
+///
+/// # Panics
+///
+/// Panics if the events do not match
+pub fn assert_events(history: &History, expected: &[StateEvent]) {
+    let events: Vec<&StateEvent> = history.iter().map(HistoryEntry::event).collect();
+    let expected: Vec<&StateEvent> = expected.iter().collect();
+    assert_eq!(events, expected, "unexpected watchdog state events");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Clock, FaultKind, RetryPolicy, Watchdog, WatchdogConfig};
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Runs a watchdog with a virtual clock until the script is finished (the script is
+    /// terminated with I/O failures which exceed the retries), returns the state events
+    fn run(script: impl FnOnce(&MockIo)) -> History {
+        run_with(script, |_| ())
+    }
+
+    /// Same as [`run`], the watchdog is prepared before the run
+    fn run_with(
+        script: impl FnOnce(&MockIo),
+        prepare: impl FnOnce(&Watchdog<MockIo, Arc<MockClock>>),
+    ) -> History {
+        let config = WatchdogConfig::new(INTERVAL)
+            .with_io_retry(RetryPolicy::new(Duration::from_millis(1)).with_max_retries(1));
+        let clock = Arc::new(MockClock::new());
+        let io = MockIo::new()
+            .with_clock(clock.clone())
+            .with_timeout(config.io_timeout());
+        script(&io);
+        io.error("end").error("end");
+        let watchdog = Watchdog::with_clock(config, io, clock);
+        prepare(&watchdog);
+        assert!(matches!(watchdog.run(), Err(Error::Failed(msg)) if msg == "end"));
+        watchdog.history()
+    }
+
+    #[test]
+    fn test_timeout() {
+        let history = run(|io| {
+            io.beats(10, INTERVAL)
+                .delay(INTERVAL * 10)
+                .beats(10, INTERVAL);
+        });
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let history = run(|io| {
+            io.beats(6, INTERVAL)
+                .delay(INTERVAL)
+                .edge(Edge::Falling)
+                .beats(10, INTERVAL);
+        });
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::OutOfOrder),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let history = run(|io| {
+            io.beats(10, INTERVAL)
+                .lost()
+                .beats(10, INTERVAL)
+                .timeout()
+                .beats(10, INTERVAL)
+                .error("link down")
+                .beats(10, INTERVAL);
+        });
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Lost),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_no_beats() {
+        let history = run(|io| {
+            io.delay(INTERVAL * 10);
+        });
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    fn parse_err(scenario: &str) -> String {
+        match MockIo::new().scenario(scenario) {
+            Err(Error::Failed(msg)) => msg,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("scenario accepted: {}", scenario),
+        }
+    }
+
+    #[test]
+    fn test_scenario() {
+        let io = MockIo::new();
+        io.scenario("beat every 100ms for 5s; silence 1s; beat every 100ms for 1s")
+            .unwrap();
+        // a delay and an edge per beat
+        assert_eq!(io.remaining(), 50 * 2 + 1 + 10 * 2);
+        let io = MockIo::new();
+        io.scenario(
+            "# comment\n\
+             beat every 100ms x 3  # trailing comment\n\
+             \n\
+             beat; edge rising; edge .; timeout; lost; error link down\n",
+        )
+        .unwrap();
+        assert_eq!(io.remaining(), 3 * 2 + 6);
+        for edge in [Edge::Rising, Edge::Falling, Edge::Rising, Edge::Falling] {
+            assert_eq!(io.get(edge).unwrap(), edge);
+        }
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Timeout)));
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Lost)));
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Failed(msg)) if msg == "link down"));
+        assert!(io.is_done());
+    }
+
+    #[test]
+    fn test_scenario_trace() {
+        let clock = Arc::new(MockClock::new());
+        let io = MockIo::new().with_clock(clock.clone());
+        io.scenario("100ms,+\n200ms,.\n450ms,1\n500ms,0").unwrap();
+        assert_eq!(io.remaining(), 8);
+        let mut times = Vec::new();
+        while !io.is_done() {
+            io.get(Edge::Rising).unwrap();
+            times.push(clock.now().as_millis());
+        }
+        assert_eq!(times, [100, 200, 450, 500]);
+    }
+
+    #[test]
+    fn test_scenario_watchdog() {
+        let history = run(|io| {
+            io.scenario("beat every 100ms for 5s; silence 1s; beat every 100ms for 1s")
+                .unwrap();
+        });
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_scenario_malformed() {
+        for cmd in [
+            "beat every 0ms for 1s",
+            "beat every 100ms x many",
+            "beat every 100ms for",
+            "beat twice",
+            "silence",
+            "silence 1s 2s",
+            "edge",
+            "timeout 1s",
+            "jump",
+        ] {
+            assert_eq!(
+                parse_err(cmd),
+                format!("invalid scenario command: {}", cmd),
+                "{}",
+                cmd
+            );
+        }
+        assert_eq!(parse_err("edge up"), "invalid edge: up");
+        assert_eq!(parse_err("100ms,x"), "invalid edge: x");
+        assert_eq!(parse_err("silence 1"), "duration unit missing: 1");
+        assert_eq!(parse_err("silence 1y"), "invalid duration unit: 1y");
+        assert_eq!(parse_err("beat every ms x 2"), "invalid duration: ms");
+        assert_eq!(parse_err("abc,+"), "invalid duration: abc");
+    }
+
+    #[test]
+    fn test_heart() {
+        let heart = MockHeart::new();
+        heart.fail_next(2);
+        assert!(heart.beat().is_err());
+        assert!(heart.beat().is_err());
+        for _ in 0..3 {
+            heart.beat().unwrap();
+        }
+        heart.assert_beats(3);
+    }
+
+    #[test]
+    fn test_paused() {
+        let history = run_with(
+            |io| {
+                io.beats(10, INTERVAL).delay(INTERVAL * 10);
+            },
+            |watchdog| watchdog.pause(),
+        );
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Paused,
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_resumed() {
+        let history = run_with(
+            |io| {
+                io.beats(10, INTERVAL)
+                    .delay(INTERVAL * 10)
+                    .beats(10, INTERVAL);
+            },
+            |watchdog| {
+                watchdog.pause();
+                // the callbacks are called from the run loop, the resume is applied on the next
+                // read
+                let w = watchdog.clone();
+                watchdog.on_state_change(move |event| {
+                    if *event == StateEvent::Paused {
+                        w.resume();
+                    }
+                });
+            },
+        );
+        assert_events(
+            &history,
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Paused,
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_resumed_after_silence() {
+        let config = WatchdogConfig::new(INTERVAL)
+            .with_io_retry(RetryPolicy::new(Duration::from_millis(1)).with_max_retries(1));
+        let clock = Arc::new(MockClock::new());
+        let io = Arc::new(
+            MockIo::new()
+                .with_clock(clock.clone())
+                .with_timeout(config.io_timeout()),
+        );
+        io.beats(5, INTERVAL).delay(INTERVAL * 10);
+        let watchdog = Watchdog::with_clock(config, io.clone(), clock);
+        watchdog.pause();
+        let handle = watchdog.spawn().unwrap();
+        assert!(io.wait_done(Duration::from_secs(5)));
+        watchdog.resume();
+        // the heart is still silent right after the resume, which is covered by the warmup. The
+        // resume is applied on the current or the next read, both timeouts are never evaluated
+        io.timeout()
+            .timeout()
+            .beats(10, INTERVAL)
+            .delay(INTERVAL * 10)
+            .error("end")
+            .error("end");
+        assert!(matches!(handle.join(), Err(Error::Failed(msg)) if msg == "end"));
+        assert_events(
+            &watchdog.history(),
+            &[
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Paused,
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Terminated,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_events_not_received() {
+        // the state events are neither received from the internal channel nor by subscribers
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = Arc::new(Mutex::new(None));
+        let history = run_with(
+            |io| {
+                io.beats(10, INTERVAL)
+                    .delay(INTERVAL * 10)
+                    .beats(10, INTERVAL);
+            },
+            |watchdog| {
+                let events = events.clone();
+                watchdog.on_state_change(move |event| events.lock().push(event.clone()));
+                subscriber.lock().replace(watchdog.subscribe());
+            },
+        );
+        let expected = [
+            StateEvent::Fault(FaultKind::Initial),
+            StateEvent::Ok,
+            StateEvent::Fault(FaultKind::Timeout),
+            StateEvent::Ok,
+            StateEvent::Fault(FaultKind::Io),
+            StateEvent::Terminated,
+        ];
+        assert_events(&history, &expected);
+        assert_eq!(*events.lock(), expected);
+    }
+
+    #[test]
+    fn test_last_fault() {
+        let mut watchdog = None;
+        run_with(
+            |io| {
+                io.beats(10, INTERVAL);
+            },
+            |w| watchdog = Some(w.clone()),
+        );
+        let watchdog = watchdog.unwrap();
+        let (kind, time) = watchdog.last_fault_clock().unwrap();
+        assert_eq!(kind, FaultKind::Io);
+        // the virtual time
+        assert_eq!(time, INTERVAL * 10);
+        let (kind, instant) = watchdog.last_fault().unwrap();
+        assert_eq!(kind, FaultKind::Io);
+        assert!(instant <= std::time::Instant::now());
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected watchdog state events")]
+    fn test_assert_events() {
+        let history = run(|io| {
+            io.beats(10, INTERVAL);
+        });
+        assert_events(&history, &[StateEvent::Fault(FaultKind::Initial)]);
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rmodbus::{client::ModbusRequest, guess_response_frame_len, ModbusProto};
+use rtsc::pi::Mutex;
+
+use super::{poll_until, WatchdogIo};
+
+/// Default Modbus TCP port
+pub const DEFAULT_TCP_PORT: u16 = 502;
+
+/// Modbus register, used to carry the heartbeat edge
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Register {
+    /// Coil (on - rising, off - falling)
+    Coil(u16),
+    /// Holding register (1 - rising, 0 - falling)
+    Holding(u16),
+}
+
+/// Sends a request frame and reads the response frame
+fn exchange<S: Read + Write>(stream: &mut S, proto: ModbusProto, frame: &[u8]) -> Result<Vec<u8>> {
+    stream.write_all(frame)?;
+    stream.flush()?;
+    let head_len = if proto == ModbusProto::TcpUdp { 6 } else { 3 };
+    let mut response = vec![0; head_len];
+    stream.read_exact(&mut response)?;
+    let len = usize::from(guess_response_frame_len(&response, proto).map_err(Error::failed)?);
+    if len > head_len {
+        response.resize(len, 0);
+        stream.read_exact(&mut response[head_len..])?;
+    }
+    Ok(response)
+}
+
+/// Writes the edge to the register
+fn write_edge<S: Read + Write>(
+    stream: &mut S,
+    mut request: ModbusRequest,
+    register: Register,
+    edge: Edge,
+) -> Result<()> {
+    let mut frame = Vec::new();
+    let value = bool::from(edge);
+    match register {
+        Register::Coil(reg) => request.generate_set_coil(reg, value, &mut frame),
+        Register::Holding(reg) => request.generate_set_holding(reg, u16::from(value), &mut frame),
+    }
+    .map_err(Error::failed)?;
+    let response = exchange(stream, request.proto, &frame)?;
+    request.parse_ok(&response).map_err(Error::failed)
+}
+
+/// Reads the edge from the register
+fn read_edge<S: Read + Write>(
+    stream: &mut S,
+    mut request: ModbusRequest,
+    register: Register,
+) -> Result<Edge> {
+    let mut frame = Vec::new();
+    match register {
+        Register::Coil(reg) => request.generate_get_coils(reg, 1, &mut frame),
+        Register::Holding(reg) => request.generate_get_holdings(reg, 1, &mut frame),
+    }
+    .map_err(Error::failed)?;
+    let response = exchange(stream, request.proto, &frame)?;
+    let value = match register {
+        Register::Coil(_) => {
+            let mut result: Vec<bool> = Vec::with_capacity(1);
+            request
+                .parse_bool(&response, &mut result)
+                .map_err(Error::failed)?;
+            result.first().copied()
+        }
+        Register::Holding(_) => {
+            let mut result: Vec<u16> = Vec::with_capacity(1);
+            request
+                .parse_u16(&response, &mut result)
+                .map_err(Error::failed)?;
+            result.first().map(|v| *v != 0)
+        }
+    };
+    value
+        .map(Edge::from)
+        .ok_or_else(|| Error::failed("empty Modbus response"))
+}
+
+/// Modbus TCP connection, re-established on demand
+struct TcpConnection {
+    addrs: Vec<SocketAddr>,
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl TcpConnection {
+    fn new<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        Ok(Self {
+            addrs: addr.to_socket_addrs()?.collect(),
+            stream: Mutex::new(None),
+        })
+    }
+    fn connect(&self, timeout: Duration) -> Result<TcpStream> {
+        let mut result = Err(Error::failed("no address to connect to"));
+        for addr in &self.addrs {
+            match TcpStream::connect_timeout(addr, timeout) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(e) => result = Err(e.into()),
+            }
+        }
+        result
+    }
+    /// runs a request, the timeout bounds the connection and each read/write, the connection is
+    /// dropped in case of errors
+    fn with_stream<R>(
+        &self,
+        timeout: Duration,
+        f: impl FnOnce(&mut TcpStream) -> Result<R>,
+    ) -> Result<R> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(self.connect(timeout)?);
+        }
+        let s = stream.as_mut().unwrap();
+        let res = s
+            .set_read_timeout(Some(timeout))
+            .and_then(|()| s.set_write_timeout(Some(timeout)))
+            .map_err(Error::from)
+            .and_then(|()| f(s));
+        if res.is_err() {
+            stream.take();
+        }
+        res
+    }
+}
+
+/// Modbus TCP client
+///
+/// Writes edges to a coil or a holding register of a Modbus server (e.g. a PLC). The connection is
+/// established on the first beat and re-established on the next beat in case of errors.
+#[allow(clippy::module_name_repetitions)]
+pub struct ModbusTcpHeart {
+    connection: TcpConnection,
+    unit: u8,
+    register: Register,
+    timeout: Duration,
+    next: AtomicBool,
+}
+
+impl ModbusTcpHeart {
+    /// creates a new Modbus TCP client, the timeout is used for both connection and requests
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        unit: u8,
+        register: Register,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
+            connection: TcpConnection::new(addr)?,
+            unit,
+            register,
+            timeout,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for ModbusTcpHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        self.connection.with_stream(self.timeout, |stream| {
+            write_edge(
+                stream,
+                ModbusRequest::new_tcp_udp(self.unit, 1),
+                self.register,
+                edge,
+            )
+        })
+    }
+}
+
+/// Modbus TCP watchdog I/O configuration
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone)]
+pub struct ModbusTcpConfig {
+    addr: String,
+    unit: u8,
+    register: Register,
+    pull_interval: Duration,
+}
+
+impl ModbusTcpConfig {
+    /// creates a new Modbus TCP watchdog I/O configuration (addr is "host:port")
+    pub fn new(addr: &str, unit: u8, register: Register, pull_interval: Duration) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            unit,
+            register,
+            pull_interval,
+        }
+    }
+}
+
+/// Modbus TCP watchdog I/O
+///
+/// Polls a coil or a holding register of a Modbus server. Communication errors are reported as
+/// timeouts, the connection is re-established on the next poll.
+#[allow(clippy::module_name_repetitions)]
+pub struct ModbusTcpIo {
+    connection: TcpConnection,
+    unit: u8,
+    register: Register,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl ModbusTcpIo {
+    /// creates a new Modbus TCP watchdog I/O
+    pub fn create(config: &ModbusTcpConfig, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            connection: TcpConnection::new(config.addr.as_str())?,
+            unit: config.unit,
+            register: config.register,
+            timeout,
+            pull_interval: config.pull_interval,
+        })
+    }
+}
+
+impl WatchdogIo for ModbusTcpIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |remaining| {
+            self.connection
+                .with_stream(remaining, |stream| {
+                    read_edge(
+                        stream,
+                        ModbusRequest::new_tcp_udp(self.unit, 1),
+                        self.register,
+                    )
+                })
+                .ok()
+                .filter(|edge| *edge == expected)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Modbus RTU serial port, the input buffer is cleared in case of errors to re-synchronize
+#[cfg(feature = "serial")]
+struct RtuPort {
+    port: Mutex<Box<dyn serialport::SerialPort>>,
+}
+
+#[cfg(feature = "serial")]
+impl RtuPort {
+    fn open(path: &str, baud_rate: u32, timeout: Duration) -> Result<Self> {
+        let port = serialport::new(path, baud_rate)
+            .timeout(timeout)
+            .open()
+            .map_err(Error::failed)?;
+        Ok(Self {
+            port: Mutex::new(port),
+        })
+    }
+    /// runs a request, the timeout bounds each read/write
+    fn with_port<R>(
+        &self,
+        timeout: Duration,
+        f: impl FnOnce(&mut Box<dyn serialport::SerialPort>) -> Result<R>,
+    ) -> Result<R> {
+        let mut port = self.port.lock();
+        let res = port
+            .set_timeout(timeout)
+            .map_err(Error::failed)
+            .and_then(|()| f(&mut port));
+        if res.is_err() {
+            let _ = port.clear(serialport::ClearBuffer::Input);
+        }
+        res
+    }
+}
+
+/// Modbus RTU client
+///
+/// Writes edges to a coil or a holding register of a Modbus slave (e.g. a PLC) via a serial port.
+#[cfg(feature = "serial")]
+#[allow(clippy::module_name_repetitions)]
+pub struct ModbusRtuHeart {
+    port: RtuPort,
+    unit: u8,
+    register: Register,
+    timeout: Duration,
+    next: AtomicBool,
+}
+
+#[cfg(feature = "serial")]
+impl ModbusRtuHeart {
+    /// creates a new Modbus RTU client, the timeout is used for requests
+    pub fn create(
+        path: &str,
+        baud_rate: u32,
+        unit: u8,
+        register: Register,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
+            port: RtuPort::open(path, baud_rate, timeout)?,
+            unit,
+            register,
+            timeout,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+#[cfg(feature = "serial")]
+impl Heart for ModbusRtuHeart {
+    fn beat(&self) -> Result<()> {
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        self.port.with_port(self.timeout, |port| {
+            write_edge(
+                port,
+                ModbusRequest::new(self.unit, ModbusProto::Rtu),
+                self.register,
+                edge,
+            )
+        })
+    }
+}
+
+/// Modbus RTU watchdog I/O configuration
+#[cfg(feature = "serial")]
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone)]
+pub struct ModbusRtuConfig {
+    path: String,
+    baud_rate: u32,
+    unit: u8,
+    register: Register,
+    pull_interval: Duration,
+}
+
+#[cfg(feature = "serial")]
+impl ModbusRtuConfig {
+    /// creates a new Modbus RTU watchdog I/O configuration
+    pub fn new(
+        path: &str,
+        baud_rate: u32,
+        unit: u8,
+        register: Register,
+        pull_interval: Duration,
+    ) -> Self {
+        Self {
+            path: path.to_owned(),
+            baud_rate,
+            unit,
+            register,
+            pull_interval,
+        }
+    }
+}
+
+/// Modbus RTU watchdog I/O
+///
+/// Polls a coil or a holding register of a Modbus slave via a serial port. Communication errors
+/// are reported as timeouts.
+#[cfg(feature = "serial")]
+#[allow(clippy::module_name_repetitions)]
+pub struct ModbusRtuIo {
+    port: RtuPort,
+    unit: u8,
+    register: Register,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+#[cfg(feature = "serial")]
+impl ModbusRtuIo {
+    /// creates a new Modbus RTU watchdog I/O
+    pub fn create(config: &ModbusRtuConfig, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            port: RtuPort::open(&config.path, config.baud_rate, timeout)?,
+            unit: config.unit,
+            register: config.register,
+            timeout,
+            pull_interval: config.pull_interval,
+        })
+    }
+}
+
+#[cfg(feature = "serial")]
+impl WatchdogIo for ModbusRtuIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |remaining| {
+            self.port
+                .with_port(remaining, |port| {
+                    read_edge(
+                        port,
+                        ModbusRequest::new(self.unit, ModbusProto::Rtu),
+                        self.register,
+                    )
+                })
+                .ok()
+                .filter(|edge| *edge == expected)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{thread, time::Instant};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+use rumqttc::{Client, Connection, Event, Packet, QoS, RecvTimeoutError, TryRecvError};
+
+#[allow(clippy::module_name_repetitions)]
+pub use rumqttc::MqttOptions;
+
+use super::WatchdogIo;
+
+const REQUEST_CAPACITY: usize = 10;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// MQTT client
+///
+/// Edges are published to the topic with QoS 0 (at most once). The connection is maintained by a
+/// background thread, which reconnects automatically in case of errors.
+#[allow(clippy::module_name_repetitions)]
+pub struct MqttHeart {
+    client: Client,
+    topic: String,
+    next: AtomicBool,
+}
+
+impl MqttHeart {
+    /// creates a new MQTT client
+    pub fn create(options: MqttOptions, topic: &str) -> Result<Self> {
+        let (client, mut connection) = Client::new(options, REQUEST_CAPACITY);
+        thread::Builder::new()
+            .name("mqtt-heart".to_owned())
+            .spawn(move || {
+                // the iterator ends when the client is dropped
+                for event in connection.iter() {
+                    if event.is_err() {
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            })?;
+        Ok(Self {
+            client,
+            topic: topic.to_owned(),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for MqttHeart {
+    fn beat(&self) -> Result<()> {
+        self.client
+            .try_publish(
+                &self.topic,
+                QoS::AtMostOnce,
+                false,
+                [Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8],
+            )
+            .map_err(Error::failed)
+    }
+}
+
+/// MQTT watchdog I/O
+///
+/// Subscribes to the topic (the subscription is renewed on every reconnect). If the broker is not
+/// available, a timeout is reported.
+#[allow(clippy::module_name_repetitions)]
+pub struct MqttIo {
+    client: Client,
+    connection: Mutex<Connection>,
+    topic: String,
+    timeout: Duration,
+}
+
+impl MqttIo {
+    /// creates a new MQTT watchdog I/O
+    pub fn create(options: MqttOptions, topic: &str, timeout: Duration) -> Result<Self> {
+        let (client, connection) = Client::new(options, REQUEST_CAPACITY);
+        Ok(Self {
+            client,
+            connection: Mutex::new(connection),
+            topic: topic.to_owned(),
+            timeout,
+        })
+    }
+    /// processes an event, returns an edge if received
+    fn process(&self, event: Event) -> Option<Edge> {
+        match event {
+            Event::Incoming(Packet::Publish(p)) if p.topic == self.topic => {
+                p.payload.first().map(|b| Edge::from(*b))
+            }
+            Event::Incoming(Packet::ConnAck(_)) => {
+                // the error is reported by the connection
+                let _ = self.client.try_subscribe(&self.topic, QoS::AtMostOnce);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+impl WatchdogIo for MqttIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let now = Instant::now();
+        let mut connection = self.connection.lock();
+        loop {
+            let remaining = self
+                .timeout
+                .checked_sub(now.elapsed())
+                .ok_or(Error::Timeout)?;
+            match connection.recv_timeout(remaining) {
+                Ok(Ok(event)) => {
+                    if let Some(edge) = self.process(event) {
+                        return Ok(edge);
+                    }
+                }
+                // the connection is re-established on the next poll
+                Ok(Err(_)) => thread::sleep(remaining.min(RECONNECT_DELAY)),
+                Err(RecvTimeoutError::Timeout) => return Err(Error::Timeout),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::failed("MQTT client disconnected"))
+                }
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut connection = self.connection.lock();
+        loop {
+            match connection.try_recv() {
+                Ok(Ok(event)) => {
+                    self.process(event);
+                }
+                Ok(Err(_)) | Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    return Err(Error::failed("MQTT client disconnected"))
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::ffi::CString;
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::WatchdogIo;
+
+const MAX_MESSAGES: libc::c_long = 10;
+
+struct Queue {
+    mqd: libc::mqd_t,
+    msg_size: usize,
+}
+
+impl Queue {
+    fn open(name: &str, flags: libc::c_int) -> Result<Self> {
+        let c_name = CString::new(name).map_err(Error::failed)?;
+        let mut attr: libc::mq_attr = unsafe { core::mem::zeroed() };
+        attr.mq_maxmsg = MAX_MESSAGES;
+        attr.mq_msgsize = 1;
+        let mqd = unsafe {
+            libc::mq_open(
+                c_name.as_ptr(),
+                flags | libc::O_CREAT,
+                0o660,
+                core::ptr::addr_of_mut!(attr),
+            )
+        };
+        if mqd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // the queue may already exist with different attributes
+        if unsafe { libc::mq_getattr(mqd, core::ptr::addr_of_mut!(attr)) } != 0 {
+            let e = std::io::Error::last_os_error();
+            unsafe { libc::mq_close(mqd) };
+            return Err(e.into());
+        }
+        Ok(Self {
+            mqd,
+            msg_size: usize::try_from(attr.mq_msgsize).map_err(Error::failed)?,
+        })
+    }
+    /// Receives a message until the absolute deadline (CLOCK_REALTIME)
+    fn receive(&self, deadline: &libc::timespec) -> std::io::Result<u8> {
+        let mut buf = vec![0u8; self.msg_size];
+        loop {
+            let res = unsafe {
+                libc::mq_timedreceive(
+                    self.mqd,
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                    core::ptr::null_mut(),
+                    deadline,
+                )
+            };
+            if res > 0 {
+                return Ok(buf[0]);
+            }
+            if res < 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Queue {
+    fn drop(&mut self) {
+        unsafe { libc::mq_close(self.mqd) };
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn deadline(timeout: Duration) -> libc::timespec {
+    let mut now: libc::timespec = unsafe { core::mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, core::ptr::addr_of_mut!(now)) };
+    let nanos = now.tv_nsec + libc::c_long::from(timeout.subsec_nanos());
+    libc::timespec {
+        tv_sec: now.tv_sec + timeout.as_secs() as libc::time_t + nanos / 1_000_000_000,
+        tv_nsec: nanos % 1_000_000_000,
+    }
+}
+
+/// POSIX message queue client
+#[allow(clippy::module_name_repetitions)]
+pub struct MqueueHeart {
+    queue: Queue,
+    next: AtomicBool,
+}
+
+impl MqueueHeart {
+    /// creates a new POSIX message queue client, the queue name must start with a slash (e.g.
+    /// "/heartbeat"). The queue is created if it does not exist
+    ///
+    /// The queue is opened in non-blocking mode, if it is full (e.g. the watchdog is not running),
+    /// beats return [`Error::Timeout`]
+    pub fn create(name: &str) -> Result<Self> {
+        Ok(Self {
+            queue: Queue::open(name, libc::O_WRONLY | libc::O_NONBLOCK)?,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for MqueueHeart {
+    fn beat(&self) -> Result<()> {
+        let buf = [Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8];
+        if unsafe { libc::mq_send(self.queue.mqd, buf.as_ptr().cast(), buf.len(), 0) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+/// POSIX message queue watchdog I/O
+///
+/// The queue is created if it does not exist and is unlinked when the I/O is dropped.
+#[allow(clippy::module_name_repetitions)]
+pub struct MqueueIo {
+    queue: Queue,
+    name: CString,
+    timeout: Duration,
+}
+
+impl MqueueIo {
+    /// creates a new POSIX message queue watchdog I/O
+    pub fn create(name: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            queue: Queue::open(name, libc::O_RDONLY)?,
+            name: CString::new(name).map_err(Error::failed)?,
+            timeout,
+        })
+    }
+}
+
+impl WatchdogIo for MqueueIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        Ok(Edge::from(self.queue.receive(&deadline(self.timeout))?))
+    }
+
+    fn clear(&self) -> Result<()> {
+        // a deadline in the past makes receive non-blocking
+        let past = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        while self.queue.receive(&past).is_ok() {}
+        Ok(())
+    }
+}
+
+impl Drop for MqueueIo {
+    fn drop(&mut self) {
+        unsafe { libc::mq_unlink(self.name.as_ptr()) };
+    }
+}
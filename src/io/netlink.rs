@@ -0,0 +1,198 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::WatchdogIo;
+
+/// Default netlink protocol (reserved for user-mode socket protocols)
+pub const DEFAULT_PROTOCOL: i32 = libc::NETLINK_USERSOCK;
+/// Heartbeat message type (the first one after the reserved control messages)
+pub const MESSAGE_TYPE: u16 = 0x10;
+
+const HEADER_SIZE: usize = core::mem::size_of::<libc::nlmsghdr>();
+const MAX_MESSAGE_SIZE: usize = 256;
+
+#[allow(clippy::cast_possible_truncation)]
+const SOCKADDR_NL_LEN: libc::socklen_t = core::mem::size_of::<libc::sockaddr_nl>() as _;
+
+fn cvt(res: libc::c_int) -> Result<()> {
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Opens a netlink socket, bound to an auto-assigned port id
+fn open(protocol: i32) -> Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            protocol,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let addr = sockaddr(0)?;
+    cvt(unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            core::ptr::addr_of!(addr).cast(),
+            SOCKADDR_NL_LEN,
+        )
+    })?;
+    Ok(socket)
+}
+
+fn sockaddr(groups: u32) -> Result<libc::sockaddr_nl> {
+    let mut addr: libc::sockaddr_nl = unsafe { core::mem::zeroed() };
+    addr.nl_family = libc::sa_family_t::try_from(libc::AF_NETLINK).map_err(Error::failed)?;
+    addr.nl_groups = groups;
+    Ok(addr)
+}
+
+/// Netlink client
+///
+/// Edges are sent as [`MESSAGE_TYPE`] messages with a single-byte payload to a multicast group.
+/// Kernel components can emit heartbeats using the same format.
+#[allow(clippy::module_name_repetitions)]
+pub struct NetlinkHeart {
+    socket: OwnedFd,
+    destination: libc::sockaddr_nl,
+    seq: AtomicU32,
+    next: AtomicBool,
+}
+
+impl NetlinkHeart {
+    /// creates a new netlink client, sending to the multicast group (1-32)
+    pub fn create(protocol: i32, group: u32) -> Result<Self> {
+        if !(1..=32).contains(&group) {
+            return Err(Error::failed("netlink group must be in range 1-32"));
+        }
+        Ok(Self {
+            socket: open(protocol)?,
+            destination: sockaddr(1 << (group - 1))?,
+            seq: AtomicU32::new(0),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for NetlinkHeart {
+    fn beat(&self) -> Result<()> {
+        let mut buf = [0u8; HEADER_SIZE + 4];
+        let header = libc::nlmsghdr {
+            nlmsg_len: u32::try_from(HEADER_SIZE + 1).map_err(Error::failed)?,
+            nlmsg_type: MESSAGE_TYPE,
+            nlmsg_flags: 0,
+            nlmsg_seq: self.seq.fetch_add(1, Ordering::Relaxed),
+            nlmsg_pid: 0,
+        };
+        unsafe { core::ptr::write_unaligned(buf.as_mut_ptr().cast(), header) };
+        buf[HEADER_SIZE] = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+        let res = unsafe {
+            libc::sendto(
+                self.socket.as_raw_fd(),
+                buf.as_ptr().cast(),
+                HEADER_SIZE + 1,
+                0,
+                core::ptr::addr_of!(self.destination).cast(),
+                SOCKADDR_NL_LEN,
+            )
+        };
+        if res < 0 {
+            let e = std::io::Error::last_os_error();
+            // the message is also unicast to the kernel, which may have no receiver for the
+            // protocol, the multicast delivery is not affected
+            if e.kind() != std::io::ErrorKind::ConnectionRefused {
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Netlink watchdog I/O
+///
+/// Joins the multicast group and receives [`MESSAGE_TYPE`] messages, the first payload byte is the
+/// edge.
+#[allow(clippy::module_name_repetitions)]
+pub struct NetlinkIo {
+    socket: OwnedFd,
+}
+
+impl NetlinkIo {
+    /// creates a new netlink watchdog I/O
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn create(protocol: i32, group: u32, timeout: Duration) -> Result<Self> {
+        let socket = open(protocol)?;
+        #[allow(clippy::cast_possible_truncation)]
+        cvt(unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_ADD_MEMBERSHIP,
+                core::ptr::addr_of!(group).cast(),
+                core::mem::size_of::<u32>() as libc::socklen_t,
+            )
+        })?;
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: libc::suseconds_t::from(timeout.subsec_micros()),
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        cvt(unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                core::ptr::addr_of!(tv).cast(),
+                core::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        })?;
+        Ok(Self { socket })
+    }
+    /// receives a message, returns the edge or None for other messages
+    fn recv(&self, flags: libc::c_int) -> Result<Option<Edge>> {
+        let mut buf = [0u8; MAX_MESSAGE_SIZE];
+        let res = unsafe {
+            libc::recv(
+                self.socket.as_raw_fd(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                flags,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let len = usize::try_from(res).map_err(Error::failed)?;
+        if len <= HEADER_SIZE {
+            return Ok(None);
+        }
+        let header: libc::nlmsghdr = unsafe { core::ptr::read_unaligned(buf.as_ptr().cast()) };
+        if header.nlmsg_type != MESSAGE_TYPE {
+            return Ok(None);
+        }
+        Ok(Some(Edge::from(buf[HEADER_SIZE])))
+    }
+}
+
+impl WatchdogIo for NetlinkIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        loop {
+            if let Some(edge) = self.recv(0)? {
+                return Ok(edge);
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.recv(libc::MSG_DONTWAIT).is_ok() {}
+        Ok(())
+    }
+}
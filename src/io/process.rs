@@ -0,0 +1,101 @@
+use crate::{Edge, Error, Result};
+use core::time::Duration;
+use std::{
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    thread,
+    time::Instant,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use super::WatchdogIo;
+
+/// Process liveness watchdog I/O
+///
+/// Supervises a process which can not be modified to send heartbeats. While the process is alive,
+/// the expected edge is synthesized every interval, the process exit is detected via a pidfd
+/// (Linux 5.3+) and reported as a timeout immediately. As a pidfd refers to the process itself, a
+/// PID reused by another process is not mistaken for the supervised one.
+///
+/// The interval is usually the watchdog interval ([`crate::WatchdogConfig::interval`]).
+#[allow(clippy::module_name_repetitions)]
+pub struct PidIo {
+    pidfd: OwnedFd,
+    interval: Duration,
+    exited: AtomicBool,
+}
+
+impl PidIo {
+    /// creates a new process liveness watchdog I/O for the given PID (e.g.
+    /// [`std::process::Child::id`])
+    pub fn create(pid: u32, interval: Duration) -> Result<Self> {
+        let pid = libc::pid_t::try_from(pid).map_err(Error::failed)?;
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let fd = libc::c_int::try_from(fd).map_err(Error::failed)?;
+        Ok(Self::from_pidfd(
+            unsafe { OwnedFd::from_raw_fd(fd) },
+            interval,
+        ))
+    }
+    /// creates a new process liveness watchdog I/O from an existing pidfd (e.g. obtained with
+    /// `clone3` or passed via a Unix socket)
+    pub fn from_pidfd(pidfd: OwnedFd, interval: Duration) -> Self {
+        Self {
+            pidfd,
+            interval,
+            exited: AtomicBool::new(false),
+        }
+    }
+    /// waits for the process exit, returns true if the process has exited
+    fn wait(&self, timeout: Duration) -> Result<bool> {
+        let mut pfd = libc::pollfd {
+            fd: self.pidfd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        let res = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+        if res < 0 {
+            let e = std::io::Error::last_os_error();
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        Ok(res > 0)
+    }
+}
+
+impl AsFd for PidIo {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.pidfd.as_fd()
+    }
+}
+
+impl WatchdogIo for PidIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        if self.exited.load(Ordering::Relaxed) {
+            // the process has already exited, do not let the watchdog spin
+            thread::sleep(self.interval);
+            return Err(Error::Timeout);
+        }
+        let now = Instant::now();
+        loop {
+            let remaining = self.interval.saturating_sub(now.elapsed());
+            if remaining.is_zero() {
+                return Ok(expected);
+            }
+            if self.wait(remaining)? {
+                self.exited.store(true, Ordering::Relaxed);
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
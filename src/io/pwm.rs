@@ -0,0 +1,93 @@
+use crate::{Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Instant,
+};
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(1);
+const EXPORT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// PWM (sysfs) client
+///
+/// Programs a Linux PWM channel to emit the heartbeat square wave in hardware: an edge every
+/// interval, 50% duty cycle. The timing is kept by the PWM controller even if the CPU is loaded.
+///
+/// # Warning
+///
+/// The client does NOT supervise the process. As the wave is generated without CPU involvement,
+/// it keeps running after the process hangs, crashes or is killed (`Drop` is not called), so the
+/// watchdog keeps reporting OK for a dead process. A beat only ensures the channel is still
+/// enabled (and re-enables it if not). Use the client to supervise the power and the wiring of a
+/// device only, the process liveness must be supervised with another heart.
+///
+/// The channel is disabled when the client is dropped (and unexported if it has been exported by
+/// the client).
+#[allow(clippy::module_name_repetitions)]
+pub struct PwmHeart {
+    chip: PathBuf,
+    channel: u32,
+    path: PathBuf,
+    // unexport the channel on drop, if exported by the client
+    exported: bool,
+}
+
+impl PwmHeart {
+    /// creates a new PWM client, the chip is a sysfs PWM chip path (e.g.
+    /// "/sys/class/pwm/pwmchip0"), the interval is the watchdog interval (the wave period is
+    /// twice the interval)
+    pub fn create<P: AsRef<Path>>(chip: P, channel: u32, interval: Duration) -> Result<Self> {
+        let chip = chip.as_ref().to_path_buf();
+        let path = chip.join(format!("pwm{}", channel));
+        let exported = !path.exists();
+        if exported {
+            fs::write(chip.join("export"), channel.to_string())?;
+            // the channel directory is created (and its permissions are set by udev)
+            // asynchronously
+            let now = Instant::now();
+            while fs::metadata(path.join("enable")).map_or(true, |m| m.permissions().readonly()) {
+                if now.elapsed() > EXPORT_TIMEOUT {
+                    return Err(Error::Timeout);
+                }
+                thread::sleep(EXPORT_POLL_INTERVAL);
+            }
+        }
+        let period = (interval * 2).as_nanos();
+        let heart = Self {
+            chip,
+            channel,
+            path,
+            exported,
+        };
+        heart.write("enable", "0")?;
+        // the duty cycle can not exceed the period, reset it first
+        heart.write("duty_cycle", "0")?;
+        heart.write("period", &period.to_string())?;
+        heart.write("duty_cycle", &(period / 2).to_string())?;
+        heart.write("enable", "1")?;
+        Ok(heart)
+    }
+    fn write(&self, attr: &str, value: &str) -> Result<()> {
+        fs::write(self.path.join(attr), value).map_err(Into::into)
+    }
+}
+
+impl Heart for PwmHeart {
+    fn beat(&self) -> Result<()> {
+        if fs::read_to_string(self.path.join("enable"))?.trim() != "1" {
+            self.write("enable", "1")?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PwmHeart {
+    fn drop(&mut self) {
+        let _ = self.write("enable", "0");
+        if self.exported {
+            let _ = fs::write(self.chip.join("unexport"), self.channel.to_string());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
+};
+
+use bytes::Bytes;
+use portable_atomic::{AtomicBool, Ordering};
+use quinn::{
+    crypto::rustls::{QuicClientConfig, QuicServerConfig},
+    ClientConfig, Connection, Endpoint, IdleTimeout, ServerConfig, TransportConfig,
+};
+use rtsc::{channel, pi::Mutex};
+use tokio::runtime::{self, Runtime};
+
+use super::WatchdogIo;
+
+const BEAT_QUEUE_SIZE: usize = 16;
+
+fn runtime(name: &str) -> Result<Runtime> {
+    runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name(name)
+        .enable_all()
+        .build()
+        .map_err(Error::from)
+}
+
+fn transport(idle_timeout: Duration) -> Result<Arc<TransportConfig>> {
+    let mut transport = TransportConfig::default();
+    transport.max_idle_timeout(Some(
+        IdleTimeout::try_from(idle_timeout).map_err(Error::failed)?,
+    ));
+    Ok(Arc::new(transport))
+}
+
+/// QUIC client
+///
+/// Edges are sent as unreliable QUIC datagrams. The connection survives client address changes
+/// (connection migration). If the connection is lost (no response from the watchdog within the
+/// idle timeout), the client tries to reconnect on the next beat.
+///
+/// The TLS configuration can be created with [`super::tls::client_config`].
+#[allow(clippy::module_name_repetitions)]
+pub struct QuicHeart {
+    endpoint: Endpoint,
+    addr: SocketAddr,
+    server_name: String,
+    timeout: Duration,
+    connection: Mutex<Option<Connection>>,
+    next: AtomicBool,
+    // must be dropped last, the endpoint requires the runtime context
+    runtime: Runtime,
+}
+
+impl QuicHeart {
+    /// creates a new QUIC client, the server name must match the watchdog certificate. The timeout
+    /// is used as the handshake and the idle timeout
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        server_name: &str,
+        tls_config: Arc<rustls::ClientConfig>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("no address resolved"))?;
+        let mut config = ClientConfig::new(Arc::new(
+            QuicClientConfig::try_from(tls_config).map_err(Error::failed)?,
+        ));
+        config.transport_config(transport(timeout)?);
+        let runtime = runtime("quic-heart")?;
+        let mut endpoint = {
+            let _guard = runtime.enter();
+            Endpoint::client(if addr.is_ipv4() {
+                (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+            } else {
+                (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+            })?
+        };
+        endpoint.set_default_client_config(config);
+        let heart = Self {
+            endpoint,
+            addr,
+            server_name: server_name.to_owned(),
+            timeout,
+            connection: Mutex::new(None),
+            next: AtomicBool::new(true),
+            runtime,
+        };
+        heart.connection.lock().replace(heart.connect()?);
+        Ok(heart)
+    }
+    fn connect(&self) -> Result<Connection> {
+        self.runtime.block_on(async {
+            let connecting = self
+                .endpoint
+                .connect(self.addr, &self.server_name)
+                .map_err(Error::failed)?;
+            tokio::time::timeout(self.timeout, connecting)
+                .await
+                .map_err(|_| Error::Timeout)?
+                .map_err(Error::failed)
+        })
+    }
+}
+
+impl Heart for QuicHeart {
+    fn beat(&self) -> Result<()> {
+        let mut connection = self.connection.lock();
+        if connection
+            .as_ref()
+            .map_or(true, |c| c.close_reason().is_some())
+        {
+            connection.replace(self.connect()?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8;
+        if let Err(e) = connection
+            .as_ref()
+            .unwrap()
+            .send_datagram(Bytes::copy_from_slice(&[edge]))
+        {
+            connection.take();
+            return Err(Error::failed(e));
+        }
+        Ok(())
+    }
+}
+
+/// QUIC watchdog I/O
+///
+/// Accepts heart connections and receives datagrams, the first byte of a datagram is the edge.
+/// Connections and datagrams are processed by a background runtime.
+///
+/// The TLS configuration can be created with [`super::tls::server_config`], which requires the
+/// hearts to present trusted client certificates.
+#[allow(clippy::module_name_repetitions)]
+pub struct QuicIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+    // the runtime is stopped when the I/O is dropped
+    _runtime: Runtime,
+}
+
+impl QuicIo {
+    /// creates a new QUIC watchdog I/O
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        tls_config: Arc<rustls::ServerConfig>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("no address resolved"))?;
+        let mut config = ServerConfig::with_crypto(Arc::new(
+            QuicServerConfig::try_from(tls_config).map_err(Error::failed)?,
+        ));
+        config.transport_config(transport(timeout)?);
+        let runtime = runtime("quic-watchdog")?;
+        let endpoint = {
+            let _guard = runtime.enter();
+            Endpoint::server(config, addr)?
+        };
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        runtime.spawn(async move {
+            while let Some(incoming) = endpoint.accept().await {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    // connections which fail the handshake (e.g. untrusted certificates) are
+                    // dropped
+                    let Ok(connection) = incoming.await else {
+                        return;
+                    };
+                    while let Ok(datagram) = connection.read_datagram().await {
+                        if let Some(b) = datagram.first() {
+                            // if the queue is full, the watchdog is not running
+                            if let Err(rtsc::Error::ChannelClosed) = tx.try_send(Edge::from(*b)) {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            rx,
+            timeout,
+            _runtime: runtime,
+        })
+    }
+}
+
+impl WatchdogIo for QuicIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Condvar, Edge, Error, Heart, RawMutex, Result};
+use core::time::Duration;
+use std::thread;
+
+use portable_atomic::{AtomicBool, Ordering};
+use redis::{Client, Commands, Connection};
+use rtsc::{channel, pi::Mutex};
+
+use super::WatchdogIo;
+
+const BEAT_QUEUE_SIZE: usize = 16;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// Redis client
+///
+/// Edges are published to the channel. If the connection is lost, the client tries to reconnect
+/// on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct RedisHeart {
+    client: Client,
+    channel: String,
+    timeout: Duration,
+    connection: Mutex<Option<Connection>>,
+    next: AtomicBool,
+}
+
+impl RedisHeart {
+    /// creates a new Redis client, the url is a Redis server URL (e.g. "redis://127.0.0.1/"), the
+    /// timeout is used as the connect and the I/O timeout
+    pub fn create(url: &str, channel: &str, timeout: Duration) -> Result<Self> {
+        let client = Client::open(url).map_err(Error::failed)?;
+        let heart = Self {
+            client,
+            channel: channel.to_owned(),
+            timeout,
+            connection: Mutex::new(None),
+            next: AtomicBool::new(true),
+        };
+        heart.connection.lock().replace(heart.connect()?);
+        Ok(heart)
+    }
+    fn connect(&self) -> Result<Connection> {
+        let connection = self
+            .client
+            .get_connection_with_timeout(self.timeout)
+            .map_err(Error::failed)?;
+        connection
+            .set_write_timeout(Some(self.timeout))
+            .and_then(|()| connection.set_read_timeout(Some(self.timeout)))
+            .map_err(Error::failed)?;
+        Ok(connection)
+    }
+}
+
+impl Heart for RedisHeart {
+    fn beat(&self) -> Result<()> {
+        let mut connection = self.connection.lock();
+        if connection.is_none() {
+            connection.replace(self.connect()?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = connection
+            .as_mut()
+            .unwrap()
+            .publish::<_, _, ()>(&self.channel, &[edge as u8])
+        {
+            connection.take();
+            return Err(Error::failed(e));
+        }
+        Ok(())
+    }
+}
+
+/// Redis watchdog I/O
+///
+/// Subscribes to the channel, the first byte of a message is the edge. The subscription is
+/// maintained by a background thread, which reconnects automatically in case of errors. If the
+/// server is not available, a timeout is reported.
+#[allow(clippy::module_name_repetitions)]
+pub struct RedisIo {
+    rx: channel::Receiver<Edge, RawMutex, Condvar>,
+    timeout: Duration,
+}
+
+impl RedisIo {
+    /// creates a new Redis watchdog I/O, see [`RedisHeart::create`] for the arguments
+    pub fn create(url: &str, channel: &str, timeout: Duration) -> Result<Self> {
+        let client = Client::open(url).map_err(Error::failed)?;
+        let channel = channel.to_owned();
+        let (tx, rx) = channel::bounded(BEAT_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("redis-watchdog".to_owned())
+            .spawn(move || loop {
+                if let Ok(mut connection) = client.get_connection_with_timeout(timeout) {
+                    let mut pubsub = connection.as_pubsub();
+                    if pubsub.subscribe(&channel).is_ok() {
+                        while let Ok(message) = pubsub.get_message() {
+                            if let Some(b) = message.get_payload_bytes().first() {
+                                // if the queue is full, the watchdog is not running
+                                if let Err(rtsc::Error::ChannelClosed) = tx.try_send(Edge::from(*b))
+                                {
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                }
+                thread::sleep(RECONNECT_DELAY);
+            })?;
+        Ok(Self { rx, timeout })
+    }
+}
+
+impl WatchdogIo for RedisIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        self.rx.recv_timeout(self.timeout).map_err(|e| match e {
+            rtsc::Error::Timeout => Error::Timeout,
+            e => Error::failed(e),
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        while self.rx.try_recv().is_ok() {}
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+
+use super::{StreamListener, WatchdogIo};
+
+const SOL_SCTP: libc::c_int = 132;
+const SCTP_NODELAY: libc::c_int = 3;
+const SCTP_SOCKOPT_BINDX_ADD: libc::c_int = 100;
+const SCTP_SOCKOPT_CONNECTX: libc::c_int = 110;
+
+/// Creates a one-to-one style SCTP socket for the address family of the given addresses
+fn socket(addrs: &[SocketAddr]) -> Result<OwnedFd> {
+    let domain = match addrs.first() {
+        Some(SocketAddr::V4(_)) => libc::AF_INET,
+        Some(SocketAddr::V6(_)) => libc::AF_INET6,
+        None => return Err(Error::failed("no SCTP addresses specified")),
+    };
+    if addrs
+        .iter()
+        .any(|a| a.is_ipv4() != (domain == libc::AF_INET))
+    {
+        return Err(Error::failed("SCTP addresses must be of the same family"));
+    }
+    let fd = unsafe {
+        libc::socket(
+            domain,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+            libc::IPPROTO_SCTP,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let on: libc::c_int = 1;
+    setsockopt(&socket, SCTP_NODELAY, &on.to_ne_bytes())?;
+    Ok(socket)
+}
+
+/// Packs the addresses into an array of sockaddr_in/sockaddr_in6 structures
+fn pack(addrs: &[SocketAddr]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    for addr in addrs {
+        match addr {
+            SocketAddr::V4(a) => {
+                let sa = libc::sockaddr_in {
+                    sin_family: libc::sa_family_t::try_from(libc::AF_INET)
+                        .map_err(Error::failed)?,
+                    sin_port: a.port().to_be(),
+                    sin_addr: libc::in_addr {
+                        s_addr: u32::from(*a.ip()).to_be(),
+                    },
+                    sin_zero: [0; 8],
+                };
+                buf.extend_from_slice(unsafe {
+                    core::slice::from_raw_parts(
+                        core::ptr::addr_of!(sa).cast::<u8>(),
+                        core::mem::size_of::<libc::sockaddr_in>(),
+                    )
+                });
+            }
+            SocketAddr::V6(a) => {
+                let sa = libc::sockaddr_in6 {
+                    sin6_family: libc::sa_family_t::try_from(libc::AF_INET6)
+                        .map_err(Error::failed)?,
+                    sin6_port: a.port().to_be(),
+                    sin6_flowinfo: a.flowinfo(),
+                    sin6_addr: libc::in6_addr {
+                        s6_addr: a.ip().octets(),
+                    },
+                    sin6_scope_id: a.scope_id(),
+                };
+                buf.extend_from_slice(unsafe {
+                    core::slice::from_raw_parts(
+                        core::ptr::addr_of!(sa).cast::<u8>(),
+                        core::mem::size_of::<libc::sockaddr_in6>(),
+                    )
+                });
+            }
+        }
+    }
+    Ok(buf)
+}
+
+fn setsockopt(socket: &OwnedFd, name: libc::c_int, value: &[u8]) -> Result<()> {
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            SOL_SCTP,
+            name,
+            value.as_ptr().cast(),
+            libc::socklen_t::try_from(value.len()).map_err(Error::failed)?,
+        )
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Connects to all (multi-homed) addresses of the peer. One-to-one style SCTP sockets share the
+/// TCP socket API, so the standard stream type is used for I/O
+fn connect(addrs: &[SocketAddr]) -> Result<TcpStream> {
+    let socket = socket(addrs)?;
+    setsockopt(&socket, SCTP_SOCKOPT_CONNECTX, &pack(addrs)?)?;
+    Ok(TcpStream::from(socket))
+}
+
+/// SCTP client
+///
+/// Connects to a (multi-homed) watchdog using all its addresses, so the association survives a
+/// single path failure. If the association is lost, the client tries to reconnect on the next
+/// beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct SctpHeart {
+    addrs: Vec<SocketAddr>,
+    stream: Mutex<Option<TcpStream>>,
+    next: AtomicBool,
+}
+
+impl SctpHeart {
+    /// creates a new SCTP client, the addresses are the watchdog addresses (of the same family and
+    /// port)
+    pub fn create(addrs: &[SocketAddr]) -> Result<Self> {
+        let stream = connect(addrs)?;
+        Ok(Self {
+            addrs: addrs.to_vec(),
+            stream: Mutex::new(Some(stream)),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for SctpHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(connect(&self.addrs)?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_mut().unwrap().write_all(&[edge as u8]) {
+            stream.take();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// SCTP watchdog I/O
+///
+/// Listens on all the given local addresses (multi-homing) and accepts a single heart association
+/// at a time, a newer association replaces the current one. If the association is broken, a
+/// timeout is reported immediately.
+#[allow(clippy::module_name_repetitions)]
+pub struct SctpIo {
+    listener: TcpListener,
+    stream: Mutex<Option<TcpStream>>,
+    timeout: Duration,
+}
+
+impl SctpIo {
+    /// creates a new SCTP watchdog I/O, the addresses must be of the same family and port
+    pub fn create(addrs: &[SocketAddr], timeout: Duration) -> Result<Self> {
+        let socket = socket(addrs)?;
+        setsockopt(&socket, SCTP_SOCKOPT_BINDX_ADD, &pack(addrs)?)?;
+        if unsafe { libc::listen(socket.as_raw_fd(), 1) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let listener = TcpListener::from(socket);
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            stream: Mutex::new(None),
+            timeout,
+        })
+    }
+}
+
+impl StreamListener for SctpIo {
+    type Stream = TcpStream;
+    fn accept_stream(&self) -> std::io::Result<TcpStream> {
+        let (stream, _) = self.listener.accept()?;
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+}
+
+impl WatchdogIo for SctpIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut stream = self.stream.lock();
+        // a pending association replaces the current one (e.g. the heart has been restarted)
+        if let Some(s) = super::try_accept(self)? {
+            stream.replace(s);
+        } else if stream.is_none() {
+            stream.replace(super::accept(self, self.timeout)?);
+        }
+        let mut buf = [0];
+        match stream.as_mut().unwrap().read(&mut buf) {
+            Ok(0) => {
+                // association shut down by the peer
+                stream.take();
+                Err(Error::Timeout)
+            }
+            Ok(_) => Ok(Edge::from(buf[0])),
+            Err(e) => {
+                if !matches!(Error::from(e), Error::Timeout) {
+                    // the association is broken (all paths have failed)
+                    stream.take();
+                }
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if let Some(s) = stream.as_mut() {
+            if super::drain(s).is_err() {
+                stream.take();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::io::{Read, Write};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+use serialport::{ClearBuffer, SerialPort};
+
+use super::WatchdogIo;
+
+/// Serial port client
+#[allow(clippy::module_name_repetitions)]
+pub struct SerialHeart {
+    port: Mutex<Box<dyn SerialPort>>,
+    next: AtomicBool,
+}
+
+impl SerialHeart {
+    /// creates a new serial port client
+    pub fn create(path: &str, baud_rate: u32) -> Result<Self> {
+        let port = serialport::new(path, baud_rate)
+            .open()
+            .map_err(Error::failed)?;
+        Ok(Self {
+            port: Mutex::new(port),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for SerialHeart {
+    fn beat(&self) -> Result<()> {
+        let mut port = self.port.lock();
+        port.write_all(&[Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8])?;
+        port.flush()?;
+        Ok(())
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+/// Serial port watchdog I/O configuration
+#[derive(Debug, Clone)]
+pub struct SerialConfig {
+    path: String,
+    baud_rate: u32,
+}
+
+impl SerialConfig {
+    /// creates a new serial port watchdog I/O configuration
+    pub fn new(path: &str, baud_rate: u32) -> Self {
+        Self {
+            path: path.to_owned(),
+            baud_rate,
+        }
+    }
+}
+
+/// Serial port watchdog I/O
+#[allow(clippy::module_name_repetitions)]
+pub struct SerialIo {
+    port: Mutex<Box<dyn SerialPort>>,
+}
+
+impl SerialIo {
+    /// creates a new serial port watchdog I/O, the timeout is used as the port read timeout
+    pub fn create(config: &SerialConfig, timeout: Duration) -> Result<Self> {
+        let port = serialport::new(&config.path, config.baud_rate)
+            .timeout(timeout)
+            .open()
+            .map_err(Error::failed)?;
+        Ok(Self {
+            port: Mutex::new(port),
+        })
+    }
+}
+
+impl WatchdogIo for SerialIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut buf = [0];
+        let mut port = self.port.lock();
+        while port.read(&mut buf)? == 0 {}
+        Ok(Edge::from(buf[0]))
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.port
+            .lock()
+            .clear(ClearBuffer::Input)
+            .map_err(Error::failed)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{Edge, Error, Heart, Result};
+use core::{ptr::NonNull, time::Duration};
+use std::{
+    ffi::CString,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use portable_atomic::{AtomicBool, AtomicU8, Ordering};
+
+use super::{poll_until, WatchdogIo};
+
+const SEGMENT_SIZE: usize = core::mem::size_of::<u64>();
+
+/// A memory-mapped segment, holding the current edge
+pub(crate) struct Segment {
+    ptr: NonNull<AtomicU8>,
+}
+
+// the segment is accessed via atomics only
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl Segment {
+    fn open_shm(name: &str) -> Result<Self> {
+        let c_name = CString::new(name).map_err(Error::failed)?;
+        let fd = unsafe { libc::shm_open(c_name.as_ptr(), libc::O_CREAT | libc::O_RDWR, 0o660) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Self::map(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+    pub(crate) fn map(fd: OwnedFd) -> Result<Self> {
+        #[allow(clippy::cast_possible_wrap)]
+        if unsafe { libc::ftruncate(fd.as_raw_fd(), SEGMENT_SIZE as libc::off_t) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                SEGMENT_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // the mapping is kept after the descriptor is closed
+        Ok(Self {
+            ptr: NonNull::new(ptr.cast()).ok_or_else(|| Error::failed("mmap returned null"))?,
+        })
+    }
+    pub(crate) fn edge(&self) -> &AtomicU8 {
+        // the mapping is page-aligned and valid until the segment is dropped
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), SEGMENT_SIZE);
+        }
+    }
+}
+
+/// Shared memory client
+#[allow(clippy::module_name_repetitions)]
+pub struct ShmHeart {
+    segment: Segment,
+    next: AtomicBool,
+}
+
+impl ShmHeart {
+    /// creates a new shared memory client, the segment name must start with a slash (e.g.
+    /// "/heartbeat"). The segment is created if it does not exist
+    pub fn create(name: &str) -> Result<Self> {
+        Ok(Self {
+            segment: Segment::open_shm(name)?,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for ShmHeart {
+    fn beat(&self) -> Result<()> {
+        self.segment.edge().store(
+            Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8,
+            Ordering::Release,
+        );
+        Ok(())
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+/// Shared memory watchdog I/O configuration
+#[derive(Debug, Clone)]
+pub struct ShmConfig {
+    name: String,
+    pull_interval: Duration,
+}
+
+impl ShmConfig {
+    /// creates a new shared memory watchdog I/O configuration
+    pub fn new(name: &str, pull_interval: Duration) -> Self {
+        Self {
+            name: name.to_owned(),
+            pull_interval,
+        }
+    }
+}
+
+/// Shared memory watchdog I/O
+///
+/// The segment is created if it does not exist and is unlinked when the I/O is dropped.
+#[allow(clippy::module_name_repetitions)]
+pub struct ShmIo {
+    segment: Segment,
+    name: CString,
+    timeout: Duration,
+    pull_interval: Duration,
+}
+
+impl ShmIo {
+    /// creates a new shared memory watchdog I/O
+    pub fn create(config: &ShmConfig, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            segment: Segment::open_shm(&config.name)?,
+            name: CString::new(config.name.as_str()).map_err(Error::failed)?,
+            timeout,
+            pull_interval: config.pull_interval,
+        })
+    }
+}
+
+impl WatchdogIo for ShmIo {
+    fn get(&self, expected: Edge) -> Result<Edge> {
+        poll_until(self.timeout, self.pull_interval, |_| {
+            let edge = Edge::from(self.segment.edge().load(Ordering::Acquire));
+            (edge == expected).then_some(edge)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ShmIo {
+    fn drop(&mut self) {
+        unsafe {
+            libc::shm_unlink(self.name.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PULL_INTERVAL: Duration = Duration::from_millis(1);
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_beats() {
+        let name = format!("/watchdog-test-{}", std::process::id());
+        let io = ShmIo::create(&ShmConfig::new(&name, PULL_INTERVAL), TIMEOUT).unwrap();
+        let heart = ShmHeart::create(&name).unwrap();
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+        // the edge has not been changed
+        assert!(matches!(io.get(Edge::Rising), Err(Error::Timeout)));
+    }
+}
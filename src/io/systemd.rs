@@ -0,0 +1,158 @@
+use crate::{Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    path::Path,
+    sync::OnceLock,
+};
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+const WATCHDOG_USEC: &str = "WATCHDOG_USEC";
+const WATCHDOG_PID: &str = "WATCHDOG_PID";
+const LISTEN_FDS: &str = "LISTEN_FDS";
+const LISTEN_PID: &str = "LISTEN_PID";
+const LISTEN_FDNAMES: &str = "LISTEN_FDNAMES";
+
+/// The first descriptor passed by the service manager
+const LISTEN_FDS_START: libc::c_int = 3;
+
+/// Takes the sockets passed by the service manager (socket activation, see
+/// `sd_listen_fds(3)`), returns the descriptors with their names (`FileDescriptorName=`, systemd
+/// uses the socket unit name by default).
+///
+/// The sockets can be used to create the watchdog I/O (e.g. [`super::udp::UdpIo::from_fd`],
+/// [`super::tcp::TcpIo::from_fd`]), so the watchdog can listen on privileged ports without
+/// running as root. The descriptors are taken by the first call only, the next calls return an
+/// empty list. The environment variables are kept (modifying the environment is unsound when
+/// other threads read it), the descriptors are not inherited by child processes as they are
+/// closed on exec and `LISTEN_PID` does not match the child ones. An empty list is returned if
+/// the process is not socket-activated.
+pub fn listen_fds() -> Result<Vec<(String, OwnedFd)>> {
+    static TAKEN: OnceLock<()> = OnceLock::new();
+    if TAKEN.set(()).is_err() {
+        return Ok(Vec::new());
+    }
+    let Some(count) = std::env::var_os(LISTEN_FDS) else {
+        return Ok(Vec::new());
+    };
+    let pid = std::env::var_os(LISTEN_PID);
+    let names = std::env::var(LISTEN_FDNAMES).unwrap_or_default();
+    let pid: u32 = pid
+        .and_then(|v| v.to_str()?.parse().ok())
+        .ok_or_else(|| Error::failed("invalid LISTEN_PID"))?;
+    if pid != std::process::id() {
+        // the descriptors are passed to another process (e.g. the parent one)
+        return Ok(Vec::new());
+    }
+    let count: libc::c_int = count
+        .to_str()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| Error::failed("invalid LISTEN_FDS"))?;
+    let mut names = names.split(':');
+    (LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(count))
+        .map(|fd| {
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let name = names.next().filter(|n| !n.is_empty()).unwrap_or("unknown");
+            Ok((name.to_owned(), unsafe { OwnedFd::from_raw_fd(fd) }))
+        })
+        .collect()
+}
+
+/// Checks that the descriptor is a socket of the given type (e.g. [`libc::SOCK_DGRAM`])
+pub(crate) fn check_socket(fd: &OwnedFd, kind: libc::c_int) -> Result<()> {
+    let mut value: libc::c_int = 0;
+    let mut len =
+        libc::socklen_t::try_from(core::mem::size_of::<libc::c_int>()).map_err(Error::failed)?;
+    if unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_TYPE,
+            core::ptr::addr_of_mut!(value).cast(),
+            &mut len,
+        )
+    } < 0
+    {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    if value != kind {
+        return Err(Error::failed("unexpected socket type"));
+    }
+    Ok(())
+}
+
+/// systemd notification (`sd_notify`) client
+///
+/// Each beat sends `WATCHDOG=1` to the service manager, so a service which beats to a watchdog
+/// of this crate can also satisfy systemd `WatchdogSec=` with the same heart (e.g. combined with
+/// other hearts into a single beat path). The beat interval should be less than a half of the
+/// systemd watchdog timeout (see [`SdNotifyHeart::watchdog_timeout`]).
+#[allow(clippy::module_name_repetitions)]
+pub struct SdNotifyHeart {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+}
+
+impl SdNotifyHeart {
+    /// creates a new client for the socket set by the service manager in `NOTIFY_SOCKET`
+    pub fn create() -> Result<Self> {
+        let path = std::env::var_os(NOTIFY_SOCKET)
+            .ok_or_else(|| Error::failed("NOTIFY_SOCKET is not set"))?;
+        Self::with_path(path)
+    }
+    /// creates a new client for the notification socket path (abstract socket names start with
+    /// `@`)
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let addr = match path.as_os_str().as_encoded_bytes() {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            [b'@', name @ ..] => {
+                #[cfg(target_os = "android")]
+                use std::os::android::net::SocketAddrExt;
+                #[cfg(target_os = "linux")]
+                use std::os::linux::net::SocketAddrExt;
+                SocketAddr::from_abstract_name(name)?
+            }
+            _ => SocketAddr::from_pathname(path)?,
+        };
+        Ok(Self {
+            socket: UnixDatagram::unbound()?,
+            addr,
+        })
+    }
+    /// sends a raw notification (e.g. `STATUS=...`), see `sd_notify(3)`
+    pub fn notify(&self, state: &str) -> Result<()> {
+        self.socket.send_to_addr(state.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+    /// notifies the service manager that the service startup is finished (`READY=1`)
+    pub fn ready(&self) -> Result<()> {
+        self.notify("READY=1")
+    }
+    /// notifies the service manager that the service is stopping (`STOPPING=1`)
+    pub fn stopping(&self) -> Result<()> {
+        self.notify("STOPPING=1")
+    }
+    /// returns the systemd watchdog timeout, if the watchdog is enabled for the service (and for
+    /// the current process)
+    pub fn watchdog_timeout() -> Option<Duration> {
+        if let Ok(pid) = std::env::var(WATCHDOG_PID) {
+            if pid.parse::<u32>().ok()? != std::process::id() {
+                return None;
+            }
+        }
+        let usec: u64 = std::env::var(WATCHDOG_USEC).ok()?.parse().ok()?;
+        (usec > 0).then(|| Duration::from_micros(usec))
+    }
+}
+
+impl Heart for SdNotifyHeart {
+    fn beat(&self) -> Result<()> {
+        self.notify("WATCHDOG=1")
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{Edge, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::Write,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    os::fd::OwnedFd,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+
+use super::{StreamListener, WatchdogIo};
+
+/// TCP client
+///
+/// The client keeps a persistent connection to the watchdog. If the connection is lost, the
+/// client tries to reconnect on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct TcpHeart {
+    addrs: Vec<SocketAddr>,
+    stream: Mutex<Option<TcpStream>>,
+    next: AtomicBool,
+}
+
+impl TcpHeart {
+    /// creates a new TCP client
+    pub fn create<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let stream = connect(&addrs)?;
+        Ok(Self {
+            addrs,
+            stream: Mutex::new(Some(stream)),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+fn connect(addrs: &[SocketAddr]) -> Result<TcpStream> {
+    let stream = TcpStream::connect(addrs)?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+impl Heart for TcpHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(connect(&self.addrs)?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_mut().unwrap().write_all(&[edge as u8]) {
+            stream.take();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// TCP watchdog I/O
+///
+/// Accepts a single heart connection at a time, a newer connection replaces the current one. If
+/// the connection is closed by the peer, a timeout is reported immediately.
+#[allow(clippy::module_name_repetitions)]
+pub struct TcpIo {
+    listener: TcpListener,
+    stream: Mutex<Option<TcpStream>>,
+    timeout: Duration,
+}
+
+impl TcpIo {
+    /// creates a new TCP watchdog I/O
+    pub fn create<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            stream: Mutex::new(None),
+            timeout,
+        })
+    }
+    /// creates a new TCP watchdog I/O from a listening socket, e.g. inherited from the service
+    /// manager (see [`super::systemd::listen_fds`])
+    pub fn from_fd(fd: OwnedFd, timeout: Duration) -> Result<Self> {
+        super::systemd::check_socket(&fd, libc::SOCK_STREAM)?;
+        let listener = TcpListener::from(fd);
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            stream: Mutex::new(None),
+            timeout,
+        })
+    }
+}
+
+impl StreamListener for TcpIo {
+    type Stream = TcpStream;
+    fn accept_stream(&self) -> std::io::Result<TcpStream> {
+        let (stream, _) = self.listener.accept()?;
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+}
+
+impl WatchdogIo for TcpIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        super::read_edge(self, &mut self.stream.lock(), self.timeout)
+    }
+
+    fn clear(&self) -> Result<()> {
+        super::clear_stream(&mut self.stream.lock());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn pair() -> (TcpHeart, TcpIo) {
+        let io = TcpIo::create("127.0.0.1:0", TIMEOUT).unwrap();
+        let heart = TcpHeart::create(io.listener.local_addr().unwrap()).unwrap();
+        (heart, io)
+    }
+
+    #[test]
+    fn test_beats() {
+        let (heart, io) = pair();
+        heart.beat().unwrap();
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert_eq!(io.get(Edge::Falling).unwrap(), Edge::Falling);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (heart, io) = pair();
+        heart.beat().unwrap();
+        assert_eq!(io.get(Edge::Rising).unwrap(), Edge::Rising);
+        assert!(matches!(io.get(Edge::Falling), Err(Error::Timeout)));
+        // the connection is closed by the peer
+        drop(heart);
+        assert!(matches!(io.get(Edge::Falling), Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::Path,
+    sync::Arc,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+    server::WebPkiClientVerifier,
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+
+pub use rustls;
+
+use super::{StreamListener, StreamSocket, WatchdogIo};
+
+type ClientStream = StreamOwned<ClientConnection, TcpStream>;
+type ServerStream = StreamOwned<ServerConnection, TcpStream>;
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(Iterator::collect)
+        .map_err(|e| Error::failed(format!("{}: {}", path.display(), e)))
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|e| Error::failed(format!("{}: {}", path.display(), e)))
+}
+
+fn load_roots(path: &Path) -> Result<Arc<RootCertStore>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(Error::failed)?;
+    }
+    Ok(Arc::new(roots))
+}
+
+/// creates a client configuration from PEM files: the CA certificate(s) to verify the watchdog,
+/// the client certificate chain and the client private key
+pub fn client_config<P: AsRef<Path>>(ca: P, cert: P, key: P) -> Result<Arc<ClientConfig>> {
+    let config = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(Error::failed)?
+        .with_root_certificates(load_roots(ca.as_ref())?)
+        .with_client_auth_cert(load_certs(cert.as_ref())?, load_key(key.as_ref())?)
+        .map_err(Error::failed)?;
+    Ok(Arc::new(config))
+}
+
+/// creates a watchdog configuration from PEM files: the CA certificate(s) to verify client
+/// certificates (required), the server certificate chain and the server private key
+pub fn server_config<P: AsRef<Path>>(ca: P, cert: P, key: P) -> Result<Arc<ServerConfig>> {
+    let provider = provider();
+    let verifier =
+        WebPkiClientVerifier::builder_with_provider(load_roots(ca.as_ref())?, provider.clone())
+            .build()
+            .map_err(Error::failed)?;
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(Error::failed)?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(load_certs(cert.as_ref())?, load_key(key.as_ref())?)
+        .map_err(Error::failed)?;
+    Ok(Arc::new(config))
+}
+
+/// TLS client
+///
+/// The client keeps a persistent TLS connection to the watchdog. If the connection is lost, the
+/// client tries to reconnect on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct TlsHeart {
+    addrs: Vec<SocketAddr>,
+    server_name: ServerName<'static>,
+    config: Arc<ClientConfig>,
+    stream: Mutex<Option<ClientStream>>,
+    next: AtomicBool,
+}
+
+impl TlsHeart {
+    /// creates a new TLS client, the server name must match the watchdog certificate (see
+    /// [`client_config`] to create the configuration)
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        server_name: &str,
+        config: Arc<ClientConfig>,
+    ) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let server_name = ServerName::try_from(server_name.to_owned()).map_err(Error::failed)?;
+        let stream = connect(&addrs, &server_name, &config)?;
+        Ok(Self {
+            addrs,
+            server_name,
+            config,
+            stream: Mutex::new(Some(stream)),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+fn connect(
+    addrs: &[SocketAddr],
+    server_name: &ServerName<'static>,
+    config: &Arc<ClientConfig>,
+) -> Result<ClientStream> {
+    let mut sock = TcpStream::connect(addrs)?;
+    sock.set_nodelay(true)?;
+    let mut conn =
+        ClientConnection::new(config.clone(), server_name.clone()).map_err(Error::failed)?;
+    // complete the handshake, so the certificate errors are reported immediately
+    while conn.is_handshaking() {
+        conn.complete_io(&mut sock)?;
+    }
+    Ok(StreamOwned::new(conn, sock))
+}
+
+impl Heart for TlsHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(connect(&self.addrs, &self.server_name, &self.config)?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_mut().unwrap().write_all(&[edge as u8]) {
+            stream.take();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// TLS watchdog I/O
+///
+/// Accepts a single heart connection at a time, a newer connection replaces the current one.
+/// Hearts must present a certificate signed by the configured CA (see [`server_config`]),
+/// connections which fail the handshake are dropped and reported as timeouts.
+#[allow(clippy::module_name_repetitions)]
+pub struct TlsIo {
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    stream: Mutex<Option<ServerStream>>,
+    timeout: Duration,
+}
+
+impl TlsIo {
+    /// creates a new TLS watchdog I/O
+    pub fn create<A: ToSocketAddrs>(
+        addr: A,
+        config: Arc<ServerConfig>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            config,
+            stream: Mutex::new(None),
+            timeout,
+        })
+    }
+}
+
+impl StreamListener for TlsIo {
+    type Stream = ServerStream;
+    fn accept_stream(&self) -> std::io::Result<ServerStream> {
+        let (sock, _) = self.listener.accept()?;
+        sock.set_nonblocking(false)?;
+        sock.set_nodelay(true)?;
+        sock.set_read_timeout(Some(self.timeout))?;
+        // the handshake is completed by the first read
+        let conn = ServerConnection::new(self.config.clone()).map_err(std::io::Error::other)?;
+        Ok(StreamOwned::new(conn, sock))
+    }
+}
+
+impl StreamSocket for ServerStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        self.sock.set_nonblocking(nonblocking)
+    }
+}
+
+impl WatchdogIo for TlsIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut stream = self.stream.lock();
+        // a pending connection replaces the current one (e.g. the heart has been restarted)
+        if let Some(s) = super::try_accept(self)? {
+            stream.replace(s);
+        } else if stream.is_none() {
+            stream.replace(super::accept(self, self.timeout)?);
+        }
+        let mut buf = [0];
+        match stream.as_mut().unwrap().read(&mut buf) {
+            Ok(0) => {
+                // connection closed by the peer
+                stream.take();
+                Err(Error::Timeout)
+            }
+            Ok(_) => Ok(Edge::from(buf[0])),
+            Err(e) => {
+                if !matches!(Error::from(e), Error::Timeout) {
+                    // the connection is broken or the handshake has failed (e.g. an untrusted
+                    // client certificate)
+                    stream.take();
+                }
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if let Some(s) = stream.as_mut() {
+            if super::drain(s).is_err() {
+                stream.take();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    io::Write,
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixDatagram, UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    thread,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+
+use super::{StreamListener, WatchdogIo};
+
+/// Unix datagram socket client
+#[allow(clippy::module_name_repetitions)]
+pub struct UnixDatagramHeart {
+    socket: UnixDatagram,
+    next: AtomicBool,
+}
+
+impl UnixDatagramHeart {
+    /// creates a new Unix datagram socket client
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for UnixDatagramHeart {
+    fn beat(&self) -> Result<()> {
+        self.socket
+            .send(&[Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8])
+            .map_err(Error::from)?;
+        Ok(())
+    }
+}
+
+/// Unix datagram socket watchdog I/O
+///
+/// The socket file is removed when the I/O is dropped. A stale socket file (e.g. left after a
+/// crash) is removed on creation, any other existing file at the path is an error.
+#[allow(clippy::module_name_repetitions)]
+pub struct UnixDatagramIo {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl UnixDatagramIo {
+    /// creates a new Unix datagram socket watchdog I/O
+    pub fn create<P: AsRef<Path>>(path: P, timeout: Duration) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let path = path.as_ref().to_path_buf();
+        remove_stale_socket(&path)?;
+        let socket = UnixDatagram::bind(&path)?;
+        socket.set_read_timeout(Some(timeout))?;
+        Ok(Self { socket, path })
+    }
+}
+
+// removes a socket file left by a previous instance, other files are never removed
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => Ok(std::fs::remove_file(path)?),
+        Ok(_) => Err(Error::failed(format!(
+            "{} already exists and is not a socket",
+            path.display()
+        ))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl WatchdogIo for UnixDatagramIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut buf = [0];
+        while self.socket.recv(&mut buf)? == 0 {}
+        Ok(Edge::from(buf[0]))
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.socket.set_nonblocking(true)?;
+        while self.socket.recv(&mut [0]).is_ok() {
+            thread::yield_now();
+        }
+        self.socket.set_nonblocking(false)?;
+        Ok(())
+    }
+}
+
+impl Drop for UnixDatagramIo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Unix stream socket client
+///
+/// The client keeps a persistent connection to the watchdog. If the connection is lost, the
+/// client tries to reconnect on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct UnixStreamHeart {
+    path: PathBuf,
+    stream: Mutex<Option<UnixStream>>,
+    next: AtomicBool,
+}
+
+impl UnixStreamHeart {
+    /// creates a new Unix stream socket client
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let stream = UnixStream::connect(&path)?;
+        Ok(Self {
+            path,
+            stream: Mutex::new(Some(stream)),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for UnixStreamHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(UnixStream::connect(&self.path)?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_mut().unwrap().write_all(&[edge as u8]) {
+            stream.take();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Unix stream socket watchdog I/O
+///
+/// Accepts a single heart connection at a time, a newer connection replaces the current one. If
+/// the connection is closed by the peer (e.g. the peer process has died), a timeout is reported
+/// immediately.
+///
+/// The socket file is removed when the I/O is dropped. A stale socket file (e.g. left after a
+/// crash) is removed on creation, any other existing file at the path is an error.
+#[allow(clippy::module_name_repetitions)]
+pub struct UnixStreamIo {
+    listener: UnixListener,
+    stream: Mutex<Option<UnixStream>>,
+    timeout: Duration,
+    path: PathBuf,
+}
+
+impl UnixStreamIo {
+    /// creates a new Unix stream socket watchdog I/O
+    pub fn create<P: AsRef<Path>>(path: P, timeout: Duration) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let path = path.as_ref().to_path_buf();
+        remove_stale_socket(&path)?;
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            stream: Mutex::new(None),
+            timeout,
+            path,
+        })
+    }
+}
+
+impl StreamListener for UnixStreamIo {
+    type Stream = UnixStream;
+    fn accept_stream(&self) -> std::io::Result<UnixStream> {
+        let (stream, _) = self.listener.accept()?;
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+}
+
+impl WatchdogIo for UnixStreamIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        super::read_edge(self, &mut self.stream.lock(), self.timeout)
+    }
+
+    fn clear(&self) -> Result<()> {
+        super::clear_stream(&mut self.stream.lock());
+        Ok(())
+    }
+}
+
+impl Drop for UnixStreamIo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
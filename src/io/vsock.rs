@@ -0,0 +1,250 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+use std::{
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    thread,
+};
+
+use portable_atomic::{AtomicBool, Ordering};
+use rtsc::pi::Mutex;
+
+use super::{StreamListener, WatchdogIo};
+
+/// Well-known CID of the hypervisor host
+pub const CID_HOST: u32 = libc::VMADDR_CID_HOST;
+/// Well-known CID for local (loopback) communication
+pub const CID_LOCAL: u32 = libc::VMADDR_CID_LOCAL;
+
+fn cvt(res: libc::c_int) -> std::io::Result<libc::c_int> {
+    if res < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(res)
+}
+
+fn cvt_size(res: libc::ssize_t) -> std::io::Result<usize> {
+    usize::try_from(res).map_err(|_| std::io::Error::last_os_error())
+}
+
+fn socket(flags: libc::c_int) -> Result<OwnedFd> {
+    let fd = cvt(unsafe {
+        libc::socket(
+            libc::AF_VSOCK,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC | flags,
+            0,
+        )
+    })?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn sockaddr(cid: u32, port: u32) -> Result<libc::sockaddr_vm> {
+    let mut addr: libc::sockaddr_vm = unsafe { core::mem::zeroed() };
+    addr.svm_family = libc::sa_family_t::try_from(libc::AF_VSOCK).map_err(Error::failed)?;
+    addr.svm_cid = cid;
+    addr.svm_port = port;
+    Ok(addr)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const SOCKADDR_VM_LEN: libc::socklen_t = core::mem::size_of::<libc::sockaddr_vm>() as _;
+
+/// Connected vsock stream
+pub(crate) struct Stream {
+    fd: OwnedFd,
+}
+
+impl Stream {
+    fn connect(cid: u32, port: u32) -> Result<Self> {
+        let fd = socket(0)?;
+        let addr = sockaddr(cid, port)?;
+        cvt(unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                core::ptr::addr_of!(addr).cast(),
+                SOCKADDR_VM_LEN,
+            )
+        })?;
+        Ok(Self { fd })
+    }
+    #[allow(clippy::cast_possible_wrap)]
+    fn set_read_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: libc::suseconds_t::from(timeout.subsec_micros()),
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        cvt(unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                core::ptr::addr_of!(tv).cast(),
+                core::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        })?;
+        Ok(())
+    }
+    fn send(&self, buf: &[u8]) -> std::io::Result<usize> {
+        cvt_size(unsafe {
+            libc::send(
+                self.fd.as_raw_fd(),
+                buf.as_ptr().cast(),
+                buf.len(),
+                libc::MSG_NOSIGNAL,
+            )
+        })
+    }
+    fn recv(&self, buf: &mut [u8], flags: libc::c_int) -> std::io::Result<usize> {
+        cvt_size(unsafe {
+            libc::recv(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                flags,
+            )
+        })
+    }
+    /// reads all pending data, returns an error if the connection is closed or broken
+    fn drain(&self) -> std::io::Result<()> {
+        let mut buf = [0; 64];
+        loop {
+            match self.recv(&mut buf, libc::MSG_DONTWAIT) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => thread::yield_now(),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// vsock client
+///
+/// The client keeps a persistent connection to the watchdog. If the connection is lost, the
+/// client tries to reconnect on the next beat.
+#[allow(clippy::module_name_repetitions)]
+pub struct VsockHeart {
+    cid: u32,
+    port: u32,
+    stream: Mutex<Option<Stream>>,
+    next: AtomicBool,
+}
+
+impl VsockHeart {
+    /// creates a new vsock client, connecting to the context (e.g. [`CID_HOST`] for a VM guest or
+    /// a guest CID for the host)
+    pub fn create(cid: u32, port: u32) -> Result<Self> {
+        let stream = Stream::connect(cid, port)?;
+        Ok(Self {
+            cid,
+            port,
+            stream: Mutex::new(Some(stream)),
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for VsockHeart {
+    fn beat(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if stream.is_none() {
+            stream.replace(Stream::connect(self.cid, self.port)?);
+        }
+        let edge = Edge::from(self.next.fetch_xor(true, Ordering::Relaxed));
+        if let Err(e) = stream.as_ref().unwrap().send(&[edge as u8]) {
+            stream.take();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// vsock watchdog I/O
+///
+/// Listens on the port for all contexts and accepts a single heart connection at a time, a newer
+/// connection replaces the current one. If the connection is broken (e.g. the VM has been
+/// stopped), a timeout is reported immediately.
+#[allow(clippy::module_name_repetitions)]
+pub struct VsockIo {
+    listener: OwnedFd,
+    stream: Mutex<Option<Stream>>,
+    timeout: Duration,
+}
+
+impl VsockIo {
+    /// creates a new vsock watchdog I/O
+    pub fn create(port: u32, timeout: Duration) -> Result<Self> {
+        let listener = socket(libc::SOCK_NONBLOCK)?;
+        let addr = sockaddr(libc::VMADDR_CID_ANY, port)?;
+        cvt(unsafe {
+            libc::bind(
+                listener.as_raw_fd(),
+                core::ptr::addr_of!(addr).cast(),
+                SOCKADDR_VM_LEN,
+            )
+        })?;
+        cvt(unsafe { libc::listen(listener.as_raw_fd(), 1) })?;
+        Ok(Self {
+            listener,
+            stream: Mutex::new(None),
+            timeout,
+        })
+    }
+}
+
+impl StreamListener for VsockIo {
+    type Stream = Stream;
+    fn accept_stream(&self) -> std::io::Result<Stream> {
+        let fd = cvt(unsafe {
+            libc::accept4(
+                self.listener.as_raw_fd(),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+                libc::SOCK_CLOEXEC,
+            )
+        })?;
+        let stream = Stream {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        };
+        stream.set_read_timeout(self.timeout)?;
+        Ok(stream)
+    }
+}
+
+impl WatchdogIo for VsockIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        let mut stream = self.stream.lock();
+        // a pending connection replaces the current one (e.g. the heart has been restarted)
+        if let Some(s) = super::try_accept(self)? {
+            stream.replace(s);
+        } else if stream.is_none() {
+            stream.replace(super::accept(self, self.timeout)?);
+        }
+        let mut buf = [0];
+        match stream.as_ref().unwrap().recv(&mut buf, 0) {
+            Ok(0) => {
+                // connection closed by the peer
+                stream.take();
+                Err(Error::Timeout)
+            }
+            Ok(_) => Ok(Edge::from(buf[0])),
+            Err(e) => {
+                if !matches!(Error::from(e), Error::Timeout) {
+                    // the stream is broken, the peer is dead
+                    stream.take();
+                }
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut stream = self.stream.lock();
+        if let Some(s) = stream.as_ref() {
+            if s.drain().is_err() {
+                stream.take();
+            }
+        }
+        Ok(())
+    }
+}
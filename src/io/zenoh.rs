@@ -0,0 +1,103 @@
+use crate::{Edge, Error, Heart, Result};
+use core::time::Duration;
+
+use portable_atomic::{AtomicBool, Ordering};
+use zenoh::{
+    handlers::FifoChannelHandler, pubsub::Publisher, pubsub::Subscriber, sample::Sample, Session,
+    Wait,
+};
+
+pub use zenoh::Config;
+
+use super::WatchdogIo;
+
+/// zenoh client
+///
+/// Edges are published to the key expression as single-byte payloads.
+#[allow(clippy::module_name_repetitions)]
+pub struct ZenohHeart {
+    publisher: Publisher<'static>,
+    _session: Session,
+    next: AtomicBool,
+}
+
+impl ZenohHeart {
+    /// creates a new zenoh client, opening a new session
+    pub fn create(config: Config, key_expr: &str) -> Result<Self> {
+        let session = zenoh::open(config).wait().map_err(Error::failed)?;
+        Self::with_session(session, key_expr)
+    }
+    /// creates a new zenoh client with an existing session
+    pub fn with_session(session: Session, key_expr: &str) -> Result<Self> {
+        let publisher = session
+            .declare_publisher(key_expr.to_owned())
+            .wait()
+            .map_err(Error::failed)?;
+        Ok(Self {
+            publisher,
+            _session: session,
+            next: AtomicBool::new(true),
+        })
+    }
+}
+
+impl Heart for ZenohHeart {
+    fn beat(&self) -> Result<()> {
+        self.publisher
+            .put([Edge::from(self.next.fetch_xor(true, Ordering::Relaxed)) as u8].to_vec())
+            .wait()
+            .map_err(Error::failed)
+    }
+}
+
+/// zenoh watchdog I/O
+///
+/// Subscribes to the key expression. All received samples are edges of a single heart, so the
+/// expression must match a single publisher: interleaved edges of several publishers (e.g. matched
+/// by wildcards) are not distinguished.
+#[allow(clippy::module_name_repetitions)]
+pub struct ZenohIo {
+    subscriber: Subscriber<FifoChannelHandler<Sample>>,
+    _session: Session,
+    timeout: Duration,
+}
+
+impl ZenohIo {
+    /// creates a new zenoh watchdog I/O, opening a new session
+    pub fn create(config: Config, key_expr: &str, timeout: Duration) -> Result<Self> {
+        let session = zenoh::open(config).wait().map_err(Error::failed)?;
+        Self::with_session(session, key_expr, timeout)
+    }
+    /// creates a new zenoh watchdog I/O with an existing session
+    pub fn with_session(session: Session, key_expr: &str, timeout: Duration) -> Result<Self> {
+        let subscriber = session
+            .declare_subscriber(key_expr.to_owned())
+            .wait()
+            .map_err(Error::failed)?;
+        Ok(Self {
+            subscriber,
+            _session: session,
+            timeout,
+        })
+    }
+}
+
+impl WatchdogIo for ZenohIo {
+    fn get(&self, _expected: Edge) -> Result<Edge> {
+        loop {
+            let sample = self
+                .subscriber
+                .recv_timeout(self.timeout)
+                .map_err(Error::failed)?
+                .ok_or(Error::Timeout)?;
+            if let Some(b) = sample.payload().to_bytes().first() {
+                return Ok(Edge::from(*b));
+            }
+        }
+    }
+
+    fn clear(&self) -> Result<()> {
+        while let Ok(Some(_)) = self.subscriber.try_recv() {}
+        Ok(())
+    }
+}
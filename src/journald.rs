@@ -0,0 +1,123 @@
+use core::time::Duration;
+use std::{
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use rtsc::pi::Mutex;
+
+use crate::{FaultKind, Result, State, StateEvent, StateSource};
+
+/// The journal socket of the native protocol
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+// syslog priorities
+const PRIORITY_ERR: u8 = 3;
+const PRIORITY_WARNING: u8 = 4;
+const PRIORITY_INFO: u8 = 6;
+
+/// journald structured logging notifier
+///
+/// State transitions are written to the journal (native protocol) with the fields:
+///
+/// * `WATCHDOG_NAME` - the watchdog name
+/// * `WATCHDOG_STATE` - the state after the transition (`Fault`, `Ok` or `Warning`)
+/// * `WATCHDOG_EVENT` - the event (`Fault`, `Ok`, `Warning`, `Paused`, `Degraded`,
+///   `HighLatency` or `Terminated`)
+/// * `FAULT_KIND` - the fault kind (faults only)
+/// * `ELAPSED_US` - microseconds since the previous transition
+///
+/// so the entries can be queried directly, e.g. `journalctl WATCHDOG_NAME=plc1 FAULT_KIND=Timeout`.
+/// The priority is `err` for faults, `warning` for warnings and degradations, `info` for others.
+pub struct JournalNotifier {
+    socket: UnixDatagram,
+    path: PathBuf,
+    identifier: Option<String>,
+}
+
+impl JournalNotifier {
+    /// creates a new notifier for the system journal
+    pub fn create() -> Result<Self> {
+        Self::with_path(JOURNAL_SOCKET)
+    }
+    /// creates a new notifier for the journal socket path
+    pub fn with_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        // the notifications are sent from the watchdog run loop, dropped if the journal is busy
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            path: path.as_ref().to_owned(),
+            identifier: None,
+        })
+    }
+    /// Set the syslog identifier (`SYSLOG_IDENTIFIER`, for `journalctl -t`)
+    pub fn with_identifier(mut self, identifier: &str) -> Self {
+        self.identifier = Some(identifier.to_owned());
+        self
+    }
+    /// Write an entry for the state event, the elapsed time is the time since the previous
+    /// transition
+    pub fn send(&self, name: &str, event: &StateEvent, elapsed: Duration) -> Result<()> {
+        let (priority, event_name) = match event {
+            StateEvent::Fault(_) => (PRIORITY_ERR, "Fault"),
+            StateEvent::Terminated => (PRIORITY_ERR, "Terminated"),
+            StateEvent::Warning => (PRIORITY_WARNING, "Warning"),
+            StateEvent::Degraded { .. } => (PRIORITY_WARNING, "Degraded"),
+            StateEvent::HighLatency { .. } => (PRIORITY_WARNING, "HighLatency"),
+            StateEvent::Ok => (PRIORITY_INFO, "Ok"),
+            StateEvent::Paused => (PRIORITY_INFO, "Paused"),
+        };
+        let mut entry = Vec::new();
+        field(&mut entry, "MESSAGE", &format!("{}: {:?}", name, event));
+        field(&mut entry, "PRIORITY", &priority.to_string());
+        if let Some(ref identifier) = self.identifier {
+            field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+        }
+        field(&mut entry, "WATCHDOG_NAME", name);
+        field(
+            &mut entry,
+            "WATCHDOG_STATE",
+            &format!("{:?}", State::from(event.clone())),
+        );
+        field(&mut entry, "WATCHDOG_EVENT", event_name);
+        if let StateEvent::Fault(kind) = event {
+            field(&mut entry, "FAULT_KIND", &format!("{:?}", kind));
+        }
+        field(&mut entry, "ELAPSED_US", &elapsed.as_micros().to_string());
+        self.socket.send_to(&entry, &self.path)?;
+        Ok(())
+    }
+    /// Write the entries on the state transitions of the watchdog (or any other state source),
+    /// write errors are ignored
+    pub fn attach<S: StateSource>(self, source: &S) {
+        let name = source.name().unwrap_or("watchdog").to_owned();
+        let last_transition = Mutex::new(Instant::now());
+        source.on_state_change(Box::new(move |event| {
+            let elapsed = {
+                let mut last_transition = last_transition.lock();
+                let now = Instant::now();
+                let elapsed = now.duration_since(*last_transition);
+                *last_transition = now;
+                elapsed
+            };
+            if *event != StateEvent::Fault(FaultKind::Initial) {
+                let _ = self.send(&name, event, elapsed);
+            }
+        }));
+    }
+}
+
+/// Appends a field, the values which contain newlines are written in the binary form
+fn field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
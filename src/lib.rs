@@ -2,18 +2,79 @@
 #![deny(missing_docs)]
 #![ doc = include_str!( concat!( env!( "CARGO_MANIFEST_DIR" ), "/", "README.md" ) ) ]
 use core::{future::Future, ops, time::Duration};
-#[cfg(feature = "embassy")]
-use embassy_time::Instant;
 #[cfg(feature = "std")]
-use std::{sync::Arc, time::Instant};
+use std::sync::Arc;
 
+#[cfg(not(feature = "std"))]
+use io::embassy::embassy_duration;
 use io::{WatchdogIo, WatchdogIoAsync};
-use portable_atomic::{AtomicBool, Ordering};
+use portable_atomic::{AtomicBool, AtomicU8, Ordering};
 #[cfg(feature = "std")]
 use rtsc::{policy_channel, policy_channel_async};
 
+/// External command actions
+#[cfg(feature = "std")]
+pub mod actions;
+/// Beat authentication
+#[cfg(feature = "auth")]
+pub mod auth;
+/// EVA ICS integration
+#[cfg(feature = "eva")]
+pub mod eva;
+/// Linux hardware watchdog (/dev/watchdog) feeding
+#[cfg(feature = "std")]
+pub mod hardware;
 /// Watchdog I/O
 pub mod io;
+/// journald structured logging
+#[cfg(feature = "std")]
+pub mod journald;
+/// MQTT state publishing
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+/// Heartbeat payloads
+pub mod payload;
+/// roboplc integration
+#[cfg(feature = "roboplc")]
+pub mod roboplc;
+/// SNMP trap notifications
+#[cfg(feature = "snmp")]
+pub mod snmp;
+/// HTTP webhook notifications
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+mod builder;
+pub use builder::WatchdogBuilder;
+mod clock;
+pub use clock::{Clock, MockClock, SystemClock};
+#[cfg(feature = "std")]
+mod composite;
+#[cfg(feature = "std")]
+pub use composite::{CompositeBuilder, CompositeWatchdog, GatedHeart, Logic, StateSource};
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "std")]
+mod escalation;
+#[cfg(feature = "std")]
+use escalation::Escalator;
+#[cfg(feature = "std")]
+pub use escalation::{Escalation, EscalationAction, EscalationPolicy};
+#[cfg(feature = "std")]
+mod multi;
+#[cfg(feature = "std")]
+pub use multi::{MultiWatchdog, NodeEvent};
+mod logging;
+mod runner;
+#[cfg(feature = "std")]
+pub use runner::HeartRunner;
+pub use runner::HeartRunnerAsync;
+mod stats;
+use stats::AvailabilityTracker;
+pub use stats::{
+    Availability, Histogram, History, HistoryEntry, WatchdogStats, WatchdogStatus,
+    MAX_HISTOGRAM_BUCKETS, MAX_HISTORY,
+};
 
 /// Errors
 #[derive(thiserror::Error, Debug)]
@@ -25,6 +86,15 @@ pub enum Error {
     /// Timeout
     #[error("Timed out")]
     Timeout,
+    /// Heartbeats lost (reported by I/Os which support sequence numbers)
+    #[error("Heartbeats lost")]
+    Lost,
+    /// Challenge not answered in time (reported by I/Os which support challenge-response)
+    #[error("Challenge not answered")]
+    Challenge,
+    /// Invalid watchdog configuration
+    #[error("Invalid configuration: {0}")]
+    Config(&'static str),
     /// All other errors
     #[cfg(feature = "std")]
     #[error("Failed: {0}")]
@@ -65,19 +135,123 @@ pub type Result<T> = core::result::Result<T, Error>;
 type RawMutex = rtsc::pi::RawMutex;
 #[cfg(feature = "std")]
 type Condvar = rtsc::pi::Condvar;
+#[cfg(feature = "std")]
+type StateCallback = Box<dyn Fn(&StateEvent) + Send + Sync>;
+#[cfg(feature = "std")]
+type ErrorCallback = Box<dyn Fn(&Error) + Send + Sync>;
+/// Subscriber queue size, if a subscriber is too slow, the oldest events are dropped
+#[cfg(feature = "std")]
+const SUBSCRIBER_QUEUE_SIZE: usize = 64;
 #[cfg(feature = "embassy")]
 type NoopMutex = embassy_sync::blocking_mutex::raw::NoopRawMutex;
 #[cfg(feature = "embassy")]
 /// Embassy state channel
 pub type EmbassyStateChannel = embassy_sync::channel::Channel<NoopMutex, StateEvent, 32>;
 
+/// Data shared between the run loop and the API
+struct Shared<T> {
+    #[cfg(feature = "std")]
+    inner: rtsc::pi::Mutex<T>,
+    #[cfg(not(feature = "std"))]
+    inner: embassy_sync::blocking_mutex::Mutex<
+        embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+        core::cell::RefCell<T>,
+    >,
+}
+
+impl<T> Shared<T> {
+    fn new(value: T) -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            inner: rtsc::pi::Mutex::new(value),
+            #[cfg(not(feature = "std"))]
+            inner: embassy_sync::blocking_mutex::Mutex::new(core::cell::RefCell::new(value)),
+        }
+    }
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        #[cfg(feature = "std")]
+        return f(&mut self.inner.lock());
+        #[cfg(not(feature = "std"))]
+        self.inner.lock(|v| f(&mut v.borrow_mut()))
+    }
+}
+
+/// Parse a duration with a unit suffix: "ns", "us", "ms", "s", "m" or "h" (e.g. "100ms"), up to
+/// [`MAX_DURATION`]
+#[cfg(feature = "std")]
+fn parse_duration(s: &str) -> Result<Duration> {
+    const OVERFLOW: Error = Error::Config("duration exceeds MAX_DURATION");
+    let s = s.trim();
+    let pos = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or(Error::Config("duration unit missing"))?;
+    let (value, unit) = s.split_at(pos);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| Error::Config("invalid duration"))?;
+    let duration = match unit.trim() {
+        "ns" => Duration::from_nanos(value),
+        "us" | "µs" => Duration::from_micros(value),
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        "m" => value
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or(OVERFLOW)?,
+        "h" => value
+            .checked_mul(3600)
+            .map(Duration::from_secs)
+            .ok_or(OVERFLOW)?,
+        _ => return Err(Error::Config("invalid duration unit")),
+    };
+    if duration > MAX_DURATION {
+        return Err(OVERFLOW);
+    }
+    Ok(duration)
+}
+
 /// State event
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StateEvent {
     /// Watchdog switched to Fault state
     Fault(FaultKind),
     /// Watchdog switched to OK state
     Ok,
+    /// Heartbeats arrive late but still within the I/O timeout (only if enabled with
+    /// [`WatchdogConfig::with_warning`])
+    Warning,
+    /// Watchdog has been paused and switched to OK state, faults are not evaluated until it is
+    /// resumed
+    Paused,
+    /// Heartbeats have been lost but the losses are tolerated by the I/O (only if enabled with
+    /// [`WatchdogConfig::with_degraded`]), the state is not changed
+    Degraded {
+        /// The number of consecutive beats lost
+        lost: u32,
+    },
+    /// A beat has been received with a high one-way latency (timestamped protocols only, if
+    /// enabled with [`WatchdogConfig::with_high_latency`]), the state is not changed
+    HighLatency {
+        /// The one-way latency of the beat
+        latency: Duration,
+    },
+    /// Watchdog run has been terminated with an error, no more events are sent (a terminated
+    /// watchdog is considered as Fault)
+    Terminated,
+}
+
+impl StateEvent {
+    /// The state after the event, `None` for the events which do not change the state
+    /// ([`StateEvent::Degraded`] and [`StateEvent::HighLatency`])
+    pub fn state(&self) -> Option<State> {
+        match self {
+            StateEvent::Ok | StateEvent::Paused => Some(State::Ok),
+            StateEvent::Warning => Some(State::Warning),
+            StateEvent::Fault(_) | StateEvent::Terminated => Some(State::Fault),
+            StateEvent::Degraded { .. } | StateEvent::HighLatency { .. } => None,
+        }
+    }
 }
 
 impl defmt::Format for StateEvent {
@@ -85,6 +259,13 @@ impl defmt::Format for StateEvent {
         match self {
             StateEvent::Fault(kind) => defmt::write!(f, "Fault({})", kind),
             StateEvent::Ok => defmt::write!(f, "Ok"),
+            StateEvent::Warning => defmt::write!(f, "Warning"),
+            StateEvent::Degraded { lost } => defmt::write!(f, "Degraded({})", lost),
+            StateEvent::HighLatency { latency } => {
+                defmt::write!(f, "HighLatency({}us)", latency.as_micros());
+            }
+            StateEvent::Paused => defmt::write!(f, "Paused"),
+            StateEvent::Terminated => defmt::write!(f, "Terminated"),
         }
     }
 }
@@ -94,14 +275,18 @@ impl rtsc::data_policy::DataDeliveryPolicy for StateEvent {
     fn delivery_policy(&self) -> rtsc::data_policy::DeliveryPolicy {
         rtsc::data_policy::DeliveryPolicy::Latest
     }
+    // a full queue drops the oldest event of any kind, so the run loop never blocks on consumers
+    // which do not read the events
+    fn eq_kind(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
+/// The events which do not change the state are converted to [`State::Ok`], use
+/// [`StateEvent::state`] to skip them
 impl From<StateEvent> for State {
     fn from(e: StateEvent) -> Self {
-        match e {
-            StateEvent::Ok => State::Ok,
-            StateEvent::Fault(_) => State::Fault,
-        }
+        e.state().unwrap_or(State::Ok)
     }
 }
 
@@ -110,6 +295,7 @@ impl defmt::Format for State {
         match self {
             State::Fault => defmt::write!(f, "Fault"),
             State::Ok => defmt::write!(f, "Ok"),
+            State::Warning => defmt::write!(f, "Warning"),
         }
     }
 }
@@ -117,17 +303,32 @@ impl defmt::Format for State {
 /// Watchdog state
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum State {
     /// Fault state
     Fault = 0,
     /// OK state
     Ok = 1,
+    /// Warning state (heartbeats are late, considered as OK)
+    Warning = 2,
+}
+
+#[cfg(feature = "std")]
+impl rtsc::data_policy::DataDeliveryPolicy for State {
+    fn delivery_policy(&self) -> rtsc::data_policy::DeliveryPolicy {
+        rtsc::data_policy::DeliveryPolicy::Latest
+    }
+    // the current state supersedes any previous one
+    fn eq_kind(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 impl From<u8> for State {
     fn from(b: u8) -> Self {
         match b {
             0 => State::Fault,
+            2 => State::Warning,
             _ => State::Ok,
         }
     }
@@ -147,7 +348,7 @@ impl From<State> for bool {
     fn from(s: State) -> bool {
         match s {
             State::Fault => false,
-            State::Ok => true,
+            State::Ok | State::Warning => true,
         }
     }
 }
@@ -202,6 +403,7 @@ impl From<Edge> for bool {
 
 /// Heartbeat range
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Range {
     /// Upper bound (timeout)
     Timeout(Duration),
@@ -211,6 +413,7 @@ pub enum Range {
 
 /// Fault state kind
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FaultKind {
     /// Initial state (watchdog is always started in "Fault")
     Initial,
@@ -220,6 +423,12 @@ pub enum FaultKind {
     Window,
     /// Out-of-order edge (e.g. for TCP/IP packets)
     OutOfOrder,
+    /// Too many heartbeats lost (detected with sequence numbers)
+    Lost,
+    /// I/O error (e.g. the network is down), the I/O is retried
+    Io,
+    /// The heart has not answered the challenge in time (challenge-response mode)
+    Challenge,
 }
 
 impl defmt::Format for FaultKind {
@@ -229,6 +438,9 @@ impl defmt::Format for FaultKind {
             FaultKind::Timeout => defmt::write!(f, "Timeout"),
             FaultKind::Window => defmt::write!(f, "Window"),
             FaultKind::OutOfOrder => defmt::write!(f, "OutOfOrder"),
+            FaultKind::Lost => defmt::write!(f, "Lost"),
+            FaultKind::Io => defmt::write!(f, "Io"),
+            FaultKind::Challenge => defmt::write!(f, "Challenge"),
         }
     }
 }
@@ -243,13 +455,81 @@ impl Range {
     }
 }
 
+/// I/O error retry policy
+///
+/// Applied when the I/O fails with errors other than timeouts: the watchdog switches to
+/// [`FaultKind::Io`], waits, resets the I/O ([`io::WatchdogIo::reset`]) and retries. The delay is
+/// doubled after each consecutive failure up to the maximum delay, the counter is reset when the
+/// I/O works again.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryPolicy {
+    delay: Duration,
+    max_delay: Duration,
+    max_retries: Option<u32>,
+}
+
+impl RetryPolicy {
+    /// Create a new retry policy with a constant delay and unlimited retries
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            max_delay: delay,
+            max_retries: None,
+        }
+    }
+    /// Set the maximum delay (exponential backoff)
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+    /// Set the maximum number of consecutive retries, when exceeded, the I/O error is returned by
+    /// the watchdog run
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+    /// Get the delay
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+    /// Get the maximum delay
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+    /// Get the maximum number of consecutive retries (`None` = unlimited)
+    pub fn max_retries(&self) -> Option<u32> {
+        self.max_retries
+    }
+    /// Get the delay before the retry (starting from 1)
+    fn backoff(&self, retry: u32) -> Duration {
+        self.delay
+            .saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+}
+
+const DEFAULT_HISTORY: usize = 16;
+
+/// Maximum interval, range and warmup durations of the watchdog configuration (one year)
+pub const MAX_DURATION: Duration = Duration::from_secs(365 * 24 * 3600);
+
 /// Watchdog configuration
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WatchdogConfig {
     interval: Duration,
     range: Range,
     warmup: Duration,
     min_beats: u32,
+    histogram: Option<(Duration, usize)>,
+    history: usize,
+    warning: Option<Duration>,
+    fault_tolerance: u32,
+    warmup_faults: u8,
+    io_retry: RetryPolicy,
+    degraded: Option<u32>,
+    high_latency: Option<Duration>,
 }
 
 impl WatchdogConfig {
@@ -257,9 +537,17 @@ impl WatchdogConfig {
     pub fn new(interval: Duration) -> Self {
         Self {
             interval,
-            range: Range::Timeout(interval + interval / 10),
-            warmup: interval * 2,
+            range: Range::Timeout(interval.saturating_add(interval / 10)),
+            warmup: interval.saturating_mul(2),
             min_beats: 2,
+            histogram: None,
+            history: DEFAULT_HISTORY,
+            warning: None,
+            fault_tolerance: 1,
+            warmup_faults: u8::MAX,
+            io_retry: RetryPolicy::new(interval),
+            degraded: None,
+            high_latency: None,
         }
     }
     /// Set the range
@@ -267,16 +555,67 @@ impl WatchdogConfig {
         self.range = range;
         self
     }
-    /// Set the warmup time (no heartbeat checked after startup/fault)
+    /// Set the warmup time (no heartbeat checked after startup/fault, the I/O is still read and the
+    /// received beats are discarded)
     pub fn with_warmup(mut self, warmup: Duration) -> Self {
         self.warmup = warmup;
         self
     }
+    /// Set the fault kinds which are followed by the I/O clear and the warmup (the default is all
+    /// kinds), e.g. a GPIO source does not need the warmup after [`FaultKind::Window`]. The
+    /// watchdog is always warmed up after startup ([`FaultKind::Initial`])
+    pub fn with_warmup_faults(mut self, kinds: &[FaultKind]) -> Self {
+        self.warmup_faults = kinds.iter().fold(0, |mask, kind| mask | 1 << *kind as u8);
+        self
+    }
     /// Set the minimum number of valid beats before switching to OK state
     pub fn with_min_beats(mut self, min_beats: u32) -> Self {
         self.min_beats = min_beats;
         self
     }
+    /// Enable the inter-beat interval histogram with the given bucket width and number of buckets
+    /// (up to [`MAX_HISTOGRAM_BUCKETS`]), see [`WatchdogStats::histogram`]
+    pub fn with_histogram(mut self, bucket_width: Duration, buckets: usize) -> Self {
+        self.histogram = Some((bucket_width, buckets));
+        self
+    }
+    /// Set the number of state events kept in the history (up to [`MAX_HISTORY`], the default is
+    /// 16, 0 - disabled), see [`Watchdog::history`]
+    pub fn with_history(mut self, size: usize) -> Self {
+        self.history = size;
+        self
+    }
+    /// Enable the Warning state: a beat which arrives later than the interval plus the threshold
+    /// (but before the I/O timeout) switches the watchdog from OK to Warning, the next timely beat
+    /// switches it back to OK
+    pub fn with_warning(mut self, threshold: Duration) -> Self {
+        self.warning = Some(threshold);
+        self
+    }
+    /// Set the number of consecutive timeout/window violations which switch the watchdog to Fault
+    /// state (the default is 1), e.g. to tolerate a single lost UDP packet
+    pub fn with_fault_tolerance(mut self, n: u32) -> Self {
+        self.fault_tolerance = n;
+        self
+    }
+    /// Send [`StateEvent::Degraded`] when at least the number of consecutive beats is lost but the
+    /// losses are tolerated by the I/O (e.g. [`io::udp::UdpIo::with_max_lost`])
+    pub fn with_degraded(mut self, min_lost: u32) -> Self {
+        self.degraded = Some(min_lost);
+        self
+    }
+    /// Send [`StateEvent::HighLatency`] when the one-way latency of a beat is at least the
+    /// threshold (timestamped protocols only, e.g. [`io::udp::UdpHeart::with_timestamp`]), so
+    /// network delays can be distinguished from sender stalls
+    pub fn with_high_latency(mut self, threshold: Duration) -> Self {
+        self.high_latency = Some(threshold);
+        self
+    }
+    /// Set the I/O error retry policy (the default is to retry once per interval)
+    pub fn with_io_retry(mut self, policy: RetryPolicy) -> Self {
+        self.io_retry = policy;
+        self
+    }
     /// Get the interval
     pub fn interval(&self) -> Duration {
         self.interval
@@ -289,30 +628,104 @@ impl WatchdogConfig {
     pub fn warmup(&self) -> Duration {
         self.warmup
     }
+    /// Is the fault kind followed by the I/O clear and the warmup
+    pub fn warmup_after(&self, kind: FaultKind) -> bool {
+        kind == FaultKind::Initial || self.warmup_faults & 1 << kind as u8 != 0
+    }
     /// Get the minimum number of valid beats
     pub fn min_beats(&self) -> u32 {
         self.min_beats
     }
+    /// Get the warning threshold
+    pub fn warning(&self) -> Option<Duration> {
+        self.warning
+    }
+    /// Get the number of consecutive violations which switch the watchdog to Fault state
+    pub fn fault_tolerance(&self) -> u32 {
+        self.fault_tolerance
+    }
+    /// Get the minimum number of lost beats which are reported with [`StateEvent::Degraded`]
+    pub fn degraded(&self) -> Option<u32> {
+        self.degraded
+    }
+    /// Get the minimum one-way latency which is reported with [`StateEvent::HighLatency`]
+    pub fn high_latency(&self) -> Option<Duration> {
+        self.high_latency
+    }
+    /// Get the I/O error retry policy
+    pub fn io_retry(&self) -> &RetryPolicy {
+        &self.io_retry
+    }
     /// Get timeout for I/O
     pub fn io_timeout(&self) -> Duration {
         match self.range {
-            Range::Timeout(_) => self.interval + self.range.timeout(),
+            Range::Timeout(_) => self.interval.saturating_add(self.range.timeout()),
             // allow flexible timeouts for windows (returns max)
-            Range::Window(_) => self.interval + self.range.timeout() * 2,
+            Range::Window(_) => self
+                .interval
+                .saturating_add(self.range.timeout().saturating_mul(2)),
+        }
+    }
+    /// Validate the configuration
+    pub fn validate(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Err(Error::Config("interval must be greater than zero"));
+        }
+        if self.interval > MAX_DURATION
+            || self.range.timeout() > MAX_DURATION
+            || self.warmup > MAX_DURATION
+        {
+            return Err(Error::Config(
+                "interval, range and warmup must not exceed MAX_DURATION",
+            ));
+        }
+        if self.min_beats == 0 {
+            return Err(Error::Config("min_beats must be greater than zero"));
+        }
+        if let Range::Window(v) = self.range {
+            if v >= self.interval {
+                return Err(Error::Config("window must be less than the interval"));
+            }
+        }
+        if self.fault_tolerance == 0 {
+            return Err(Error::Config("fault_tolerance must be greater than zero"));
         }
+        if let Some((_, buckets)) = self.histogram {
+            if buckets == 0 || buckets > MAX_HISTOGRAM_BUCKETS {
+                return Err(Error::Config(
+                    "histogram buckets must be between 1 and MAX_HISTOGRAM_BUCKETS",
+                ));
+            }
+        }
+        if self.history > MAX_HISTORY {
+            return Err(Error::Config("history size must not exceed MAX_HISTORY"));
+        }
+        if let Some(threshold) = self.warning {
+            if threshold >= self.range.timeout() {
+                return Err(Error::Config(
+                    "warning threshold must be less than the range duration",
+                ));
+            }
+        }
+        if self.io_retry.max_delay < self.io_retry.delay {
+            return Err(Error::Config(
+                "retry max delay must not be less than the delay",
+            ));
+        }
+        Ok(())
     }
 }
 
 /// Watchdog
-pub struct Watchdog<I: WatchdogIo> {
+pub struct Watchdog<I: WatchdogIo, C: Clock = SystemClock> {
     #[cfg(feature = "std")]
-    inner: Arc<WatchDogInner<I>>,
+    inner: Arc<WatchDogInner<I, C>>,
     #[cfg(not(feature = "std"))]
-    inner: WatchDogInner<I>,
+    inner: WatchDogInner<I, C>,
 }
 
 #[cfg(feature = "std")]
-impl<I: WatchdogIo> Clone for Watchdog<I> {
+impl<I: WatchdogIo, C: Clock> Clone for Watchdog<I, C> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
@@ -320,200 +733,942 @@ impl<I: WatchdogIo> Clone for Watchdog<I> {
     }
 }
 
-struct WatchDogProcessor<'a> {
+// the processor times are provided by the watchdog clock
+struct WatchDogProcessor<'a, C: Clock> {
     packets: u32,
+    misses: u32,
     next: Edge,
-    last_packet: Instant,
-    config: &'a WatchdogConfig,
+    last_packet: Duration,
+    last_beat: Option<Duration>,
+    warmup_start: Option<Duration>,
+    // the next beat time expected by the window schedule
+    expected: Option<Duration>,
+    // consecutive I/O failures and the delay before the next retry
+    retries: u32,
+    backoff: Option<Duration>,
+    config: WatchdogConfig,
+    stats: &'a Shared<WatchdogStats>,
+    clock: &'a C,
 }
 
-impl<'a> WatchDogProcessor<'a> {
-    fn new(config: &'a WatchdogConfig) -> Self {
+impl<'a, C: Clock> WatchDogProcessor<'a, C> {
+    fn new(config: WatchdogConfig, stats: &'a Shared<WatchdogStats>, clock: &'a C) -> Self {
         Self {
             packets: 0,
+            misses: 0,
             next: Edge::Rising,
-            last_packet: Instant::now(),
+            last_packet: clock.now(),
+            last_beat: None,
+            warmup_start: None,
+            expected: None,
+            retries: 0,
+            backoff: None,
             config,
+            stats,
+            clock,
         }
     }
-    fn process(&mut self, res: Result<Edge>, current_state: State) -> Result<Option<StateEvent>> {
-        #[cfg(feature = "std")]
-        let elapsed_ms = u64::try_from(self.last_packet.elapsed().as_micros()).unwrap();
-        #[cfg(feature = "embassy")]
-        let elapsed_ms = self.last_packet.elapsed().as_micros();
-        self.last_packet = Instant::now();
+    fn elapsed(&self, t: Duration) -> Duration {
+        self.clock.now().saturating_sub(t)
+    }
+    /// Creates a processor which starts with the warmup, the expected edge is taken from the
+    /// beats received during the warmup
+    fn with_warmup(config: WatchdogConfig, stats: &'a Shared<WatchdogStats>, clock: &'a C) -> Self {
+        let mut p = Self::new(config, stats, clock);
+        p.start_warmup();
+        p
+    }
+    fn start_warmup(&mut self) {
+        self.warmup_start = Some(self.clock.now());
+        self.expected = None;
+        self.packets = 0;
+        self.misses = 0;
+    }
+    /// Count an I/O failure and schedule the retry, the error is returned back if the retries are
+    /// exhausted
+    fn io_failed(&mut self, e: Error) -> Result<Error> {
+        self.retries += 1;
+        let policy = &self.config.io_retry;
+        if policy.max_retries.is_some_and(|n| self.retries > n) {
+            return Err(e);
+        }
+        logging::io_retry(&e, self.retries);
+        self.backoff = Some(policy.backoff(self.retries));
+        Ok(e)
+    }
+    /// Check the I/O read result, see [`Self::io_failed`]
+    fn check_io(&mut self, res: Result<Edge>) -> Result<Result<Edge>> {
+        match res {
+            Err(e) if !matches!(e, Error::Timeout | Error::Lost | Error::Challenge) => {
+                Ok(Err(self.io_failed(e)?))
+            }
+            res => {
+                self.retries = 0;
+                Ok(res)
+            }
+        }
+    }
+    fn process(
+        &mut self,
+        res: Result<Edge>,
+        latency: Option<Duration>,
+        lost: u32,
+        current_state: State,
+    ) -> Option<StateEvent> {
+        if let Some(start) = self.warmup_start {
+            if self.elapsed(start) < self.config.warmup {
+                // beats are read and discarded during the warmup, I/O errors are ignored
+                if let Ok(edge) = res {
+                    self.next = !edge;
+                }
+                self.last_packet = self.clock.now();
+                return None;
+            }
+            self.warmup_start = None;
+        }
+        if res.is_ok() {
+            let interval = self.last_beat.map(|t| self.elapsed(t));
+            self.stats.with(|s| s.record_beat(interval, latency));
+            self.last_beat = Some(self.clock.now());
+        }
+        if lost > 0 {
+            self.stats.with(|s| s.record_lost(lost));
+        }
+        let event = self.evaluate(res, current_state);
+        if matches!(event, Some(StateEvent::Fault(_))) {
+            // the next beat is received after the warmup
+            self.last_beat = None;
+        }
+        if event.is_none()
+            && lost > 0
+            && current_state != State::Fault
+            && self.config.degraded.is_some_and(|n| lost >= n)
+        {
+            return Some(StateEvent::Degraded { lost });
+        }
+        if let Some(latency) = latency.filter(|l| {
+            event.is_none()
+                && current_state != State::Fault
+                && self.config.high_latency.is_some_and(|t| *l >= t)
+        }) {
+            return Some(StateEvent::HighLatency { latency });
+        }
+        event
+    }
+    fn evaluate(&mut self, res: Result<Edge>, current_state: State) -> Option<StateEvent> {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_packet);
+        self.last_packet = now;
         match res {
             Ok(edge) => {
+                let mut violation = false;
                 if let Range::Window(v) = self.config.range {
-                    if elapsed_ms
-                        < u64::try_from(self.config.interval.as_micros() - v.as_micros()).unwrap()
-                    {
-                        self.packets = 0;
-                        return Ok(Some(StateEvent::Fault(FaultKind::Window)));
+                    // beats are checked against the schedule, so the processing latency is not
+                    // accumulated. An early beat keeps the schedule, a beat out of the window (or
+                    // the first one) starts a new schedule
+                    let expected = self.expected;
+                    self.expected = Some(match expected {
+                        Some(e) if now + v < e => e,
+                        Some(e) if now <= e + v => e + self.config.interval,
+                        _ => now + self.config.interval,
+                    });
+                    if expected.is_some_and(|e| now + v < e) {
+                        if !self.tolerate(current_state) {
+                            self.packets = 0;
+                            return Some(StateEvent::Fault(FaultKind::Window));
+                        }
+                        // the tolerated beat is processed as usual
+                        violation = true;
                     }
                 }
+                if !violation {
+                    self.misses = 0;
+                }
                 if edge == self.next {
                     self.next = !self.next;
                     if current_state == State::Fault {
                         self.packets += 1;
                         if self.packets >= self.config.min_beats * 2 {
-                            return Ok(Some(StateEvent::Ok));
+                            return Some(StateEvent::Ok);
+                        }
+                    } else if let Some(threshold) = self.config.warning {
+                        let late = elapsed > self.config.interval + threshold;
+                        if late && current_state == State::Ok {
+                            return Some(StateEvent::Warning);
+                        }
+                        if !late && current_state == State::Warning {
+                            return Some(StateEvent::Ok);
                         }
                     }
-                    return Ok(None);
+                    return None;
                 }
                 if self.packets > 1 {
                     self.packets = 0;
-                    return Ok(Some(StateEvent::Fault(FaultKind::OutOfOrder)));
+                    return Some(StateEvent::Fault(FaultKind::OutOfOrder));
                 }
-                Ok(None)
+                None
             }
             Err(Error::Timeout) => {
+                self.expected = None;
+                if self.tolerate(current_state) {
+                    return None;
+                }
+                self.packets = 0;
+                Some(StateEvent::Fault(FaultKind::Timeout))
+            }
+            Err(Error::Lost) => {
+                self.expected = None;
                 self.packets = 0;
-                Ok(Some(StateEvent::Fault(FaultKind::Timeout)))
+                Some(StateEvent::Fault(FaultKind::Lost))
             }
-            Err(e) => Err(e),
+            Err(Error::Challenge) => {
+                self.expected = None;
+                self.packets = 0;
+                Some(StateEvent::Fault(FaultKind::Challenge))
+            }
+            Err(_) => {
+                self.expected = None;
+                self.packets = 0;
+                Some(StateEvent::Fault(FaultKind::Io))
+            }
+        }
+    }
+    /// Count a timeout/window violation, returns true if it is tolerated
+    fn tolerate(&mut self, current_state: State) -> bool {
+        if current_state == State::Fault {
+            return false;
+        }
+        self.misses += 1;
+        if self.misses < self.config.fault_tolerance {
+            return true;
         }
+        self.misses = 0;
+        false
     }
 }
 
-struct WatchDogInner<I: WatchdogIo> {
+struct WatchDogInner<I: WatchdogIo, C: Clock> {
     io: I,
-    state: AtomicBool,
-    config: WatchdogConfig,
+    clock: C,
+    state: AtomicU8,
+    paused: AtomicBool,
+    // the pause and the fault transitions are serialized, so no fault is stored after the pause
+    transition: Shared<()>,
+    config: Shared<WatchdogConfig>,
+    reconfigured: AtomicBool,
+    #[cfg(feature = "std")]
+    name: Option<String>,
     #[cfg(feature = "std")]
     state_tx: policy_channel::Sender<StateEvent, RawMutex, Condvar>,
     #[cfg(feature = "std")]
     state_rx: policy_channel::Receiver<StateEvent, RawMutex, Condvar>,
+    #[cfg(feature = "std")]
+    subscribers: rtsc::pi::Mutex<Vec<policy_channel::Sender<StateEvent, RawMutex, Condvar>>>,
+    #[cfg(feature = "std")]
+    callbacks: rtsc::pi::Mutex<Vec<StateCallback>>,
+    #[cfg(feature = "std")]
+    error_callbacks: rtsc::pi::Mutex<Vec<ErrorCallback>>,
+    #[cfg(feature = "std")]
+    escalation: rtsc::pi::Mutex<Option<Escalator>>,
+    #[cfg(feature = "tokio")]
+    state_watch: tokio::sync::watch::Sender<State>,
+    stats: Shared<WatchdogStats>,
+    history: Shared<History>,
+    last_fault: Shared<Option<(FaultKind, Duration)>>,
+    availability: Shared<AvailabilityTracker>,
 }
 
 impl<I: WatchdogIo> Watchdog<I> {
+    /// Create a new watchdog, the configuration is validated
+    pub fn try_new(config: WatchdogConfig, io: I) -> Result<Self> {
+        config.validate()?;
+        Ok(Self::new(config, io))
+    }
     /// Create a new watchdog
-    #[allow(clippy::useless_conversion)]
     pub fn new(config: WatchdogConfig, io: I) -> Self {
+        Self::with_clock(config, io, SystemClock)
+    }
+}
+
+impl<I: WatchdogIo, C: Clock> Watchdog<I, C> {
+    /// Create a new watchdog with a custom clock, which is used to evaluate heartbeats
+    #[allow(clippy::useless_conversion)]
+    pub fn with_clock(config: WatchdogConfig, io: I, clock: C) -> Self {
+        Self {
+            inner: Self::new_inner(config, io, clock).into(),
+        }
+    }
+    fn new_inner(config: WatchdogConfig, io: I, clock: C) -> WatchDogInner<I, C> {
         #[cfg(feature = "std")]
         let (state_tx, state_rx) = rtsc::policy_channel::bounded(1);
-        Self {
-            inner: WatchDogInner {
-                io,
-                state: AtomicBool::new(State::Fault.into()),
-                config,
-                #[cfg(feature = "std")]
-                state_tx,
-                #[cfg(feature = "std")]
-                state_rx,
-            }
-            .into(),
+        let stats = Shared::new(WatchdogStats::new(
+            config
+                .histogram
+                .map(|(width, buckets)| Histogram::new(width, buckets)),
+        ));
+        let history = Shared::new(History::new(config.history));
+        let availability = Shared::new(AvailabilityTracker::new(clock.now()));
+        WatchDogInner {
+            io,
+            clock,
+            state: AtomicU8::new(State::Fault as u8),
+            paused: AtomicBool::new(false),
+            transition: Shared::new(()),
+            config: Shared::new(config),
+            reconfigured: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            name: None,
+            #[cfg(feature = "std")]
+            state_tx,
+            #[cfg(feature = "std")]
+            state_rx,
+            #[cfg(feature = "std")]
+            subscribers: <_>::default(),
+            #[cfg(feature = "std")]
+            callbacks: <_>::default(),
+            #[cfg(feature = "std")]
+            error_callbacks: <_>::default(),
+            #[cfg(feature = "std")]
+            escalation: <_>::default(),
+            #[cfg(feature = "tokio")]
+            state_watch: tokio::sync::watch::Sender::new(State::Fault),
+            stats,
+            history,
+            last_fault: Shared::new(None),
+            availability,
         }
     }
     /// Get the current state
     pub fn state(&self) -> State {
-        self.inner.state.load(Ordering::Relaxed).into()
+        State::from(self.inner.state.load(Ordering::Relaxed))
     }
-    /// Get the state receiver channel
+    /// Get the watchdog name (set with [`WatchdogBuilder::name`])
+    #[cfg(feature = "std")]
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name.as_deref()
+    }
+    /// Get the current configuration
+    pub fn config(&self) -> WatchdogConfig {
+        self.inner.config.with(|c| c.clone())
+    }
+    /// Replace the configuration of the running watchdog. The new configuration takes effect at
+    /// the next beat, the current state is kept. The histogram and history settings can not be
+    /// changed at runtime and are ignored. Note that the I/O timeout is set when the I/O is created,
+    /// so the new interval and range must fit it
+    pub fn reconfigure(&self, mut config: WatchdogConfig) -> Result<()> {
+        config.validate()?;
+        self.inner.config.with(|c| {
+            config.histogram = c.histogram;
+            config.history = c.history;
+            *c = config;
+        });
+        self.inner.reconfigured.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+    /// Get the statistics
+    pub fn stats(&self) -> WatchdogStats {
+        self.inner.stats.with(|s| s.clone())
+    }
+    /// Get the state event history
+    pub fn history(&self) -> History {
+        self.inner.history.with(|h| h.clone())
+    }
+    /// Get the kind and the time of the last fault
+    #[cfg(feature = "std")]
+    pub fn last_fault(&self) -> Option<(FaultKind, std::time::Instant)> {
+        self.last_fault_clock()
+            .map(|(kind, time)| (kind, clock::to_instant(&self.inner.clock, time)))
+    }
+    /// Get the kind and the time of the last fault, the time is the watchdog clock one (see
+    /// [`Clock::now`]), e.g. for the virtual time of [`MockClock`]
+    pub fn last_fault_clock(&self) -> Option<(FaultKind, Duration)> {
+        self.inner.last_fault.with(|f| *f)
+    }
+    /// Get the time spent in OK and Fault states
+    pub fn availability(&self) -> Availability {
+        let now = self.inner.clock.now();
+        self.inner.availability.with(|a| a.snapshot(now))
+    }
+    /// Get the status snapshot (the state, the last fault, the counters and the configuration
+    /// summary)
+    pub fn status(&self) -> WatchdogStatus {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut status = WatchdogStatus::new(
+            self.state(),
+            self.is_paused(),
+            &self.config(),
+            &self.stats(),
+            self.last_fault_clock(),
+            &self.availability(),
+            self.inner.clock.now(),
+        );
+        #[cfg(feature = "std")]
+        {
+            status.name = self.name().map(ToOwned::to_owned);
+        }
+        status
+    }
+    /// Get the state receiver channel. The channel keeps the latest event only, which replaces
+    /// an unread one
     #[cfg(feature = "std")]
     pub fn state_rx(&self) -> policy_channel::Receiver<StateEvent, RawMutex, Condvar> {
         self.inner.state_rx.clone()
     }
+    /// Subscribe to state events. Unlike the shared state receiver channel, every subscriber
+    /// receives all transitions
+    #[cfg(feature = "std")]
+    pub fn subscribe(&self) -> policy_channel::Receiver<StateEvent, RawMutex, Condvar> {
+        let (tx, rx) = policy_channel::bounded(SUBSCRIBER_QUEUE_SIZE);
+        self.inner.subscribers.lock().push(tx);
+        rx
+    }
+    /// Register a state change callback. Callbacks are called from the run loop, so they must not
+    /// block and must not register other callbacks
+    #[cfg(feature = "std")]
+    pub fn on_state_change(&self, f: impl Fn(&StateEvent) + Send + Sync + 'static) {
+        self.inner.callbacks.lock().push(Box::new(f));
+    }
+    /// Register a terminal error callback, called when the watchdog run fails (e.g. the I/O
+    /// retries are exhausted), before [`StateEvent::Terminated`] is sent
+    #[cfg(feature = "std")]
+    pub fn on_error(&self, f: impl Fn(&Error) + Send + Sync + 'static) {
+        self.inner.error_callbacks.lock().push(Box::new(f));
+    }
+    /// Set the fault escalation policy, which replaces the previous one
+    #[cfg(feature = "std")]
+    pub fn set_escalation(&self, policy: EscalationPolicy) {
+        *self.inner.escalation.lock() = Some(Escalator::new(policy));
+    }
+    /// Get the state watch receiver, which always contains the latest state
+    #[cfg(feature = "tokio")]
+    pub fn watch_state(&self) -> tokio::sync::watch::Receiver<State> {
+        self.inner.state_watch.subscribe()
+    }
     /// Run the watchdog
     pub fn run(&self) -> Result<()> {
-        self.set_fault(FaultKind::Initial)?;
-        let mut p = WatchDogProcessor::new(&self.inner.config);
-        loop {
-            match p.process(self.inner.io.get(p.next), self.state()) {
-                Ok(Some(event)) => match event {
+        self.run_until(&core::sync::atomic::AtomicBool::new(false))
+    }
+    /// Run the watchdog until the stop flag is set. The flag is checked after each I/O read, so
+    /// the watchdog stops within the I/O timeout. If the run fails, the error is passed to the
+    /// error callbacks and [`StateEvent::Terminated`] is sent
+    pub fn run_until(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let res = self.run_loop(stop);
+        if let Err(ref e) = res {
+            self.terminate(e);
+        }
+        res
+    }
+    fn run_loop(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let mut p = WatchDogProcessor::new(self.config(), &self.inner.stats, &self.inner.clock);
+        self.set_fault(FaultKind::Initial, &mut p)?;
+        let mut paused = false;
+        while !stop.load(Ordering::Relaxed) {
+            if let Some(delay) = p.backoff.take() {
+                // failed I/Os usually return immediately, the retries are delayed
+                #[cfg(feature = "std")]
+                std::thread::sleep(delay);
+                #[cfg(not(feature = "std"))]
+                embassy_time::block_for(embassy_duration(delay));
+                if let Err(e) = self.inner.io.reset() {
+                    p.io_failed(e)?;
+                    continue;
+                }
+            }
+            let res = p.check_io(self.inner.io.get(p.next))?;
+            let rejected = self.inner.io.rejected();
+            if rejected > 0 {
+                self.inner.stats.with(|s| s.record_rejected(rejected));
+            }
+            // the last read may be interrupted by the shutdown, do not report it
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            // pause and resume are applied here, so they never race the state transitions. The
+            // I/O is still read while paused, so the heart is not blocked
+            if self.is_paused() {
+                if !paused {
+                    paused = true;
+                    self.store_state(State::Ok);
+                    self.send_event(StateEvent::Paused)?;
+                }
+                continue;
+            }
+            if paused {
+                paused = false;
+                // the evaluation is restarted from scratch, the phase of the heart is unknown
+                p = WatchDogProcessor::with_warmup(
+                    self.config(),
+                    &self.inner.stats,
+                    &self.inner.clock,
+                );
+                self.store_state(State::Ok);
+                self.send_event(StateEvent::Ok)?;
+                // the failed I/O is retried by the run loop
+                if let Err(e) = self.inner.io.clear() {
+                    p.io_failed(e)?;
+                }
+                continue;
+            }
+            if self.inner.reconfigured.swap(false, Ordering::Relaxed) {
+                p.config = self.config();
+            }
+            let (latency, lost) = (self.inner.io.latency(), self.inner.io.lost());
+            let beat = res.is_ok();
+            if let Some(event) = p.process(res, latency, lost, self.state()) {
+                match event {
                     StateEvent::Ok => self.set_ok()?,
-                    StateEvent::Fault(kind) => self.set_fault(kind)?,
-                },
-                Ok(None) => (),
-                Err(e) => return Err(e),
+                    StateEvent::Warning => self.set_warning()?,
+                    StateEvent::Degraded { lost } => self.set_degraded(lost)?,
+                    StateEvent::HighLatency { latency } => self.set_high_latency(latency)?,
+                    StateEvent::Fault(kind) => self.set_fault(kind, &mut p)?,
+                    // never produced by the processor
+                    StateEvent::Paused | StateEvent::Terminated => (),
+                }
+            }
+            if beat {
+                // acknowledgements are best-effort, the heart detects missing ones
+                let _ = self.inner.io.ack(self.state());
+            }
+            #[cfg(feature = "std")]
+            self.escalate();
+        }
+        Ok(())
+    }
+    /// Pause the watchdog: faults are not evaluated (the I/O is still read) until the watchdog is
+    /// resumed, e.g. during a planned restart of the supervised application. A paused watchdog is
+    /// in OK state
+    ///
+    /// No faults are reported after the method has returned (a fault detected by the current I/O
+    /// read is discarded). [`StateEvent::Paused`] is sent by the run loop after the current I/O
+    /// read (within the I/O timeout), event delivery errors are returned by the run
+    pub fn pause(&self) {
+        self.inner
+            .transition
+            .with(|()| self.inner.paused.store(true, Ordering::Relaxed));
+    }
+    /// Resume the paused watchdog. The evaluation is restarted from scratch in OK state, which is
+    /// reported again, the beats are checked after the warmup
+    ///
+    /// The resume is applied by the run loop after the current I/O read (within the I/O timeout),
+    /// I/O and event delivery errors are handled by the run
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::Relaxed);
+    }
+    /// Is the watchdog paused, `true` as soon as the pause is requested
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Relaxed)
+    }
+    #[cfg_attr(not(feature = "std"), allow(clippy::unnecessary_wraps))]
+    fn send_event(&self, event: StateEvent) -> Result<()> {
+        let latency = self.inner.stats.with(|s| s.last_latency());
+        let now = self.inner.clock.now();
+        self.inner
+            .history
+            .with(|h| h.push(event.clone(), latency, now));
+        #[cfg(feature = "std")]
+        logging::state_event(self.name(), &event);
+        #[cfg(not(feature = "std"))]
+        logging::state_event(None, &event);
+        #[cfg(feature = "std")]
+        {
+            for f in self.inner.callbacks.lock().iter() {
+                f(&event);
             }
+            // the events never block as a full queue drops the oldest one, the subscribers which
+            // have dropped their receivers are removed
+            self.inner.subscribers.lock().retain(|tx| {
+                !matches!(tx.try_send(event.clone()), Err(rtsc::Error::ChannelClosed))
+            });
+            self.inner.state_tx.send(event).map_err(Error::failed)?;
+        }
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    fn escalate(&self) {
+        let (state, last_fault) = (self.state(), self.last_fault_clock());
+        if let Some(ref mut escalator) = *self.inner.escalation.lock() {
+            escalator.update(state, last_fault, self.inner.clock.now());
+        }
+    }
+    fn store_state(&self, state: State) {
+        self.inner.state.store(state as u8, Ordering::Relaxed);
+        let now = self.inner.clock.now();
+        self.inner.availability.with(|a| a.transition(state, now));
+        #[cfg(feature = "tokio")]
+        self.inner.state_watch.send_replace(state);
+    }
+    fn terminate(&self, e: &Error) {
+        #[cfg(feature = "std")]
+        logging::run_failed(self.name(), e);
+        #[cfg(not(feature = "std"))]
+        logging::run_failed(None, e);
+        #[cfg(feature = "std")]
+        for f in self.inner.error_callbacks.lock().iter() {
+            f(e);
         }
+        self.store_state(State::Fault);
+        // the run has already failed, the event delivery errors are ignored
+        let _ = self.send_event(StateEvent::Terminated);
     }
-    #[allow(clippy::unnecessary_wraps)]
     fn set_ok(&self) -> Result<()> {
-        if self.state() == State::Ok {
+        if self.state() == State::Ok || self.is_paused() {
             return Ok(());
         }
-        self.inner.state.store(true, Ordering::Relaxed);
-        #[cfg(feature = "std")]
-        self.inner
-            .state_tx
-            .send(StateEvent::Ok)
-            .map_err(Error::failed)?;
-        Ok(())
+        self.store_state(State::Ok);
+        self.send_event(StateEvent::Ok)
     }
-    fn set_fault(&self, kind: FaultKind) -> Result<()> {
-        if self.state() == State::Fault && kind != FaultKind::Initial {
+    fn set_warning(&self) -> Result<()> {
+        if self.state() != State::Ok || self.is_paused() {
             return Ok(());
         }
-        self.inner.state.store(false, Ordering::Relaxed);
-        #[cfg(feature = "std")]
+        self.store_state(State::Warning);
+        self.send_event(StateEvent::Warning)
+    }
+    fn set_degraded(&self, lost: u32) -> Result<()> {
+        if self.state() == State::Fault || self.is_paused() {
+            return Ok(());
+        }
+        self.send_event(StateEvent::Degraded { lost })
+    }
+    fn set_high_latency(&self, latency: Duration) -> Result<()> {
+        if self.state() == State::Fault || self.is_paused() {
+            return Ok(());
+        }
+        self.send_event(StateEvent::HighLatency { latency })
+    }
+    fn set_fault(&self, kind: FaultKind, p: &mut WatchDogProcessor<'_, C>) -> Result<()> {
+        // the pause flag is checked under the transition lock, so the fault is either stored
+        // before the pause or discarded
+        let faulted = self.inner.transition.with(|()| {
+            if (self.state() == State::Fault || self.is_paused()) && kind != FaultKind::Initial {
+                return false;
+            }
+            self.store_state(State::Fault);
+            true
+        });
+        if !faulted {
+            return Ok(());
+        }
+        self.inner.stats.with(|s| s.record_fault(kind));
         self.inner
-            .state_tx
-            .send(StateEvent::Fault(kind))
-            .map_err(Error::failed)?;
-        self.warmup()?;
+            .last_fault
+            .with(|f| *f = Some((kind, self.inner.clock.now())));
+        self.send_event(StateEvent::Fault(kind))?;
+        if self.inner.config.with(|c| c.warmup_after(kind)) {
+            p.start_warmup();
+            // the failed I/O is retried by the run loop
+            if let Err(e) = self.inner.io.clear() {
+                p.io_failed(e)?;
+            }
+        }
         Ok(())
     }
-    fn warmup(&self) -> Result<()> {
-        #[cfg(feature = "std")]
-        std::thread::sleep(self.inner.config.warmup);
-        self.inner.io.clear()?;
-        Ok(())
+}
+
+#[cfg(feature = "std")]
+impl<I: WatchdogIo + Send + Sync + 'static, C: Clock + Send + Sync + 'static> Watchdog<I, C> {
+    /// Run the watchdog in a dedicated thread
+    pub fn spawn(&self) -> Result<WatchdogHandle<I, C>> {
+        let stop = Arc::new(core::sync::atomic::AtomicBool::new(false));
+        let watchdog = self.clone();
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name(self.name().unwrap_or("watchdog").to_owned())
+            .spawn(move || watchdog.run_until(&thread_stop))?;
+        Ok(WatchdogHandle {
+            watchdog: self.clone(),
+            stop,
+            thread,
+        })
+    }
+}
+
+/// Handle of a watchdog running in a dedicated thread (see [`Watchdog::spawn`])
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub struct WatchdogHandle<I: WatchdogIo, C: Clock = SystemClock> {
+    watchdog: Watchdog<I, C>,
+    stop: Arc<core::sync::atomic::AtomicBool>,
+    thread: std::thread::JoinHandle<Result<()>>,
+}
+
+#[cfg(feature = "std")]
+impl<I: WatchdogIo, C: Clock> WatchdogHandle<I, C> {
+    /// Get the current state
+    pub fn state(&self) -> State {
+        self.watchdog.state()
+    }
+    /// Get the state receiver channel
+    pub fn state_rx(&self) -> policy_channel::Receiver<StateEvent, RawMutex, Condvar> {
+        self.watchdog.state_rx()
+    }
+    /// Get the watchdog name
+    pub fn name(&self) -> Option<&str> {
+        self.watchdog.name()
+    }
+    /// Get the current configuration
+    pub fn config(&self) -> WatchdogConfig {
+        self.watchdog.config()
+    }
+    /// Replace the configuration of the running watchdog, see [`Watchdog::reconfigure`]
+    pub fn reconfigure(&self, config: WatchdogConfig) -> Result<()> {
+        self.watchdog.reconfigure(config)
+    }
+    /// Get the statistics
+    pub fn stats(&self) -> WatchdogStats {
+        self.watchdog.stats()
+    }
+    /// Get the state event history
+    pub fn history(&self) -> History {
+        self.watchdog.history()
+    }
+    /// Get the kind and the time of the last fault
+    pub fn last_fault(&self) -> Option<(FaultKind, std::time::Instant)> {
+        self.watchdog.last_fault()
+    }
+    /// Get the kind and the time of the last fault, the time is the watchdog clock one (see
+    /// [`Watchdog::last_fault_clock`])
+    pub fn last_fault_clock(&self) -> Option<(FaultKind, Duration)> {
+        self.watchdog.last_fault_clock()
+    }
+    /// Get the time spent in OK and Fault states
+    pub fn availability(&self) -> Availability {
+        self.watchdog.availability()
+    }
+    /// Get the status snapshot, see [`Watchdog::status`]
+    pub fn status(&self) -> WatchdogStatus {
+        self.watchdog.status()
+    }
+    /// Subscribe to state events, see [`Watchdog::subscribe`]
+    pub fn subscribe(&self) -> policy_channel::Receiver<StateEvent, RawMutex, Condvar> {
+        self.watchdog.subscribe()
+    }
+    /// Get the state watch receiver, see [`Watchdog::watch_state`]
+    #[cfg(feature = "tokio")]
+    pub fn watch_state(&self) -> tokio::sync::watch::Receiver<State> {
+        self.watchdog.watch_state()
+    }
+    /// Stop the watchdog, the thread is finished within the I/O timeout
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+    /// Wait for the watchdog thread to finish, returns the result of [`Watchdog::run_until`]
+    pub fn join(self) -> Result<()> {
+        self.thread
+            .join()
+            .map_err(|_| Error::failed("watchdog thread panicked"))?
     }
 }
 
 /// Watchdog
-pub struct WatchdogAsync<I: WatchdogIoAsync> {
+pub struct WatchdogAsync<I: WatchdogIoAsync, C: Clock = SystemClock> {
     #[cfg(feature = "std")]
-    inner: Arc<WatchDogInnerAsync<I>>,
+    inner: Arc<WatchDogInnerAsync<I, C>>,
     #[cfg(not(feature = "std"))]
-    inner: WatchDogInnerAsync<I>,
+    inner: WatchDogInnerAsync<I, C>,
 }
 
-struct WatchDogInnerAsync<I: WatchdogIoAsync> {
+struct WatchDogInnerAsync<I: WatchdogIoAsync, C: Clock> {
     io: I,
-    state: AtomicBool,
-    config: WatchdogConfig,
+    clock: C,
+    state: AtomicU8,
+    paused: AtomicBool,
+    // the pause and the fault transitions are serialized, so no fault is stored after the pause
+    transition: Shared<()>,
+    config: Shared<WatchdogConfig>,
+    reconfigured: AtomicBool,
+    #[cfg(feature = "std")]
+    name: Option<String>,
     #[cfg(feature = "std")]
     state_tx: policy_channel_async::Sender<StateEvent>,
     #[cfg(feature = "std")]
     state_rx: policy_channel_async::Receiver<StateEvent>,
+    #[cfg(feature = "std")]
+    subscribers: rtsc::pi::Mutex<Vec<policy_channel_async::Sender<StateEvent>>>,
+    #[cfg(feature = "std")]
+    callbacks: rtsc::pi::Mutex<Vec<StateCallback>>,
+    #[cfg(feature = "std")]
+    error_callbacks: rtsc::pi::Mutex<Vec<ErrorCallback>>,
+    #[cfg(feature = "std")]
+    escalation: rtsc::pi::Mutex<Option<Escalator>>,
+    #[cfg(feature = "tokio")]
+    state_watch: tokio::sync::watch::Sender<State>,
+    stats: Shared<WatchdogStats>,
+    history: Shared<History>,
+    last_fault: Shared<Option<(FaultKind, Duration)>>,
+    availability: Shared<AvailabilityTracker>,
     #[cfg(feature = "embassy")]
     embassy_state_tx: Option<embassy_sync::channel::Sender<'static, NoopMutex, StateEvent, 32>>,
 }
 
 impl<I: WatchdogIoAsync> WatchdogAsync<I> {
+    /// Create a new watchdog, the configuration is validated
+    pub fn try_new(config: WatchdogConfig, io: I) -> Result<Self> {
+        config.validate()?;
+        Ok(Self::new(config, io))
+    }
     /// Create a new watchdog
-    #[allow(clippy::useless_conversion)]
     pub fn new(config: WatchdogConfig, io: I) -> Self {
+        Self::with_clock(config, io, SystemClock)
+    }
+}
+
+impl<I: WatchdogIoAsync, C: Clock> WatchdogAsync<I, C> {
+    /// Create a new watchdog with a custom clock, which is used to evaluate heartbeats
+    #[allow(clippy::useless_conversion)]
+    pub fn with_clock(config: WatchdogConfig, io: I, clock: C) -> Self {
+        Self {
+            inner: Self::new_inner(config, io, clock).into(),
+        }
+    }
+    fn new_inner(config: WatchdogConfig, io: I, clock: C) -> WatchDogInnerAsync<I, C> {
         #[cfg(feature = "std")]
         let (state_tx, state_rx) = rtsc::policy_channel_async::bounded(1);
-        Self {
-            inner: WatchDogInnerAsync {
-                io,
-                state: AtomicBool::new(State::Fault.into()),
-                config,
-                #[cfg(feature = "std")]
-                state_tx,
-                #[cfg(feature = "std")]
-                state_rx,
-                #[cfg(feature = "embassy")]
-                embassy_state_tx: None,
-            }
-            .into(),
+        let stats = Shared::new(WatchdogStats::new(
+            config
+                .histogram
+                .map(|(width, buckets)| Histogram::new(width, buckets)),
+        ));
+        let history = Shared::new(History::new(config.history));
+        let availability = Shared::new(AvailabilityTracker::new(clock.now()));
+        WatchDogInnerAsync {
+            io,
+            clock,
+            state: AtomicU8::new(State::Fault as u8),
+            paused: AtomicBool::new(false),
+            transition: Shared::new(()),
+            config: Shared::new(config),
+            reconfigured: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            name: None,
+            #[cfg(feature = "std")]
+            state_tx,
+            #[cfg(feature = "std")]
+            state_rx,
+            #[cfg(feature = "std")]
+            subscribers: <_>::default(),
+            #[cfg(feature = "std")]
+            callbacks: <_>::default(),
+            #[cfg(feature = "std")]
+            error_callbacks: <_>::default(),
+            #[cfg(feature = "std")]
+            escalation: <_>::default(),
+            #[cfg(feature = "tokio")]
+            state_watch: tokio::sync::watch::Sender::new(State::Fault),
+            stats,
+            history,
+            last_fault: Shared::new(None),
+            availability,
+            #[cfg(feature = "embassy")]
+            embassy_state_tx: None,
         }
     }
     /// Get the current state
     pub fn state(&self) -> State {
-        self.inner.state.load(Ordering::Relaxed).into()
+        State::from(self.inner.state.load(Ordering::Relaxed))
     }
+    /// Get the watchdog name (set with [`WatchdogBuilder::name`])
     #[cfg(feature = "std")]
-    /// Get the state receiver channel
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name.as_deref()
+    }
+    /// Get the current configuration
+    pub fn config(&self) -> WatchdogConfig {
+        self.inner.config.with(|c| c.clone())
+    }
+    /// Replace the configuration of the running watchdog. The new configuration takes effect at
+    /// the next beat, the current state is kept. The histogram and history settings can not be
+    /// changed at runtime and are ignored. Note that the I/O timeout is set when the I/O is created,
+    /// so the new interval and range must fit it
+    pub fn reconfigure(&self, mut config: WatchdogConfig) -> Result<()> {
+        config.validate()?;
+        self.inner.config.with(|c| {
+            config.histogram = c.histogram;
+            config.history = c.history;
+            *c = config;
+        });
+        self.inner.reconfigured.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+    /// Get the statistics
+    pub fn stats(&self) -> WatchdogStats {
+        self.inner.stats.with(|s| s.clone())
+    }
+    /// Get the state event history
+    pub fn history(&self) -> History {
+        self.inner.history.with(|h| h.clone())
+    }
+    /// Get the kind and the time of the last fault
+    #[cfg(feature = "std")]
+    pub fn last_fault(&self) -> Option<(FaultKind, std::time::Instant)> {
+        self.last_fault_clock()
+            .map(|(kind, time)| (kind, clock::to_instant(&self.inner.clock, time)))
+    }
+    /// Get the kind and the time of the last fault, the time is the watchdog clock one (see
+    /// [`Clock::now`]), e.g. for the virtual time of [`MockClock`]
+    pub fn last_fault_clock(&self) -> Option<(FaultKind, Duration)> {
+        self.inner.last_fault.with(|f| *f)
+    }
+    /// Get the time spent in OK and Fault states
+    pub fn availability(&self) -> Availability {
+        let now = self.inner.clock.now();
+        self.inner.availability.with(|a| a.snapshot(now))
+    }
+    /// Get the status snapshot (the state, the last fault, the counters and the configuration
+    /// summary)
+    pub fn status(&self) -> WatchdogStatus {
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut status = WatchdogStatus::new(
+            self.state(),
+            self.is_paused(),
+            &self.config(),
+            &self.stats(),
+            self.last_fault_clock(),
+            &self.availability(),
+            self.inner.clock.now(),
+        );
+        #[cfg(feature = "std")]
+        {
+            status.name = self.name().map(ToOwned::to_owned);
+        }
+        status
+    }
+    #[cfg(feature = "std")]
+    /// Get the state receiver channel. The channel keeps the latest event only, which replaces
+    /// an unread one
     pub fn state_rx(&self) -> policy_channel_async::Receiver<StateEvent> {
         self.inner.state_rx.clone()
     }
+    /// Subscribe to state events. Unlike the shared state receiver channel, every subscriber
+    /// receives all transitions
+    #[cfg(feature = "std")]
+    pub fn subscribe(&self) -> policy_channel_async::Receiver<StateEvent> {
+        let (tx, rx) = policy_channel_async::bounded(SUBSCRIBER_QUEUE_SIZE);
+        self.inner.subscribers.lock().push(tx);
+        rx
+    }
+    /// Register a state change callback. Callbacks are called from the run loop, so they must not
+    /// block and must not register other callbacks
+    #[cfg(feature = "std")]
+    pub fn on_state_change(&self, f: impl Fn(&StateEvent) + Send + Sync + 'static) {
+        self.inner.callbacks.lock().push(Box::new(f));
+    }
+    /// Register a terminal error callback, called when the watchdog run fails (e.g. the I/O
+    /// retries are exhausted), before [`StateEvent::Terminated`] is sent
+    #[cfg(feature = "std")]
+    pub fn on_error(&self, f: impl Fn(&Error) + Send + Sync + 'static) {
+        self.inner.error_callbacks.lock().push(Box::new(f));
+    }
+    /// Set the fault escalation policy, which replaces the previous one
+    #[cfg(feature = "std")]
+    pub fn set_escalation(&self, policy: EscalationPolicy) {
+        *self.inner.escalation.lock() = Some(Escalator::new(policy));
+    }
+    /// Get the state watch receiver, which always contains the latest state
+    #[cfg(feature = "tokio")]
+    pub fn watch_state(&self) -> tokio::sync::watch::Receiver<State> {
+        self.inner.state_watch.subscribe()
+    }
     #[cfg(all(feature = "embassy", not(feature = "std")))]
     /// Set the state sender channel
     pub fn set_state_tx(
@@ -524,75 +1679,570 @@ impl<I: WatchdogIoAsync> WatchdogAsync<I> {
     }
     /// Run the watchdog
     pub async fn run(&self) -> Result<()> {
-        self.set_fault(FaultKind::Initial).await?;
-        let mut p = WatchDogProcessor::new(&self.inner.config);
-        loop {
-            match p.process(self.inner.io.get(p.next).await, self.state()) {
-                Ok(Some(event)) => match event {
+        self.run_until(&core::sync::atomic::AtomicBool::new(false))
+            .await
+    }
+    /// Run the watchdog until the stop flag is set. The flag is checked after each I/O read, so
+    /// the watchdog stops within the I/O timeout. If the run fails, the error is passed to the
+    /// error callbacks and [`StateEvent::Terminated`] is sent
+    pub async fn run_until(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let res = self.run_loop(stop).await;
+        if let Err(ref e) = res {
+            self.terminate(e).await;
+        }
+        res
+    }
+    async fn run_loop(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let mut p = WatchDogProcessor::new(self.config(), &self.inner.stats, &self.inner.clock);
+        self.set_fault(FaultKind::Initial, &mut p).await?;
+        let mut paused = false;
+        while !stop.load(Ordering::Relaxed) {
+            if let Some(delay) = p.backoff.take() {
+                // failed I/Os usually return immediately, the retries are delayed
+                #[cfg(feature = "std")]
+                async_io::Timer::after(delay).await;
+                #[cfg(not(feature = "std"))]
+                embassy_time::Timer::after(embassy_duration(delay)).await;
+                if let Err(e) = self.inner.io.reset().await {
+                    p.io_failed(e)?;
+                    continue;
+                }
+            }
+            let res = p.check_io(self.inner.io.get(p.next).await)?;
+            let rejected = self.inner.io.rejected();
+            if rejected > 0 {
+                self.inner.stats.with(|s| s.record_rejected(rejected));
+            }
+            // the last read may be interrupted by the shutdown, do not report it
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            // pause and resume are applied here, so they never race the state transitions. The
+            // I/O is still read while paused, so the heart is not blocked
+            if self.is_paused() {
+                if !paused {
+                    paused = true;
+                    self.store_state(State::Ok);
+                    self.send_event(StateEvent::Paused).await?;
+                }
+                continue;
+            }
+            if paused {
+                paused = false;
+                // the evaluation is restarted from scratch, the phase of the heart is unknown
+                p = WatchDogProcessor::with_warmup(
+                    self.config(),
+                    &self.inner.stats,
+                    &self.inner.clock,
+                );
+                self.store_state(State::Ok);
+                self.send_event(StateEvent::Ok).await?;
+                // the failed I/O is retried by the run loop
+                if let Err(e) = self.inner.io.clear().await {
+                    p.io_failed(e)?;
+                }
+                continue;
+            }
+            if self.inner.reconfigured.swap(false, Ordering::Relaxed) {
+                p.config = self.config();
+            }
+            let (latency, lost) = (self.inner.io.latency(), self.inner.io.lost());
+            let beat = res.is_ok();
+            if let Some(event) = p.process(res, latency, lost, self.state()) {
+                match event {
                     StateEvent::Ok => self.set_ok().await?,
-                    StateEvent::Fault(kind) => self.set_fault(kind).await?,
-                },
-                Ok(None) => (),
-                Err(e) => return Err(e),
+                    StateEvent::Warning => self.set_warning().await?,
+                    StateEvent::Degraded { lost } => self.set_degraded(lost).await?,
+                    StateEvent::HighLatency { latency } => {
+                        self.set_high_latency(latency).await?;
+                    }
+                    StateEvent::Fault(kind) => self.set_fault(kind, &mut p).await?,
+                    // never produced by the processor
+                    StateEvent::Paused | StateEvent::Terminated => (),
+                }
+            }
+            if beat {
+                // acknowledgements are best-effort, the heart detects missing ones
+                let _ = self.inner.io.ack(self.state()).await;
             }
+            #[cfg(feature = "std")]
+            self.escalate();
         }
+        Ok(())
     }
-    async fn set_ok(&self) -> Result<()> {
-        if self.state() == State::Ok {
-            return Ok(());
-        }
-        self.inner.state.store(true, Ordering::Relaxed);
-        #[cfg(feature = "std")]
+    /// Pause the watchdog: faults are not evaluated (the I/O is still read) until the watchdog is
+    /// resumed, e.g. during a planned restart of the supervised application. A paused watchdog is
+    /// in OK state
+    ///
+    /// No faults are reported after the method has returned (a fault detected by the current I/O
+    /// read is discarded). [`StateEvent::Paused`] is sent by the run loop after the current I/O
+    /// read (within the I/O timeout), event delivery errors are returned by the run
+    pub fn pause(&self) {
         self.inner
-            .state_tx
-            .send(StateEvent::Ok)
-            .await
-            .map_err(Error::failed)?;
+            .transition
+            .with(|()| self.inner.paused.store(true, Ordering::Relaxed));
+    }
+    /// Resume the paused watchdog. The evaluation is restarted from scratch in OK state, which is
+    /// reported again, the beats are checked after the warmup
+    ///
+    /// The resume is applied by the run loop after the current I/O read (within the I/O timeout),
+    /// I/O and event delivery errors are handled by the run
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::Relaxed);
+    }
+    /// Is the watchdog paused, `true` as soon as the pause is requested
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Relaxed)
+    }
+    async fn send_event(&self, event: StateEvent) -> Result<()> {
+        let latency = self.inner.stats.with(|s| s.last_latency());
+        let now = self.inner.clock.now();
+        self.inner
+            .history
+            .with(|h| h.push(event.clone(), latency, now));
+        #[cfg(feature = "std")]
+        logging::state_event(self.name(), &event);
+        #[cfg(not(feature = "std"))]
+        logging::state_event(None, &event);
+        #[cfg(feature = "std")]
+        {
+            for f in self.inner.callbacks.lock().iter() {
+                f(&event);
+            }
+            // the events never block as a full queue drops the oldest one, the subscribers which
+            // have dropped their receivers are removed
+            self.inner.subscribers.lock().retain(|tx| {
+                !matches!(tx.try_send(event.clone()), Err(rtsc::Error::ChannelClosed))
+            });
+            self.inner
+                .state_tx
+                .send(event.clone())
+                .await
+                .map_err(Error::failed)?;
+        }
         #[cfg(feature = "embassy")]
         if let Some(tx) = &self.inner.embassy_state_tx {
-            tx.send(StateEvent::Ok).await;
+            tx.send(event).await;
         }
         Ok(())
     }
-    async fn set_fault(&self, kind: FaultKind) -> Result<()> {
-        if self.state() == State::Fault && kind != FaultKind::Initial {
-            return Ok(());
+    #[cfg(feature = "std")]
+    fn escalate(&self) {
+        let (state, last_fault) = (self.state(), self.last_fault_clock());
+        if let Some(ref mut escalator) = *self.inner.escalation.lock() {
+            escalator.update(state, last_fault, self.inner.clock.now());
         }
-        self.inner.state.store(false, Ordering::Relaxed);
+    }
+    fn store_state(&self, state: State) {
+        self.inner.state.store(state as u8, Ordering::Relaxed);
+        let now = self.inner.clock.now();
+        self.inner.availability.with(|a| a.transition(state, now));
+        #[cfg(feature = "tokio")]
+        self.inner.state_watch.send_replace(state);
+    }
+    async fn terminate(&self, e: &Error) {
         #[cfg(feature = "std")]
-        self.inner
-            .state_tx
-            .send(StateEvent::Fault(kind))
-            .await
-            .map_err(Error::failed)?;
-        #[cfg(feature = "embassy")]
-        if let Some(tx) = &self.inner.embassy_state_tx {
-            tx.send(StateEvent::Fault(kind)).await;
+        logging::run_failed(self.name(), e);
+        #[cfg(not(feature = "std"))]
+        logging::run_failed(None, e);
+        #[cfg(feature = "std")]
+        for f in self.inner.error_callbacks.lock().iter() {
+            f(e);
         }
-        self.warmup().await?;
-        Ok(())
+        self.store_state(State::Fault);
+        // the run has already failed, the event delivery errors are ignored
+        let _ = self.send_event(StateEvent::Terminated).await;
     }
-    async fn warmup(&self) -> Result<()> {
-        #[cfg(feature = "std")]
-        async_io::Timer::after(self.inner.config.warmup).await;
-        #[cfg(all(feature = "embassy", not(feature = "std")))]
-        embassy_time::Timer::after(embassy_time::Duration::from_micros(
-            self.inner.config.warmup.as_micros().try_into().unwrap(),
-        ))
-        .await;
-        self.inner.io.clear().await?;
+    async fn set_ok(&self) -> Result<()> {
+        if self.state() == State::Ok || self.is_paused() {
+            return Ok(());
+        }
+        self.store_state(State::Ok);
+        self.send_event(StateEvent::Ok).await
+    }
+    async fn set_warning(&self) -> Result<()> {
+        if self.state() != State::Ok || self.is_paused() {
+            return Ok(());
+        }
+        self.store_state(State::Warning);
+        self.send_event(StateEvent::Warning).await
+    }
+    async fn set_degraded(&self, lost: u32) -> Result<()> {
+        if self.state() == State::Fault || self.is_paused() {
+            return Ok(());
+        }
+        self.send_event(StateEvent::Degraded { lost }).await
+    }
+    async fn set_high_latency(&self, latency: Duration) -> Result<()> {
+        if self.state() == State::Fault || self.is_paused() {
+            return Ok(());
+        }
+        self.send_event(StateEvent::HighLatency { latency }).await
+    }
+    async fn set_fault(&self, kind: FaultKind, p: &mut WatchDogProcessor<'_, C>) -> Result<()> {
+        // the pause flag is checked under the transition lock, so the fault is either stored
+        // before the pause or discarded
+        let faulted = self.inner.transition.with(|()| {
+            if (self.state() == State::Fault || self.is_paused()) && kind != FaultKind::Initial {
+                return false;
+            }
+            self.store_state(State::Fault);
+            true
+        });
+        if !faulted {
+            return Ok(());
+        }
+        self.inner.stats.with(|s| s.record_fault(kind));
+        self.inner
+            .last_fault
+            .with(|f| *f = Some((kind, self.inner.clock.now())));
+        self.send_event(StateEvent::Fault(kind)).await?;
+        if self.inner.config.with(|c| c.warmup_after(kind)) {
+            p.start_warmup();
+            // the failed I/O is retried by the run loop
+            if let Err(e) = self.inner.io.clear().await {
+                p.io_failed(e)?;
+            }
+        }
         Ok(())
     }
 }
 
+/// Watchdog acknowledgement, received by hearts which support the feedback path
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Ack {
+    /// The watchdog state
+    pub state: State,
+    /// The time elapsed since the acknowledgement has been received
+    pub elapsed: Duration,
+}
+
 /// Heartbeat client trait
 pub trait Heart {
     /// Send the current edge
     fn beat(&self) -> Result<()>;
+    /// Get the last watchdog acknowledgement (never blocks), `None` if no acknowledgements have
+    /// been received or the feedback path is not supported. If the elapsed time keeps growing,
+    /// the beats are not acknowledged anymore (e.g. the watchdog or the network is down)
+    fn ack(&self) -> Result<Option<Ack>> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Heart + ?Sized> Heart for Arc<H> {
+    fn beat(&self) -> Result<()> {
+        (**self).beat()
+    }
+    fn ack(&self) -> Result<Option<Ack>> {
+        (**self).ack()
+    }
 }
 
 /// Heartbeat async client trait
 pub trait HeartAsync {
     /// Send the current edge asynchronouslyyc
     fn beat_async(&self) -> impl Future<Output = Result<()>>;
+    /// Get the last watchdog acknowledgement asynchronously, see [`Heart::ack`]
+    fn ack_async(&self) -> impl Future<Output = Result<Option<Ack>>> {
+        async { Ok(None) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: HeartAsync + ?Sized> HeartAsync for Arc<H> {
+    fn beat_async(&self) -> impl Future<Output = Result<()>> {
+        (**self).beat_async()
+    }
+    fn ack_async(&self) -> impl Future<Output = Result<Option<Ack>>> {
+        (**self).ack_async()
+    }
+}
+
+// no_std builds require a critical section implementation to link
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Drives the processor the way the run loop does: the events are applied to the state and
+    /// faults start the warmup
+    struct Harness<'a> {
+        p: WatchDogProcessor<'a, MockClock>,
+        clock: &'a MockClock,
+        state: State,
+    }
+
+    impl<'a> Harness<'a> {
+        fn new(
+            config: WatchdogConfig,
+            stats: &'a Shared<WatchdogStats>,
+            clock: &'a MockClock,
+        ) -> Self {
+            Self {
+                p: WatchDogProcessor::with_warmup(config, stats, clock),
+                clock,
+                state: State::Fault,
+            }
+        }
+        fn step(&mut self, after: Duration, res: Result<Edge>, lost: u32) -> Option<StateEvent> {
+            self.step_timed(after, res, None, lost)
+        }
+        fn step_timed(
+            &mut self,
+            after: Duration,
+            res: Result<Edge>,
+            latency: Option<Duration>,
+            lost: u32,
+        ) -> Option<StateEvent> {
+            self.clock.advance(after);
+            let res = self.p.check_io(res).unwrap();
+            let event = self.p.process(res, latency, lost, self.state);
+            match event {
+                Some(StateEvent::Fault(kind)) => {
+                    if self.state != State::Fault && self.p.config.warmup_after(kind) {
+                        self.p.start_warmup();
+                    }
+                    self.state = State::Fault;
+                }
+                Some(StateEvent::Degraded { .. } | StateEvent::HighLatency { .. }) | None => {}
+                Some(ref e) => self.state = e.clone().into(),
+            }
+            event
+        }
+        fn beat(&mut self, after: Duration) -> Option<StateEvent> {
+            let edge = self.p.next;
+            self.step(after, Ok(edge), 0)
+        }
+        /// Passes the warmup and sends the beats required to recover
+        fn recover(&mut self) {
+            self.clock.advance(self.p.config.warmup);
+            let beats = self.p.config.min_beats * 2;
+            for i in 1..=beats {
+                let event = self.beat(INTERVAL);
+                assert_eq!(event, (i == beats).then_some(StateEvent::Ok));
+            }
+            assert_eq!(self.state, State::Ok);
+        }
+    }
+
+    fn stats() -> Shared<WatchdogStats> {
+        Shared::new(WatchdogStats::new(None))
+    }
+
+    #[test]
+    fn test_initial() {
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(WatchdogConfig::new(INTERVAL), &stats, &clock);
+        // beats and errors are discarded during the warmup
+        assert_eq!(h.beat(INTERVAL), None);
+        assert_eq!(h.step(Duration::ZERO, Err(Error::Timeout), 0), None);
+        assert_eq!(h.state, State::Fault);
+        assert_eq!(stats.with(|s| s.beats()), 0);
+        h.recover();
+        assert_eq!(stats.with(|s| s.beats()), 4);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(WatchdogConfig::new(INTERVAL), &stats, &clock);
+        h.recover();
+        assert_eq!(h.beat(INTERVAL), None);
+        assert_eq!(
+            h.step(INTERVAL * 2, Err(Error::Timeout), 0),
+            Some(StateEvent::Fault(FaultKind::Timeout))
+        );
+        // the I/O is discarded during the warmup after the fault
+        assert_eq!(h.step(INTERVAL, Err(Error::Timeout), 0), None);
+        h.recover();
+    }
+
+    #[test]
+    fn test_window() {
+        let config = WatchdogConfig::new(INTERVAL).with_range(Range::Window(INTERVAL / 10));
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        // beats within the window keep the schedule
+        assert_eq!(h.beat(INTERVAL + INTERVAL / 20), None);
+        assert_eq!(h.beat(INTERVAL - INTERVAL / 20), None);
+        assert_eq!(
+            h.beat(INTERVAL / 2),
+            Some(StateEvent::Fault(FaultKind::Window))
+        );
+        h.recover();
+    }
+
+    #[test]
+    fn test_tolerated_miss() {
+        let config = WatchdogConfig::new(INTERVAL).with_fault_tolerance(2);
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        assert_eq!(h.step(INTERVAL * 2, Err(Error::Timeout), 0), None);
+        // a beat resets the misses
+        assert_eq!(h.beat(INTERVAL), None);
+        assert_eq!(h.step(INTERVAL * 2, Err(Error::Timeout), 0), None);
+        assert_eq!(
+            h.step(INTERVAL * 2, Err(Error::Timeout), 0),
+            Some(StateEvent::Fault(FaultKind::Timeout))
+        );
+    }
+
+    #[test]
+    fn test_warning() {
+        let config = WatchdogConfig::new(INTERVAL).with_warning(INTERVAL / 5);
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        assert_eq!(h.beat(INTERVAL + INTERVAL / 10), None);
+        assert_eq!(h.beat(INTERVAL * 2), Some(StateEvent::Warning));
+        assert_eq!(h.state, State::Warning);
+        assert_eq!(h.beat(INTERVAL * 2), None);
+        assert_eq!(h.beat(INTERVAL), Some(StateEvent::Ok));
+    }
+
+    #[test]
+    fn test_io_recovery() {
+        let config = WatchdogConfig::new(INTERVAL).with_io_retry(
+            RetryPolicy::new(INTERVAL)
+                .with_max_delay(INTERVAL * 10)
+                .with_max_retries(2),
+        );
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        assert_eq!(
+            h.step(INTERVAL, Err(Error::Config("I/O failed")), 0),
+            Some(StateEvent::Fault(FaultKind::Io))
+        );
+        assert_eq!(h.p.backoff.take(), Some(INTERVAL));
+        assert_eq!(h.step(INTERVAL, Err(Error::Config("I/O failed")), 0), None);
+        assert_eq!(h.p.backoff.take(), Some(INTERVAL * 2));
+        // a successful read resets the retries
+        h.recover();
+        assert_eq!(h.p.retries, 0);
+        // the retries are exhausted
+        for _ in 0..2 {
+            assert!(h.p.check_io(Err(Error::Config("I/O failed"))).is_ok());
+        }
+        assert!(h.p.check_io(Err(Error::Config("I/O failed"))).is_err());
+    }
+
+    #[test]
+    fn test_high_latency() {
+        let threshold = Duration::from_millis(5);
+        let config = WatchdogConfig::new(INTERVAL).with_high_latency(threshold);
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        let edge = h.p.next;
+        assert_eq!(
+            h.step_timed(INTERVAL, Ok(edge), Some(threshold / 2), 0),
+            None
+        );
+        let edge = h.p.next;
+        assert_eq!(
+            h.step_timed(INTERVAL, Ok(edge), Some(threshold), 0),
+            Some(StateEvent::HighLatency { latency: threshold })
+        );
+        assert_eq!(h.state, State::Ok);
+        // not reported without timestamps
+        let edge = h.p.next;
+        assert_eq!(h.step(INTERVAL, Ok(edge), 0), None);
+    }
+
+    #[test]
+    fn test_lost() {
+        let config = WatchdogConfig::new(INTERVAL).with_degraded(2);
+        let (stats, clock) = (stats(), MockClock::new());
+        let mut h = Harness::new(config, &stats, &clock);
+        h.recover();
+        // tolerated losses below the threshold are only counted
+        let edge = h.p.next;
+        assert_eq!(h.step(INTERVAL, Ok(edge), 1), None);
+        let edge = h.p.next;
+        assert_eq!(
+            h.step(INTERVAL, Ok(edge), 2),
+            Some(StateEvent::Degraded { lost: 2 })
+        );
+        assert_eq!(h.state, State::Ok);
+        assert_eq!(stats.with(|s| s.lost()), 3);
+        assert_eq!(
+            h.step(INTERVAL, Err(Error::Lost), 5),
+            Some(StateEvent::Fault(FaultKind::Lost))
+        );
+        h.recover();
+    }
+
+    #[test]
+    fn test_event_state() {
+        assert_eq!(StateEvent::Paused.state(), Some(State::Ok));
+        assert_eq!(StateEvent::Warning.state(), Some(State::Warning));
+        assert_eq!(StateEvent::Terminated.state(), Some(State::Fault));
+        assert_eq!(StateEvent::Degraded { lost: 1 }.state(), None);
+        let latency = Duration::from_millis(10);
+        assert_eq!(StateEvent::HighLatency { latency }.state(), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10ns").unwrap(), Duration::from_nanos(10));
+        assert_eq!(parse_duration("10us").unwrap(), Duration::from_micros(10));
+        assert_eq!(parse_duration("10µs").unwrap(), Duration::from_micros(10));
+        assert_eq!(parse_duration("10ms").unwrap(), Duration::from_millis(10));
+        assert_eq!(parse_duration(" 10 s ").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("10h").unwrap(), Duration::from_secs(36000));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        let err = |s| parse_duration(s).unwrap_err().to_string();
+        assert_eq!(err("10"), "Invalid configuration: duration unit missing");
+        assert_eq!(err("10d"), "Invalid configuration: invalid duration unit");
+        assert_eq!(err("ms"), "Invalid configuration: invalid duration");
+        assert_eq!(err("-1s"), "Invalid configuration: invalid duration");
+        assert_eq!(
+            err("99999999999999999999s"),
+            "Invalid configuration: invalid duration"
+        );
+        let max = u64::MAX;
+        for s in [
+            format!("{}s", max),
+            format!("{}m", max),
+            format!("{}h", max / 60),
+            format!("{}s", MAX_DURATION.as_secs() + 1),
+        ] {
+            assert!(matches!(
+                parse_duration(&s),
+                Err(Error::Config("duration exceeds MAX_DURATION"))
+            ));
+        }
+        assert_eq!(
+            parse_duration(&format!("{}s", MAX_DURATION.as_secs())).unwrap(),
+            MAX_DURATION
+        );
+    }
+
+    #[test]
+    fn test_config_max_duration() {
+        // must not panic
+        let config = WatchdogConfig::new(Duration::from_secs(u64::MAX));
+        assert!(config.io_timeout() >= config.interval());
+        assert!(matches!(
+            config.validate(),
+            Err(Error::Config(
+                "interval, range and warmup must not exceed MAX_DURATION"
+            ))
+        ));
+        assert!(WatchdogConfig::new(MAX_DURATION).validate().is_err());
+        assert!(WatchdogConfig::new(MAX_DURATION)
+            .with_warmup(MAX_DURATION)
+            .with_range(Range::Timeout(MAX_DURATION))
+            .validate()
+            .is_ok());
+    }
 }
@@ -0,0 +1,84 @@
+use crate::{Error, FaultKind, StateEvent};
+
+/// The name which is logged for unnamed watchdogs
+const DEFAULT_NAME: &str = "watchdog";
+
+// the format strings are compatible with both backends: `{}` for strings and `{:?}` for the
+// values which implement both Debug and defmt::Format
+macro_rules! emit {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::$level!($($arg)*);
+        #[cfg(feature = "defmt")]
+        defmt::$level!($($arg)*);
+    };
+}
+
+// faults are logged as errors (except the initial one, which is the normal startup state),
+// warnings, degradations and high latencies as warnings, others as info
+macro_rules! emit_event {
+    ($event:expr, $($arg:tt)*) => {
+        match $event {
+            StateEvent::Fault(FaultKind::Initial) | StateEvent::Ok | StateEvent::Paused => {
+                emit!(info, $($arg)*);
+            }
+            StateEvent::Warning | StateEvent::Degraded { .. } | StateEvent::HighLatency { .. } => {
+                emit!(warn, $($arg)*);
+            }
+            StateEvent::Fault(_) | StateEvent::Terminated => {
+                emit!(error, $($arg)*);
+            }
+        }
+    };
+}
+
+/// Log a state transition
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(unused_variables))]
+pub(crate) fn state_event(name: Option<&str>, event: &StateEvent) {
+    let name = name.unwrap_or(DEFAULT_NAME);
+    emit_event!(event, "{}: {:?}", name, event);
+}
+
+/// Log a node state transition of a multi-node watchdog
+#[cfg(feature = "std")]
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(unused_variables))]
+pub(crate) fn node_event(node: u16, event: &StateEvent) {
+    emit_event!(event, "node {}: {:?}", node, event);
+}
+
+/// Log an I/O error which is retried
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(unused_variables))]
+pub(crate) fn io_retry(e: &Error, retry: u32) {
+    #[cfg(feature = "log")]
+    log::warn!("watchdog I/O error (retry {}): {}", retry, e);
+    #[cfg(feature = "defmt")]
+    defmt::warn!(
+        "watchdog I/O error (retry {}): {}",
+        retry,
+        defmt::Display2Format(e)
+    );
+}
+
+/// Log the error which has terminated the watchdog run
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(unused_variables))]
+pub(crate) fn run_failed(name: Option<&str>, e: &Error) {
+    let name = name.unwrap_or(DEFAULT_NAME);
+    #[cfg(feature = "log")]
+    log::error!("{}: run failed: {}", name, e);
+    #[cfg(feature = "defmt")]
+    defmt::error!("{}: run failed: {}", name, defmt::Display2Format(e));
+}
+
+/// Log a notification which has been dropped after the retries
+#[cfg(feature = "webhook")]
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(unused_variables))]
+pub(crate) fn notification_failed(target: &str, e: &Error) {
+    #[cfg(feature = "log")]
+    log::error!("{} notification failed: {}", target, e);
+    #[cfg(feature = "defmt")]
+    defmt::error!(
+        "{} notification failed: {}",
+        target,
+        defmt::Display2Format(e)
+    );
+}
@@ -0,0 +1,123 @@
+use core::time::Duration;
+use std::thread;
+
+use rtsc::policy_channel;
+use rumqttc::{Client, QoS};
+
+use crate::{
+    io::mqtt::MqttOptions, Condvar, Error, FaultKind, RawMutex, Result, State, StateEvent,
+    StateSource,
+};
+
+const REQUEST_CAPACITY: usize = 10;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// MQTT state publisher
+///
+/// State transitions are published to the topic (not retained) as text: `Fault:<kind>` (e.g.
+/// `Fault:Timeout`), `Ok`, `Warning`, `Paused`, `Degraded:<lost beats>`,
+/// `HighLatency:<latency in microseconds>` or `Terminated`.
+///
+/// Optionally (see [`StatePublisher::with_state`]), the current state (`Fault`, `Ok` or `Warning`)
+/// is published as a retained message to `<topic>/state` on transitions and periodically, so
+/// dashboards get it immediately after subscribing. Set the last will of the client to a retained
+/// `Fault` message on the state topic to report the publisher failure.
+#[derive(Clone)]
+pub struct StatePublisher {
+    client: Client,
+    topic: String,
+    state_topic: String,
+    qos: QoS,
+    refresh: Option<Duration>,
+}
+
+impl StatePublisher {
+    /// creates a new publisher, the connection is maintained by a background thread, which
+    /// reconnects automatically in case of errors
+    pub fn create(options: MqttOptions, topic: &str) -> Result<Self> {
+        let (client, mut connection) = Client::new(options, REQUEST_CAPACITY);
+        thread::Builder::new()
+            .name("watchdog-mqtt".to_owned())
+            .spawn(move || {
+                // the iterator ends when the client is dropped
+                for event in connection.iter() {
+                    if event.is_err() {
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            })?;
+        Ok(Self {
+            client,
+            topic: topic.to_owned(),
+            state_topic: format!("{}/state", topic),
+            qos: QoS::AtLeastOnce,
+            refresh: None,
+        })
+    }
+    /// Set the QoS of the messages (at least once by default)
+    pub fn with_qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+    /// Publish the retained state messages, which are refreshed with the interval
+    pub fn with_state(mut self, refresh: Duration) -> Self {
+        self.refresh = Some(refresh);
+        self
+    }
+    /// Publish a state event (does not block, fails if the request queue is full)
+    pub fn publish(&self, event: &StateEvent) -> Result<()> {
+        let payload = match event {
+            StateEvent::Fault(kind) => format!("Fault:{:?}", kind),
+            StateEvent::Degraded { lost } => format!("Degraded:{}", lost),
+            StateEvent::HighLatency { latency } => format!("HighLatency:{}", latency.as_micros()),
+            StateEvent::Ok => "Ok".to_owned(),
+            StateEvent::Warning => "Warning".to_owned(),
+            StateEvent::Paused => "Paused".to_owned(),
+            StateEvent::Terminated => "Terminated".to_owned(),
+        };
+        self.client
+            .try_publish(&self.topic, self.qos, false, payload)
+            .map_err(Error::failed)
+    }
+    /// Publish the retained state message (does not block, fails if the request queue is full)
+    pub fn publish_state(&self, state: State) -> Result<()> {
+        self.client
+            .try_publish(&self.state_topic, self.qos, true, format!("{:?}", state))
+            .map_err(Error::failed)
+    }
+    /// Publish the state events of the watchdog (or any other state source), publish errors are
+    /// ignored
+    pub fn attach<S: StateSource>(self, source: &S) -> Result<()> {
+        let Some(refresh) = self.refresh else {
+            source.on_state_change(Box::new(move |event| {
+                if *event != StateEvent::Fault(FaultKind::Initial) {
+                    let _ = self.publish(event);
+                }
+            }));
+            return Ok(());
+        };
+        let (tx, rx) = policy_channel::bounded::<State, RawMutex, Condvar>(1);
+        let mut state = source.state();
+        let publisher = self.clone();
+        source.on_state_change(Box::new(move |event| {
+            if *event != StateEvent::Fault(FaultKind::Initial) {
+                let _ = publisher.publish(event);
+            }
+            if let Some(state) = event.state() {
+                // never blocks as the latest state replaces the previous one
+                let _ = tx.try_send(state);
+            }
+        }));
+        thread::Builder::new()
+            .name("watchdog-mqtt-st".to_owned())
+            .spawn(move || loop {
+                let _ = self.publish_state(state);
+                match rx.recv_timeout(refresh) {
+                    Ok(new_state) => state = new_state,
+                    Err(rtsc::Error::Timeout) => {}
+                    Err(_) => break,
+                }
+            })?;
+        Ok(())
+    }
+}
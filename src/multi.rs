@@ -0,0 +1,493 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use core::time::Duration;
+use portable_atomic::{AtomicU8, Ordering};
+use rtsc::policy_channel;
+
+use crate::{
+    io::MultiWatchdogIo, logging, Clock, Condvar, Edge, Error, ErrorCallback, FaultKind, Histogram,
+    RawMutex, Result, RetryPolicy, Shared, State, StateEvent, SystemClock, WatchDogProcessor,
+    WatchdogConfig, WatchdogStats, SUBSCRIBER_QUEUE_SIZE,
+};
+
+type NodeCallback = Box<dyn Fn(&NodeEvent) + Send + Sync>;
+
+/// State event of a node, see [`MultiWatchdog`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeEvent {
+    /// The node ID
+    pub node: u16,
+    /// The state event
+    pub event: StateEvent,
+}
+
+impl rtsc::data_policy::DataDeliveryPolicy for NodeEvent {
+    fn delivery_policy(&self) -> rtsc::data_policy::DeliveryPolicy {
+        rtsc::data_policy::DeliveryPolicy::Latest
+    }
+    // a full queue drops the oldest event, so a watchdog without a receiver never blocks
+    fn eq_kind(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+struct Node {
+    state: AtomicU8,
+    stats: Shared<WatchdogStats>,
+    last_fault: Shared<Option<(FaultKind, Duration)>>,
+}
+
+impl Node {
+    fn state(&self) -> State {
+        State::from(self.state.load(Ordering::Relaxed))
+    }
+}
+
+/// Consecutive I/O failures and the delay before the next retry
+#[derive(Default)]
+struct IoRetry {
+    retries: u32,
+    backoff: Option<Duration>,
+}
+
+impl IoRetry {
+    /// Count an I/O failure and schedule the retry, the error is returned back if the retries are
+    /// exhausted
+    fn failed(&mut self, policy: &RetryPolicy, e: Error) -> Result<Error> {
+        self.retries += 1;
+        if policy.max_retries.is_some_and(|n| self.retries > n) {
+            return Err(e);
+        }
+        self.backoff = Some(policy.backoff(self.retries));
+        Ok(e)
+    }
+}
+
+struct MultiWatchdogInner<I: MultiWatchdogIo, C: Clock> {
+    io: I,
+    clock: C,
+    config: WatchdogConfig,
+    nodes: BTreeMap<u16, Node>,
+    subscribers: rtsc::pi::Mutex<Vec<policy_channel::Sender<NodeEvent, RawMutex, Condvar>>>,
+    callbacks: rtsc::pi::Mutex<Vec<NodeCallback>>,
+    error_callbacks: rtsc::pi::Mutex<Vec<ErrorCallback>>,
+}
+
+/// Multi-node watchdog
+///
+/// Supervises a fixed set of nodes, which send beats with node IDs over a single I/O (e.g.
+/// [`crate::io::udp::UdpIo`], see [`crate::io::udp::UdpHeart::with_node_id`]), with a single run
+/// loop. Each node has got an independent state, the same configuration is applied to all nodes.
+/// Beats of unknown nodes are ignored. A node is switched to Fault state if it has sent no beats
+/// within [`WatchdogConfig::io_timeout`], so the I/O timeout should be shorter (e.g. the beat
+/// interval), otherwise the faults of silent nodes are reported with a delay.
+///
+/// ```rust,ignore
+/// let watchdog = MultiWatchdog::new(WatchdogConfig::new(interval), io, 1..=40);
+/// watchdog.on_state_change(|e| println!("node {}: {:?}", e.node, e.event));
+/// watchdog.run()?;
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct MultiWatchdog<I: MultiWatchdogIo, C: Clock = SystemClock> {
+    inner: Arc<MultiWatchdogInner<I, C>>,
+}
+
+impl<I: MultiWatchdogIo, C: Clock> Clone for MultiWatchdog<I, C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<I: MultiWatchdogIo> MultiWatchdog<I> {
+    /// Create a new multi-node watchdog, the configuration is validated
+    pub fn try_new(
+        config: WatchdogConfig,
+        io: I,
+        nodes: impl IntoIterator<Item = u16>,
+    ) -> Result<Self> {
+        config.validate()?;
+        Ok(Self::new(config, io, nodes))
+    }
+    /// Create a new multi-node watchdog
+    pub fn new(config: WatchdogConfig, io: I, nodes: impl IntoIterator<Item = u16>) -> Self {
+        Self::with_clock(config, io, nodes, SystemClock)
+    }
+}
+
+impl<I: MultiWatchdogIo, C: Clock> MultiWatchdog<I, C> {
+    /// Create a new multi-node watchdog with a custom clock, which is used to evaluate heartbeats
+    pub fn with_clock(
+        config: WatchdogConfig,
+        io: I,
+        nodes: impl IntoIterator<Item = u16>,
+        clock: C,
+    ) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .map(|id| {
+                let node = Node {
+                    state: AtomicU8::new(State::Fault as u8),
+                    stats: Shared::new(WatchdogStats::new(
+                        config
+                            .histogram
+                            .map(|(width, buckets)| Histogram::new(width, buckets)),
+                    )),
+                    last_fault: Shared::new(None),
+                };
+                (id, node)
+            })
+            .collect();
+        Self {
+            inner: MultiWatchdogInner {
+                io,
+                clock,
+                config,
+                nodes,
+                subscribers: <_>::default(),
+                callbacks: <_>::default(),
+                error_callbacks: <_>::default(),
+            }
+            .into(),
+        }
+    }
+    /// Get the node IDs
+    pub fn nodes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.inner.nodes.keys().copied()
+    }
+    /// Get the current state of the node, `None` if the node is unknown
+    pub fn state(&self, node: u16) -> Option<State> {
+        self.inner.nodes.get(&node).map(Node::state)
+    }
+    /// Get the statistics of the node
+    pub fn stats(&self, node: u16) -> Option<WatchdogStats> {
+        self.inner
+            .nodes
+            .get(&node)
+            .map(|n| n.stats.with(|s| s.clone()))
+    }
+    /// Get the kind and the time of the last fault of the node
+    pub fn last_fault(&self, node: u16) -> Option<(FaultKind, std::time::Instant)> {
+        self.last_fault_clock(node)
+            .map(|(kind, time)| (kind, crate::clock::to_instant(&self.inner.clock, time)))
+    }
+    /// Get the kind and the time of the last fault of the node, the time is the watchdog clock one
+    /// (see [`Clock::now`])
+    pub fn last_fault_clock(&self, node: u16) -> Option<(FaultKind, Duration)> {
+        self.inner
+            .nodes
+            .get(&node)
+            .and_then(|n| n.last_fault.with(|f| *f))
+    }
+    /// Get the configuration
+    pub fn config(&self) -> WatchdogConfig {
+        self.inner.config.clone()
+    }
+    /// Subscribe to node state events, every subscriber receives all transitions
+    pub fn subscribe(&self) -> policy_channel::Receiver<NodeEvent, RawMutex, Condvar> {
+        let (tx, rx) = policy_channel::bounded(SUBSCRIBER_QUEUE_SIZE);
+        self.inner.subscribers.lock().push(tx);
+        rx
+    }
+    /// Register a node state change callback. Callbacks are called from the run loop, so they
+    /// must not block and must not register other callbacks
+    pub fn on_state_change(&self, f: impl Fn(&NodeEvent) + Send + Sync + 'static) {
+        self.inner.callbacks.lock().push(Box::new(f));
+    }
+    /// Register a terminal error callback, called when the watchdog run fails (e.g. the I/O
+    /// retries are exhausted), before [`StateEvent::Terminated`] is sent for all nodes
+    pub fn on_error(&self, f: impl Fn(&Error) + Send + Sync + 'static) {
+        self.inner.error_callbacks.lock().push(Box::new(f));
+    }
+    /// Run the watchdog
+    pub fn run(&self) -> Result<()> {
+        self.run_until(&core::sync::atomic::AtomicBool::new(false))
+    }
+    /// Run the watchdog until the stop flag is set. The flag is checked after each I/O read. If
+    /// the run fails, the error is passed to the error callbacks and [`StateEvent::Terminated`]
+    /// is sent for all nodes
+    pub fn run_until(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let res = self.run_loop(stop);
+        if let Err(ref e) = res {
+            self.terminate(e);
+        }
+        res
+    }
+    fn run_loop(&self, stop: &core::sync::atomic::AtomicBool) -> Result<()> {
+        let inner = &*self.inner;
+        let mut processors: BTreeMap<u16, WatchDogProcessor<'_, C>> = inner
+            .nodes
+            .iter()
+            .map(|(id, node)| {
+                let p = WatchDogProcessor::new(inner.config.clone(), &node.stats, &inner.clock);
+                (*id, p)
+            })
+            .collect();
+        let mut retry = IoRetry::default();
+        for (id, p) in &mut processors {
+            self.set_fault(*id, FaultKind::Initial, p, &mut retry)?;
+        }
+        let timeout = inner.config.io_timeout();
+        while !stop.load(Ordering::Relaxed) {
+            if let Some(delay) = retry.backoff.take() {
+                // failed I/Os usually return immediately, the retries are delayed
+                std::thread::sleep(delay);
+                if let Err(e) = inner.io.reset() {
+                    retry.failed(&inner.config.io_retry, e)?;
+                    continue;
+                }
+            }
+            let res = inner.io.get();
+            // the last read may be interrupted by the shutdown, do not report it
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            match res {
+                Ok((id, res)) => {
+                    retry.retries = 0;
+                    if let Some(p) = processors.get_mut(&id) {
+                        let beat = res.is_ok();
+                        let (latency, lost) = (inner.io.latency(), inner.io.lost());
+                        self.process(id, p, res, latency, lost, &mut retry)?;
+                        if beat {
+                            // acknowledgements are best-effort, the heart detects missing ones
+                            let _ = inner.io.ack(inner.nodes[&id].state());
+                        }
+                    }
+                }
+                Err(Error::Timeout) => retry.retries = 0,
+                Err(e) => {
+                    // no node can be observed
+                    let e = retry.failed(&inner.config.io_retry, e)?;
+                    for (id, p) in &mut processors {
+                        let res = Err(Error::failed(&e));
+                        self.process(*id, p, res, None, 0, &mut retry)?;
+                    }
+                }
+            }
+            for (id, p) in &mut processors {
+                if p.elapsed(p.last_packet) >= timeout {
+                    self.process(*id, p, Err(Error::Timeout), None, 0, &mut retry)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn process(
+        &self,
+        id: u16,
+        p: &mut WatchDogProcessor<'_, C>,
+        res: Result<Edge>,
+        latency: Option<Duration>,
+        lost: u32,
+        retry: &mut IoRetry,
+    ) -> Result<()> {
+        let node = &self.inner.nodes[&id];
+        let Some(event) = p.process(res, latency, lost, node.state()) else {
+            return Ok(());
+        };
+        match event {
+            StateEvent::Ok => {
+                if node.state() != State::Ok {
+                    node.state.store(State::Ok as u8, Ordering::Relaxed);
+                    self.send_event(id, StateEvent::Ok);
+                }
+            }
+            StateEvent::Warning => {
+                if node.state() == State::Ok {
+                    node.state.store(State::Warning as u8, Ordering::Relaxed);
+                    self.send_event(id, StateEvent::Warning);
+                }
+            }
+            StateEvent::Degraded { lost } => {
+                if node.state() != State::Fault {
+                    self.send_event(id, StateEvent::Degraded { lost });
+                }
+            }
+            StateEvent::HighLatency { latency } => {
+                if node.state() != State::Fault {
+                    self.send_event(id, StateEvent::HighLatency { latency });
+                }
+            }
+            StateEvent::Fault(kind) => self.set_fault(id, kind, p, retry)?,
+            // never produced by the processor
+            StateEvent::Paused | StateEvent::Terminated => (),
+        }
+        Ok(())
+    }
+    fn set_fault(
+        &self,
+        id: u16,
+        kind: FaultKind,
+        p: &mut WatchDogProcessor<'_, C>,
+        retry: &mut IoRetry,
+    ) -> Result<()> {
+        let node = &self.inner.nodes[&id];
+        if node.state() == State::Fault && kind != FaultKind::Initial {
+            return Ok(());
+        }
+        node.state.store(State::Fault as u8, Ordering::Relaxed);
+        node.stats.with(|s| s.record_fault(kind));
+        let now = self.inner.clock.now();
+        node.last_fault.with(|f| *f = Some((kind, now)));
+        self.send_event(id, StateEvent::Fault(kind));
+        if self.inner.config.warmup_after(kind) {
+            p.start_warmup();
+            // the failed I/O is retried by the run loop
+            if let Err(e) = self.inner.io.clear(id) {
+                retry.failed(&self.inner.config.io_retry, e)?;
+            }
+        }
+        Ok(())
+    }
+    fn send_event(&self, node: u16, event: StateEvent) {
+        logging::node_event(node, &event);
+        let event = NodeEvent { node, event };
+        for f in self.inner.callbacks.lock().iter() {
+            f(&event);
+        }
+        // the events never block as a full queue drops the oldest one, the subscribers which have
+        // dropped their receivers are removed
+        self.inner
+            .subscribers
+            .lock()
+            .retain(|tx| !matches!(tx.try_send(event.clone()), Err(rtsc::Error::ChannelClosed)));
+    }
+    fn terminate(&self, e: &Error) {
+        logging::run_failed(None, e);
+        for f in self.inner.error_callbacks.lock().iter() {
+            f(e);
+        }
+        for (id, node) in &self.inner.nodes {
+            node.state.store(State::Fault as u8, Ordering::Relaxed);
+            self.send_event(*id, StateEvent::Terminated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        io::{mock::MockIo, WatchdogIo},
+        MockClock,
+    };
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Replays a mock I/O script for two nodes: rising edges are the beats of node 1, falling
+    /// ones are the beats of node 2 (the edges of each node alternate)
+    struct TwoNodes {
+        io: MockIo,
+        next: [portable_atomic::AtomicBool; 2],
+    }
+
+    impl MultiWatchdogIo for TwoNodes {
+        fn get(&self) -> Result<(u16, Result<Edge>)> {
+            let node = match self.io.get(Edge::Rising)? {
+                Edge::Rising => 1,
+                Edge::Falling => 2,
+            };
+            let next = &self.next[usize::from(node - 1)];
+            Ok((
+                node,
+                Ok(Edge::from(next.fetch_xor(true, Ordering::Relaxed))),
+            ))
+        }
+        fn clear(&self, _node: u16) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs a watchdog for nodes 1 and 2 with a virtual clock until the script is finished (the
+    /// script is terminated with I/O failures which exceed the retries), returns the node events
+    fn run(script: impl FnOnce(&MockIo)) -> Vec<NodeEvent> {
+        let config = WatchdogConfig::new(INTERVAL)
+            .with_io_retry(RetryPolicy::new(Duration::from_millis(1)).with_max_retries(1));
+        let clock = Arc::new(MockClock::new());
+        let io = MockIo::new()
+            .with_clock(clock.clone())
+            .with_timeout(config.io_timeout());
+        script(&io);
+        io.error("end").error("end");
+        let io = TwoNodes {
+            io,
+            next: [true.into(), true.into()],
+        };
+        let watchdog = MultiWatchdog::with_clock(config, io, [1, 2], clock);
+        let events = Arc::new(rtsc::pi::Mutex::new(Vec::new()));
+        let e = events.clone();
+        watchdog.on_state_change(move |event| e.lock().push(event.clone()));
+        assert!(matches!(watchdog.run(), Err(Error::Failed(msg)) if msg == "end"));
+        let events = events.lock().clone();
+        events
+    }
+
+    /// Both nodes beat with the interval
+    fn beats(io: &MockIo, n: usize) {
+        for _ in 0..n {
+            io.delay(INTERVAL / 2)
+                .edge(Edge::Rising)
+                .delay(INTERVAL / 2)
+                .edge(Edge::Falling);
+        }
+    }
+
+    fn node_events(events: &[NodeEvent], node: u16) -> Vec<StateEvent> {
+        events
+            .iter()
+            .filter(|e| e.node == node)
+            .map(|e| e.event.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_node_fault() {
+        let events = run(|io| {
+            beats(io, 10);
+            // node 2 is silent
+            for _ in 0..10 {
+                io.delay(INTERVAL).edge(Edge::Rising);
+            }
+        });
+        assert_eq!(
+            node_events(&events, 1),
+            [
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Io),
+                StateEvent::Terminated,
+            ]
+        );
+        assert_eq!(
+            node_events(&events, 2),
+            [
+                StateEvent::Fault(FaultKind::Initial),
+                StateEvent::Ok,
+                StateEvent::Fault(FaultKind::Timeout),
+                StateEvent::Terminated,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_io_fault() {
+        let events = run(|io| {
+            beats(io, 10);
+            // the I/O failure is recovered with a retry, but no node can be observed
+            io.error("link down");
+            beats(io, 10);
+        });
+        let expected = [
+            StateEvent::Fault(FaultKind::Initial),
+            StateEvent::Ok,
+            StateEvent::Fault(FaultKind::Io),
+            StateEvent::Ok,
+            StateEvent::Fault(FaultKind::Io),
+            StateEvent::Terminated,
+        ];
+        assert_eq!(node_events(&events, 1), expected);
+        assert_eq!(node_events(&events, 2), expected);
+    }
+}
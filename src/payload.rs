@@ -0,0 +1,349 @@
+#[cfg(feature = "std")]
+use crate::{Condvar, Edge, RawMutex};
+use crate::{Error, Result};
+#[cfg(feature = "encryption")]
+use chacha20poly1305::{
+    aead::{AeadInPlace, KeyInit},
+    Tag, XChaCha20Poly1305, XNonce,
+};
+#[cfg(feature = "encryption")]
+use portable_atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use rtsc::policy_channel;
+#[cfg(feature = "encryption")]
+use std::collections::VecDeque;
+
+/// Maximum encoded payload size
+pub const MAX_PAYLOAD: usize = 64;
+
+/// Heartbeat payload encoder
+pub trait Encode {
+    /// Encode the payload into the buffer ([`MAX_PAYLOAD`] bytes), returns the encoded size
+    fn encode(&self, buf: &mut [u8]) -> Result<usize>;
+}
+
+/// Heartbeat payload decoder
+pub trait Decode: Sized {
+    /// Decode the payload
+    fn decode(buf: &[u8]) -> Result<Self>;
+}
+
+/// Heartbeat client which sends payloads with beats
+#[allow(clippy::module_name_repetitions)]
+pub trait PayloadHeart<T: Encode> {
+    /// Send the current edge with the payload
+    fn beat_with(&self, payload: &T) -> Result<()>;
+}
+
+fn invalid_payload() -> Error {
+    #[cfg(feature = "std")]
+    return Error::failed("invalid payload");
+    #[cfg(not(feature = "std"))]
+    Error::failed()
+}
+
+// numbers are encoded in the network byte order, same as sequence numbers
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, buf: &mut [u8]) -> Result<usize> {
+                    let b = self.to_be_bytes();
+                    buf.get_mut(..b.len())
+                        .ok_or_else(invalid_payload)?
+                        .copy_from_slice(&b);
+                    Ok(b.len())
+                }
+            }
+            impl Decode for $t {
+                fn decode(buf: &[u8]) -> Result<Self> {
+                    Ok(Self::from_be_bytes(
+                        buf.try_into().map_err(|_| invalid_payload())?,
+                    ))
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Encode for bool {
+    fn encode(&self, buf: &mut [u8]) -> Result<usize> {
+        u8::from(*self).encode(buf)
+    }
+}
+
+impl Decode for bool {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        Ok(u8::decode(buf)? != 0)
+    }
+}
+
+impl<const N: usize> Encode for [u8; N] {
+    fn encode(&self, buf: &mut [u8]) -> Result<usize> {
+        buf.get_mut(..N)
+            .ok_or_else(invalid_payload)?
+            .copy_from_slice(self);
+        Ok(N)
+    }
+}
+
+impl<const N: usize> Decode for [u8; N] {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        buf.try_into().map_err(|_| invalid_payload())
+    }
+}
+
+/// Beat payload, delivered to the application by watchdog I/Os alongside the edge
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Payload<T> {
+    /// The beat edge
+    pub edge: Edge,
+    /// The decoded payload
+    pub data: T,
+}
+
+#[cfg(feature = "std")]
+impl<T> rtsc::data_policy::DataDeliveryPolicy for Payload<T> {
+    fn delivery_policy(&self) -> rtsc::data_policy::DeliveryPolicy {
+        rtsc::data_policy::DeliveryPolicy::Latest
+    }
+    // the latest payload supersedes the previous one, so the I/O never blocks
+    fn eq_kind(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Payload channel sender, used by watchdog I/Os
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub type PayloadSender<T> = policy_channel::Sender<Payload<T>, RawMutex, Condvar>;
+
+/// Payload channel receiver, always contains the latest payload
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub type PayloadReceiver<T> = policy_channel::Receiver<Payload<T>, RawMutex, Condvar>;
+
+/// Create a new payload channel
+#[cfg(feature = "std")]
+pub fn channel<T>() -> (PayloadSender<T>, PayloadReceiver<T>) {
+    policy_channel::bounded(1)
+}
+
+#[cfg(feature = "encryption")]
+const NONCE_SIZE: usize = 24;
+/// The random sender ID, the counter follows
+#[cfg(feature = "encryption")]
+const SENDER_SIZE: usize = 16;
+#[cfg(feature = "encryption")]
+const CIPHER_TAG_SIZE: usize = 16;
+/// The number of senders whose last nonce counters are remembered to reject replays
+#[cfg(feature = "encryption")]
+const MAX_SENDERS: usize = 64;
+
+/// Size of the nonce and the tag, which are sent with encrypted payloads
+#[cfg(feature = "encryption")]
+pub const ENCRYPTION_OVERHEAD: usize = NONCE_SIZE + CIPHER_TAG_SIZE;
+
+/// Payload cipher (XChaCha20-Poly1305)
+///
+/// A 192-bit nonce consists of a random 128-bit sender ID, which is generated when the cipher is
+/// created, and a counter, so nonces are not reused with the same key, also by many hearts and
+/// after restarts. Payloads with nonces at or below the last accepted counter of the sender are
+/// rejected as replayed. The heart and the watchdog must use the same key.
+///
+/// Only the counters of the last 64 senders are remembered. Every restarted heart is a new
+/// sender, so when more senders are seen, the oldest one is forgotten and its recorded payloads
+/// are accepted again (once per eviction). Unknown senders are not rejected by a global counter
+/// mark, as a restarted heart always starts counting from zero.
+#[cfg(feature = "encryption")]
+#[allow(clippy::module_name_repetitions)]
+pub struct PayloadCipher {
+    cipher: XChaCha20Poly1305,
+    sender: [u8; SENDER_SIZE],
+    counter: AtomicU64,
+    // the last accepted counters of the senders, the most recent last
+    received: rtsc::pi::Mutex<VecDeque<([u8; SENDER_SIZE], u64)>>,
+}
+
+#[cfg(feature = "encryption")]
+impl PayloadCipher {
+    /// Create a new cipher with the shared key
+    pub fn new(key: &[u8; 32]) -> Result<Self> {
+        let mut sender = [0; SENDER_SIZE];
+        getrandom::getrandom(&mut sender).map_err(Error::failed)?;
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(key.into()),
+            sender,
+            counter: AtomicU64::new(0),
+            received: <_>::default(),
+        })
+    }
+    /// Encrypt the payload of the given size in place, the nonce and the tag are appended, so the
+    /// buffer must have [`ENCRYPTION_OVERHEAD`] bytes of space after the payload. The associated
+    /// data (e.g. the beat header) is authenticated but not encrypted. Returns the encrypted size
+    pub fn seal(&self, associated_data: &[u8], buf: &mut [u8], len: usize) -> Result<usize> {
+        let size = len + ENCRYPTION_OVERHEAD;
+        let (data, rest) = buf
+            .get_mut(..size)
+            .ok_or_else(invalid_payload)?
+            .split_at_mut(len);
+        let (nonce, tag) = rest.split_at_mut(NONCE_SIZE);
+        nonce[..SENDER_SIZE].copy_from_slice(&self.sender);
+        nonce[SENDER_SIZE..]
+            .copy_from_slice(&self.counter.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+        tag.copy_from_slice(
+            &self
+                .cipher
+                .encrypt_in_place_detached(XNonce::from_slice(nonce), associated_data, data)
+                .map_err(|_| invalid_payload())?,
+        );
+        Ok(size)
+    }
+    /// Decrypt the payload in place, returns `None` if the payload is forged, corrupted or
+    /// replayed
+    pub fn open<'a>(&self, associated_data: &[u8], buf: &'a mut [u8]) -> Option<&'a [u8]> {
+        let len = buf.len().checked_sub(ENCRYPTION_OVERHEAD)?;
+        let (data, rest) = buf.split_at_mut(len);
+        let (nonce, tag) = rest.split_at(NONCE_SIZE);
+        let mut sender = [0; SENDER_SIZE];
+        sender.copy_from_slice(&nonce[..SENDER_SIZE]);
+        let mut counter = [0; 8];
+        counter.copy_from_slice(&nonce[SENDER_SIZE..]);
+        let counter = u64::from_be_bytes(counter);
+        let mut received = self.received.lock();
+        let pos = received.iter().position(|(s, _)| *s == sender);
+        if pos.is_some_and(|pos| counter <= received[pos].1) {
+            return None;
+        }
+        self.cipher
+            .decrypt_in_place_detached(
+                XNonce::from_slice(nonce),
+                associated_data,
+                data,
+                Tag::from_slice(tag),
+            )
+            .ok()?;
+        // the counter is recorded for authentic payloads only
+        if let Some(pos) = pos {
+            received.remove(pos);
+        } else if received.len() == MAX_SENDERS {
+            received.pop_front();
+        }
+        received.push_back((sender, counter));
+        Some(data)
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
+    const HEADER: &[u8] = b"+\x00\x01";
+
+    fn seal(cipher: &PayloadCipher, payload: &[u8]) -> Vec<u8> {
+        let mut buf = [0; MAX_PAYLOAD + ENCRYPTION_OVERHEAD];
+        buf[..payload.len()].copy_from_slice(payload);
+        let len = cipher.seal(HEADER, &mut buf, payload.len()).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn open(cipher: &PayloadCipher, header: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+        let mut buf = sealed.to_vec();
+        cipher.open(header, &mut buf).map(<[u8]>::to_vec)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (heart, watchdog) = (
+            PayloadCipher::new(KEY).unwrap(),
+            PayloadCipher::new(KEY).unwrap(),
+        );
+        for payload in [&b"temperature=21"[..], b"", &[0xff; MAX_PAYLOAD]] {
+            let sealed = seal(&heart, payload);
+            assert_eq!(sealed.len(), payload.len() + ENCRYPTION_OVERHEAD);
+            assert_eq!(open(&watchdog, HEADER, &sealed).unwrap(), payload);
+        }
+        // the payload does not fit
+        let mut buf = [0; MAX_PAYLOAD];
+        assert!(heart.seal(HEADER, &mut buf, MAX_PAYLOAD).is_err());
+    }
+
+    #[test]
+    fn test_tampered() {
+        let (heart, watchdog) = (
+            PayloadCipher::new(KEY).unwrap(),
+            PayloadCipher::new(KEY).unwrap(),
+        );
+        let sealed = seal(&heart, b"mode=auto");
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x80;
+            assert!(open(&watchdog, HEADER, &tampered).is_none(), "byte {}", i);
+        }
+        // the header is authenticated
+        assert!(open(&watchdog, b".\x00\x01", &sealed).is_none());
+        assert!(open(&watchdog, HEADER, &sealed[1..]).is_none());
+        assert!(open(&watchdog, HEADER, &sealed[..ENCRYPTION_OVERHEAD - 1]).is_none());
+        let other = PayloadCipher::new(&[0; 32]).unwrap();
+        assert!(open(&other, HEADER, &sealed).is_none());
+        // failed attempts do not affect the authentic payload
+        assert_eq!(open(&watchdog, HEADER, &sealed).unwrap(), b"mode=auto");
+    }
+
+    #[test]
+    fn test_replayed() {
+        let watchdog = PayloadCipher::new(KEY).unwrap();
+        let (heart, restarted) = (
+            PayloadCipher::new(KEY).unwrap(),
+            PayloadCipher::new(KEY).unwrap(),
+        );
+        let sealed: Vec<Vec<u8>> = (0..3).map(|i| seal(&heart, &[i])).collect();
+        assert!(open(&watchdog, HEADER, &sealed[1]).is_some());
+        assert!(open(&watchdog, HEADER, &sealed[1]).is_none());
+        assert!(open(&watchdog, HEADER, &sealed[0]).is_none());
+        // the counters are tracked per sender, e.g. a restarted heart starts from zero
+        assert!(open(&watchdog, HEADER, &seal(&restarted, b"")).is_some());
+        assert!(open(&watchdog, HEADER, &sealed[2]).is_some());
+        assert!(open(&watchdog, HEADER, &sealed[2]).is_none());
+    }
+
+    #[test]
+    fn test_evicted() {
+        let watchdog = PayloadCipher::new(KEY).unwrap();
+        let heart = PayloadCipher::new(KEY).unwrap();
+        let sealed = seal(&heart, b"");
+        assert!(open(&watchdog, HEADER, &sealed).is_some());
+        // the active senders are kept, the oldest one is forgotten
+        for _ in 0..MAX_SENDERS - 1 {
+            let other = PayloadCipher::new(KEY).unwrap();
+            assert!(open(&watchdog, HEADER, &seal(&other, b"")).is_some());
+        }
+        assert!(open(&watchdog, HEADER, &sealed).is_none());
+        let other = PayloadCipher::new(KEY).unwrap();
+        assert!(open(&watchdog, HEADER, &seal(&other, b"")).is_some());
+        // the payload of the evicted sender is accepted again, then rejected as usual
+        assert!(open(&watchdog, HEADER, &sealed).is_some());
+        assert!(open(&watchdog, HEADER, &sealed).is_none());
+    }
+
+    #[test]
+    fn test_nonces() {
+        let (a, b) = (
+            PayloadCipher::new(KEY).unwrap(),
+            PayloadCipher::new(KEY).unwrap(),
+        );
+        let nonce = |sealed: &[u8]| sealed[..NONCE_SIZE].to_vec();
+        let (a0, a1, b0) = (
+            nonce(&seal(&a, b"")),
+            nonce(&seal(&a, b"")),
+            nonce(&seal(&b, b"")),
+        );
+        assert_eq!(a0[..SENDER_SIZE], a1[..SENDER_SIZE]);
+        assert_ne!(a0, a1);
+        assert_ne!(a0[..SENDER_SIZE], b0[..SENDER_SIZE]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use ::roboplc::{
+    controller::{Context, WResult, Worker, WorkerOptions},
+    hub::Hub,
+    thread_rt::Scheduling,
+    DataDeliveryPolicy,
+};
+use portable_atomic::{AtomicU8, Ordering};
+
+use crate::{io::WatchdogIo, Clock, State, StateEvent, StateSource, Watchdog};
+
+type HubMapper<D> = Arc<dyn Fn(&StateEvent) -> Option<D> + Send + Sync>;
+
+/// Watchdog state variable, which can be a part of the controller shared variables
+///
+/// ```rust,ignore
+/// #[derive(Default)]
+/// struct Variables {
+///     plc_alive: StateVariable,
+/// }
+///
+/// let mut controller = Controller::<Message, Variables>::new();
+/// controller.variables().plc_alive.attach(&watchdog);
+/// ```
+#[derive(Clone)]
+pub struct StateVariable {
+    state: Arc<AtomicU8>,
+}
+
+impl Default for StateVariable {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(AtomicU8::new(State::Fault as u8)),
+        }
+    }
+}
+
+impl StateVariable {
+    /// Create a new state variable (in Fault state until attached)
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Follow the state of the watchdog (or any other state source)
+    pub fn attach<S: StateSource>(&self, source: &S) {
+        self.set(source.state());
+        let state = self.state.clone();
+        source.on_state_change(Box::new(move |event| {
+            if let Some(event_state) = event.state() {
+                state.store(event_state as u8, Ordering::Relaxed);
+            }
+        }));
+    }
+    /// Get the current state
+    pub fn get(&self) -> State {
+        State::from(self.state.load(Ordering::Relaxed))
+    }
+    /// Is the watchdog in OK (or Warning) state
+    pub fn is_alive(&self) -> bool {
+        self.get() != State::Fault
+    }
+    fn set(&self, state: State) {
+        self.state.store(state as u8, Ordering::Relaxed);
+    }
+}
+
+/// Publish the state events of the watchdog (or any other state source) to the controller hub.
+/// The events are mapped to the hub messages, `None` skips the event. The message delivery
+/// policy should not block (e.g. `Latest`/`Single`) as the messages are sent from the watchdog
+/// run loop
+pub fn publish<S, D>(
+    source: &S,
+    hub: &Hub<D>,
+    map: impl Fn(&StateEvent) -> Option<D> + Send + Sync + 'static,
+) where
+    S: StateSource,
+    D: DataDeliveryPolicy + Clone + Send + Sync + 'static,
+{
+    let hub = hub.clone();
+    source.on_state_change(Box::new(move |event| {
+        if let Some(message) = map(event) {
+            hub.send(message);
+        }
+    }));
+}
+
+/// Controller worker which runs the watchdog
+///
+/// ```rust,ignore
+/// controller.spawn_worker(
+///     WatchdogWorker::new(watchdog.clone())
+///         .with_priority(80)
+///         .with_hub(|event| Some(Message::Watchdog(event.clone()))),
+/// )?;
+/// ```
+///
+/// The worker thread is real-time if the priority is set (`FIFO` scheduling by default). The
+/// worker is blocking (it runs until the watchdog fails), if the watchdog run fails, the
+/// controller terminates the process.
+pub struct WatchdogWorker<I: WatchdogIo, C: Clock, D> {
+    watchdog: Watchdog<I, C>,
+    name: String,
+    priority: Option<i32>,
+    scheduling: Scheduling,
+    cpu_ids: Vec<usize>,
+    hub: Option<HubMapper<D>>,
+}
+
+impl<I: WatchdogIo, C: Clock, D> WatchdogWorker<I, C, D> {
+    /// Create a new worker, the worker is named after the watchdog (`watchdog` by default)
+    pub fn new(watchdog: Watchdog<I, C>) -> Self {
+        let name = watchdog.name().unwrap_or("watchdog").to_owned();
+        Self {
+            watchdog,
+            name,
+            priority: None,
+            scheduling: Scheduling::default(),
+            cpu_ids: Vec::new(),
+            hub: None,
+        }
+    }
+    /// Set the worker name (must be unique for the controller)
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+    /// Set the real-time priority, the scheduling is set to `FIFO` unless set explicitly
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        if self.scheduling == Scheduling::default() {
+            self.scheduling = Scheduling::FIFO;
+        }
+        self
+    }
+    /// Set the scheduling policy
+    pub fn with_scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.scheduling = scheduling;
+        self
+    }
+    /// Set the CPU affinity
+    pub fn with_cpu_ids(mut self, cpu_ids: &[usize]) -> Self {
+        self.cpu_ids = cpu_ids.to_vec();
+        self
+    }
+    /// Publish the state events to the controller hub when the worker is started (see
+    /// [`publish`])
+    pub fn with_hub(
+        mut self,
+        map: impl Fn(&StateEvent) -> Option<D> + Send + Sync + 'static,
+    ) -> Self {
+        self.hub = Some(Arc::new(map));
+        self
+    }
+    /// Get the watchdog
+    pub fn watchdog(&self) -> &Watchdog<I, C> {
+        &self.watchdog
+    }
+}
+
+impl<I, C, D, V> Worker<D, V> for WatchdogWorker<I, C, D>
+where
+    I: WatchdogIo + Send + Sync + 'static,
+    C: Clock + Send + Sync + 'static,
+    D: DataDeliveryPolicy + Clone + Send + Sync + 'static,
+    V: Send,
+{
+    fn run(&mut self, context: &Context<D, V>) -> WResult {
+        if let Some(map) = self.hub.take() {
+            publish(&self.watchdog, context.hub(), move |event| map(event));
+        }
+        self.watchdog.run()?;
+        Ok(())
+    }
+}
+
+impl<I: WatchdogIo, C: Clock, D> WorkerOptions for WatchdogWorker<I, C, D> {
+    fn worker_name(&self) -> &str {
+        &self.name
+    }
+    fn worker_scheduling(&self) -> Scheduling {
+        self.scheduling
+    }
+    fn worker_priority(&self) -> Option<i32> {
+        self.priority
+    }
+    fn worker_cpu_ids(&self) -> Option<&[usize]> {
+        (!self.cpu_ids.is_empty()).then_some(self.cpu_ids.as_slice())
+    }
+    fn worker_is_blocking(&self) -> bool {
+        true
+    }
+}
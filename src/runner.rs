@@ -0,0 +1,275 @@
+use core::{
+    future::{poll_fn, Future},
+    pin::pin,
+    task::{Poll, Waker},
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::{sync::Arc, thread};
+
+use portable_atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use rtsc::pi::{Condvar, Mutex};
+
+#[cfg(not(feature = "std"))]
+use crate::io::embassy::embassy_duration;
+#[cfg(feature = "std")]
+use crate::Heart;
+use crate::{Clock, Error, HeartAsync, Result, Shared, SystemClock};
+
+/// Returns the time of the next beat and the number of beats skipped. The beats are scheduled at
+/// fixed points of time, if the next one has already passed (a late beat), the passed ones are
+/// skipped and the first one not before now is returned (a point of time equal to now is due)
+fn reschedule(next: Duration, now: Duration, interval: Duration) -> (Duration, u64) {
+    let next = next + interval;
+    if next >= now {
+        return (next, 0);
+    }
+    let missed = (now - next).as_nanos().div_ceil(interval.as_nanos());
+    (
+        next + interval * u32::try_from(missed).unwrap_or(u32::MAX),
+        u64::try_from(missed).unwrap_or(u64::MAX),
+    )
+}
+
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct RunnerShared {
+    stopped: AtomicBool,
+    lock: Mutex<()>,
+    wakeup: Condvar,
+    errors: AtomicU64,
+    missed: AtomicU64,
+}
+
+/// Automatic heart beating in a dedicated thread
+///
+/// The beats are scheduled at fixed points of time (`start + n * interval`), so the intervals do
+/// not drift with the beat duration and the scheduler latency. If a beat is late (e.g. the heart
+/// has been blocked), the missed beats are skipped instead of being sent in a burst, which would
+/// violate the watchdog window, and the beating continues with the next point of time.
+///
+/// ```rust,ignore
+/// let runner = HeartRunner::spawn(UdpHeart::create("127.0.0.1:9999")?, Duration::from_millis(100))?;
+/// // ...
+/// runner.stop();
+/// runner.join()?;
+/// ```
+///
+/// Beat errors are counted and ignored, the watchdog detects the missing beats.
+#[cfg(feature = "std")]
+#[allow(clippy::module_name_repetitions)]
+pub struct HeartRunner {
+    shared: Arc<RunnerShared>,
+    thread: thread::JoinHandle<()>,
+}
+
+#[cfg(feature = "std")]
+impl HeartRunner {
+    /// Start beating, the first beat is sent immediately
+    pub fn spawn<H: Heart + Send + 'static>(heart: H, interval: Duration) -> Result<Self> {
+        if interval.is_zero() {
+            return Err(Error::Config("interval must be greater than zero"));
+        }
+        let shared = Arc::new(RunnerShared::default());
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("heart-runner".to_owned())
+            .spawn(move || run(&heart, interval, &thread_shared))?;
+        Ok(Self { shared, thread })
+    }
+    /// Stop beating, the thread is woken up immediately
+    pub fn stop(&self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        // the runner either checks the flag under the lock or is already waiting
+        drop(self.shared.lock.lock());
+        self.shared.wakeup.notify_all();
+    }
+    /// Is the runner stopped
+    pub fn is_stopped(&self) -> bool {
+        self.shared.stopped.load(Ordering::Relaxed)
+    }
+    /// Get the number of failed beats
+    pub fn errors(&self) -> u64 {
+        self.shared.errors.load(Ordering::Relaxed)
+    }
+    /// Get the number of beats skipped because of the schedule overruns
+    pub fn missed(&self) -> u64 {
+        self.shared.missed.load(Ordering::Relaxed)
+    }
+    /// Wait for the runner thread to finish (after [`HeartRunner::stop`])
+    pub fn join(self) -> Result<()> {
+        self.thread
+            .join()
+            .map_err(|_| Error::failed("heart runner thread panicked"))
+    }
+}
+
+#[cfg(feature = "std")]
+fn run<H: Heart>(heart: &H, interval: Duration, shared: &RunnerShared) {
+    let clock = SystemClock;
+    let mut next = clock.now();
+    while !shared.stopped.load(Ordering::Relaxed) {
+        if heart.beat().is_err() {
+            shared.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        let missed;
+        (next, missed) = reschedule(next, clock.now(), interval);
+        shared.missed.fetch_add(missed, Ordering::Relaxed);
+        let mut lock = shared.lock.lock();
+        // the wait may be interrupted by a spurious wakeup
+        while !shared.stopped.load(Ordering::Relaxed) {
+            let remaining = next.saturating_sub(clock.now());
+            if remaining.is_zero() {
+                break;
+            }
+            shared.wakeup.wait_for(&mut lock, remaining);
+        }
+    }
+}
+
+/// Automatic heart beating in an async task (tokio, embassy or any other executor)
+///
+/// The beats are scheduled the same way as by [`HeartRunner`]. The runner is stopped with
+/// [`HeartRunnerAsync::stop`] (e.g. from another task, the pending beat or wait is cancelled
+/// immediately) or by dropping the [`HeartRunnerAsync::run`] future.
+///
+/// ```rust,ignore
+/// static RUNNER: StaticCell<HeartRunnerAsync<UartHeart>> = StaticCell::new();
+///
+/// #[embassy_executor::task]
+/// async fn heart_task(runner: &'static HeartRunnerAsync<UartHeart>) {
+///     runner.run().await.unwrap();
+/// }
+///
+/// let runner = RUNNER.init(HeartRunnerAsync::new(heart, Duration::from_millis(100)));
+/// spawner.spawn(heart_task(runner)).unwrap();
+/// ```
+///
+/// Beat errors are counted and ignored, the watchdog detects the missing beats.
+#[allow(clippy::module_name_repetitions)]
+pub struct HeartRunnerAsync<H> {
+    heart: H,
+    interval: Duration,
+    stopped: AtomicBool,
+    waker: Shared<Option<Waker>>,
+    errors: AtomicU64,
+    missed: AtomicU64,
+}
+
+impl<H: HeartAsync> HeartRunnerAsync<H> {
+    /// Create a new runner
+    pub fn new(heart: H, interval: Duration) -> Self {
+        Self {
+            heart,
+            interval,
+            stopped: AtomicBool::new(false),
+            waker: Shared::new(None),
+            errors: AtomicU64::new(0),
+            missed: AtomicU64::new(0),
+        }
+    }
+    /// Get the heart
+    pub fn heart(&self) -> &H {
+        &self.heart
+    }
+    /// Beat until stopped, the first beat is sent immediately. The runner can not be restarted
+    /// after stop and must not be run by several tasks at once
+    pub async fn run(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Err(Error::Config("interval must be greater than zero"));
+        }
+        let clock = SystemClock;
+        let mut next = clock.now();
+        loop {
+            match self.until_stopped(self.heart.beat_async()).await {
+                Some(Ok(())) => {}
+                Some(Err(_)) => {
+                    self.errors.fetch_add(1, Ordering::Relaxed);
+                }
+                None => break,
+            }
+            let missed;
+            (next, missed) = reschedule(next, clock.now(), self.interval);
+            self.missed.fetch_add(missed, Ordering::Relaxed);
+            let remaining = next.saturating_sub(clock.now());
+            #[cfg(feature = "std")]
+            let timer = async_io::Timer::after(remaining);
+            #[cfg(not(feature = "std"))]
+            let timer = embassy_time::Timer::after(embassy_duration(remaining));
+            if self.until_stopped(timer).await.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Stop beating, the running task is woken up immediately
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(waker) = self.waker.with(Option::take) {
+            waker.wake();
+        }
+    }
+    /// Is the runner stopped
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+    /// Get the number of failed beats
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+    /// Get the number of beats skipped because of the schedule overruns
+    pub fn missed(&self) -> u64 {
+        self.missed.load(Ordering::Relaxed)
+    }
+    /// Polls the future until it is ready or the runner is stopped (returns `None`)
+    async fn until_stopped<F: Future>(&self, f: F) -> Option<F::Output> {
+        let mut f = pin!(f);
+        poll_fn(|cx| {
+            if self.is_stopped() {
+                return Poll::Ready(None);
+            }
+            self.waker.with(|w| *w = Some(cx.waker().clone()));
+            // the runner may be stopped before the waker is registered
+            if self.is_stopped() {
+                return Poll::Ready(None);
+            }
+            f.as_mut().poll(cx).map(Some)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_reschedule() {
+        assert_eq!(reschedule(ms(0), ms(10), INTERVAL), (ms(100), 0));
+        assert_eq!(reschedule(ms(100), ms(199), INTERVAL), (ms(200), 0));
+        // the beat is due
+        assert_eq!(reschedule(ms(100), ms(200), INTERVAL), (ms(200), 0));
+    }
+
+    #[test]
+    fn test_reschedule_late() {
+        assert_eq!(reschedule(ms(0), ms(101), INTERVAL), (ms(200), 1));
+        assert_eq!(reschedule(ms(0), ms(150), INTERVAL), (ms(200), 1));
+        // the point of time equal to now is still due
+        assert_eq!(reschedule(ms(0), ms(200), INTERVAL), (ms(200), 1));
+        assert_eq!(reschedule(ms(0), ms(201), INTERVAL), (ms(300), 2));
+        assert_eq!(reschedule(ms(0), ms(1050), INTERVAL), (ms(1100), 10));
+        for now in 101..1000 {
+            let (next, missed) = reschedule(ms(0), ms(now), INTERVAL);
+            assert!(next >= ms(now) && next < ms(now) + INTERVAL);
+            assert_eq!(next, INTERVAL * (u32::try_from(missed).unwrap() + 1));
+        }
+    }
+}
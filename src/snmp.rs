@@ -0,0 +1,625 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::Instant,
+};
+
+use aes::Aes128;
+use cfb_mode::{
+    cipher::{AsyncStreamCipher, KeyIvInit},
+    Encryptor,
+};
+use hmac::{Hmac, Mac};
+use portable_atomic::{AtomicU32, AtomicU64, Ordering};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{Error, FaultKind, Result, State, StateEvent, StateSource};
+
+/// The default notification OID prefix (the net-snmp experimental area, an own enterprise OID
+/// should be used in production)
+pub const DEFAULT_OID: &[u32] = &[1, 3, 6, 1, 4, 1, 8072, 9999, 9999, 1];
+
+const SYS_UP_TIME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 3, 0];
+const SNMP_TRAP_OID: &[u32] = &[1, 3, 6, 1, 6, 3, 1, 1, 4, 1, 0];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_TIME_TICKS: u8 = 0x43;
+const TAG_TRAP_V2: u8 = 0xa7;
+
+const VERSION_2C: i64 = 1;
+const VERSION_3: i64 = 3;
+const SECURITY_MODEL_USM: i64 = 3;
+const MAX_MESSAGE_SIZE: i64 = 65507;
+const FLAG_AUTH: u8 = 0x01;
+const FLAG_PRIV: u8 = 0x02;
+// the default boots counter, for notifier engines which do not persist it
+const DEFAULT_ENGINE_BOOTS: u32 = 1;
+// the maximum boots counter and engine time (RFC 3414 2.2.2)
+const MAX_ENGINE_VALUE: u32 = 0x7fff_ffff;
+
+const MIN_PASSWORD_SIZE: usize = 8;
+// the password is expanded to 1 MiB before hashing (RFC 3414 A.2)
+const PASSWORD_EXPANSION: usize = 1_048_576;
+
+/// SNMPv3 authentication protocol
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AuthProtocol {
+    /// HMAC-SHA-96 (usmHMACSHAAuthProtocol, RFC 3414)
+    Sha1,
+    /// HMAC-SHA-256-192 (usmHMAC192SHA256AuthProtocol, RFC 7860)
+    Sha256,
+}
+
+impl AuthProtocol {
+    fn mac_size(self) -> usize {
+        match self {
+            AuthProtocol::Sha1 => 12,
+            AuthProtocol::Sha256 => 24,
+        }
+    }
+    /// Localize the password to the engine (RFC 3414 A.2)
+    fn localize(self, password: &str, engine_id: &[u8]) -> Result<Vec<u8>> {
+        if password.len() < MIN_PASSWORD_SIZE {
+            return Err(Error::failed(
+                "SNMPv3 passwords must be at least 8 characters long",
+            ));
+        }
+        Ok(match self {
+            AuthProtocol::Sha1 => localize::<Sha1>(password.as_bytes(), engine_id),
+            AuthProtocol::Sha256 => localize::<Sha256>(password.as_bytes(), engine_id),
+        })
+    }
+    fn mac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        // HMAC accepts keys of any size
+        let mut mac = match self {
+            AuthProtocol::Sha1 => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap_or_else(|_| unreachable!());
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            AuthProtocol::Sha256 => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key).unwrap_or_else(|_| unreachable!());
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        };
+        mac.truncate(self.mac_size());
+        mac
+    }
+}
+
+fn localize<D: Digest>(password: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut chunk = [0; 64];
+    let mut pos = 0;
+    for _ in 0..PASSWORD_EXPANSION / chunk.len() {
+        for b in &mut chunk {
+            *b = password[pos % password.len()];
+            pos += 1;
+        }
+        hasher.update(chunk);
+    }
+    let key = hasher.finalize();
+    let mut hasher = D::new();
+    hasher.update(&key);
+    hasher.update(engine_id);
+    hasher.update(&key);
+    hasher.finalize().to_vec()
+}
+
+struct User {
+    name: String,
+    engine_id: Vec<u8>,
+    auth: Option<(AuthProtocol, Vec<u8>)>,
+    privacy: Option<[u8; 16]>,
+}
+
+enum Security {
+    Community(String),
+    User(User),
+}
+
+// BER encoding
+
+fn push_tlv(buf: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    buf.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        #[allow(clippy::cast_possible_truncation)]
+        buf.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        #[allow(clippy::cast_possible_truncation)]
+        buf.push(0x80 | (bytes.len() - skip) as u8);
+        buf.extend_from_slice(&bytes[skip..]);
+    }
+    buf.extend_from_slice(content);
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(content.len() + 4);
+    push_tlv(&mut buf, tag, content);
+    buf
+}
+
+fn sequence(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    tlv(tag, &parts.concat())
+}
+
+fn integer(tag: u8, n: i64) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    // the minimal two's complement form
+    let mut skip = 0;
+    while skip < bytes.len() - 1
+        && ((bytes[skip] == 0 && bytes[skip + 1] & 0x80 == 0)
+            || (bytes[skip] == 0xff && bytes[skip + 1] & 0x80 != 0))
+    {
+        skip += 1;
+    }
+    tlv(tag, &bytes[skip..])
+}
+
+fn octets(data: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, data)
+}
+
+fn oid(arcs: &[u32]) -> Vec<u8> {
+    let mut content = Vec::new();
+    let mut push_arc = |arc: u32| {
+        let mut groups = [0u8; 5];
+        let mut n = 0;
+        let mut v = arc;
+        loop {
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                groups[n] = (v & 0x7f) as u8;
+            }
+            n += 1;
+            v >>= 7;
+            if v == 0 {
+                break;
+            }
+        }
+        for i in (0..n).rev() {
+            content.push(groups[i] | if i > 0 { 0x80 } else { 0 });
+        }
+    };
+    match arcs {
+        [first, second, rest @ ..] => {
+            push_arc(first * 40 + second);
+            for arc in rest {
+                push_arc(*arc);
+            }
+        }
+        [first] => push_arc(first * 40),
+        [] => {}
+    }
+    tlv(TAG_OID, &content)
+}
+
+fn varbind(name: &[u32], value: &[u8]) -> Vec<u8> {
+    sequence(TAG_SEQUENCE, &[&oid(name), value])
+}
+
+/// SNMP trap notifier
+///
+/// Sends SNMPv2c or SNMPv3 (USM) traps on state transitions (except the initial fault, when the
+/// watchdog is started):
+///
+/// ```rust,ignore
+/// SnmpNotifier::v3("nms:162", "watchdog", &engine_id)?
+///     .with_auth(AuthProtocol::Sha1, "authpassword")?
+///     .with_privacy("privpassword")?
+///     .with_engine_boots(boots)
+///     .attach(&watchdog);
+/// ```
+///
+/// The notification OIDs are `<oid>.0.N`, where N is 1 - Fault, 2 - OK, 3 - Warning,
+/// 4 - Degraded, 5 - Paused, 6 - Terminated, 7 - HighLatency. The traps carry the variables `<oid>.1.1` - the
+/// watchdog name (string), `<oid>.1.2` - the state (0 - Fault, 1 - OK, 2 - Warning) and
+/// `<oid>.1.3` - the fault kind (string, e.g. `Timeout`, empty if not a fault).
+///
+/// SNMPv3 traps are sent by the notifier engine (the engine ID must be configured for the user
+/// on the receiver, e.g. `createUser -e <engine ID>` for snmptrapd), AES-128 privacy is
+/// supported. The engine boots counter must be incremented on each start (see
+/// [`SnmpNotifier::with_engine_boots`]).
+#[allow(clippy::module_name_repetitions)]
+pub struct SnmpNotifier {
+    socket: UdpSocket,
+    security: Security,
+    oid: Vec<u32>,
+    started: Instant,
+    engine_boots: u32,
+    request_id: AtomicU32,
+    salt: AtomicU64,
+}
+
+impl SnmpNotifier {
+    fn create<A: ToSocketAddrs>(target: A, security: Security) -> Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::failed("unable to resolve the target"))?;
+        let socket = match target {
+            SocketAddr::V4(_) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+            SocketAddr::V6(_) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?,
+        };
+        socket.connect(target)?;
+        let seed = RandomState::new().build_hasher().finish();
+        Ok(Self {
+            socket,
+            security,
+            oid: DEFAULT_OID.to_vec(),
+            started: Instant::now(),
+            engine_boots: DEFAULT_ENGINE_BOOTS,
+            #[allow(clippy::cast_possible_truncation)]
+            request_id: AtomicU32::new(seed as u32 & 0x7fff_ffff),
+            salt: AtomicU64::new(seed),
+        })
+    }
+    /// Create a new SNMPv2c notifier, the target is usually `host:162`
+    pub fn v2c<A: ToSocketAddrs>(target: A, community: &str) -> Result<Self> {
+        Self::create(target, Security::Community(community.to_owned()))
+    }
+    /// Create a new SNMPv3 notifier without authentication (noAuthNoPriv), the engine ID is the
+    /// notifier one (5-32 bytes, RFC 3411)
+    pub fn v3<A: ToSocketAddrs>(target: A, user: &str, engine_id: &[u8]) -> Result<Self> {
+        if !(5..=32).contains(&engine_id.len()) {
+            return Err(Error::failed("invalid SNMP engine ID"));
+        }
+        Self::create(
+            target,
+            Security::User(User {
+                name: user.to_owned(),
+                engine_id: engine_id.to_vec(),
+                auth: None,
+                privacy: None,
+            }),
+        )
+    }
+    /// Enable SNMPv3 authentication (authNoPriv)
+    pub fn with_auth(mut self, protocol: AuthProtocol, password: &str) -> Result<Self> {
+        let Security::User(ref mut user) = self.security else {
+            return Err(Error::failed("authentication requires SNMPv3"));
+        };
+        user.auth = Some((protocol, protocol.localize(password, &user.engine_id)?));
+        Ok(self)
+    }
+    /// Enable SNMPv3 AES-128 privacy (authPriv), the authentication must be enabled first
+    pub fn with_privacy(mut self, password: &str) -> Result<Self> {
+        let Security::User(ref mut user) = self.security else {
+            return Err(Error::failed("privacy requires SNMPv3"));
+        };
+        let Some((protocol, _)) = user.auth else {
+            return Err(Error::failed("privacy requires authentication"));
+        };
+        let key = protocol.localize(password, &user.engine_id)?;
+        let mut privacy = [0; 16];
+        privacy.copy_from_slice(&key[..16]);
+        user.privacy = Some(privacy);
+        Ok(self)
+    }
+    /// Set the SNMPv3 engine boots counter (snmpEngineBoots, the default is 1). The engine time
+    /// is counted from the notifier creation, so receivers reject the traps of a restarted
+    /// notifier as outside of the time window until the counter is increased: it must be
+    /// persisted and incremented on each start (the counter is latched at 2147483647)
+    pub fn with_engine_boots(mut self, boots: u32) -> Self {
+        self.engine_boots = boots.min(MAX_ENGINE_VALUE);
+        self
+    }
+    /// Set the notification OID prefix (see [`DEFAULT_OID`])
+    pub fn with_oid(mut self, oid: &[u32]) -> Self {
+        self.oid = oid.to_vec();
+        self
+    }
+    /// Send a trap for the state event
+    pub fn send(&self, name: &str, event: &StateEvent) -> Result<()> {
+        let request_id = i64::from(self.request_id.fetch_add(1, Ordering::Relaxed) & 0x7fff_ffff);
+        let pdu = self.pdu(request_id, name, event);
+        let message = match self.security {
+            Security::Community(ref community) => sequence(
+                TAG_SEQUENCE,
+                &[
+                    &integer(TAG_INTEGER, VERSION_2C),
+                    &octets(community.as_bytes()),
+                    &pdu,
+                ],
+            ),
+            Security::User(ref user) => self.v3_message(user, request_id, &pdu),
+        };
+        self.socket.send(&message)?;
+        Ok(())
+    }
+    /// Send the traps on the state transitions of the watchdog (or any other state source),
+    /// send errors are ignored
+    pub fn attach<S: StateSource>(self, source: &S) {
+        let name = source.name().unwrap_or("watchdog").to_owned();
+        source.on_state_change(Box::new(move |event| {
+            if *event != StateEvent::Fault(FaultKind::Initial) {
+                let _ = self.send(&name, event);
+            }
+        }));
+    }
+    fn pdu(&self, request_id: i64, name: &str, event: &StateEvent) -> Vec<u8> {
+        let (notification, fault) = match event {
+            StateEvent::Fault(kind) => (1, format!("{:?}", kind)),
+            StateEvent::Ok => (2, String::new()),
+            StateEvent::Warning => (3, String::new()),
+            StateEvent::Degraded { .. } => (4, String::new()),
+            StateEvent::Paused => (5, String::new()),
+            StateEvent::Terminated => (6, String::new()),
+            StateEvent::HighLatency { .. } => (7, String::new()),
+        };
+        let object = |suffix: &[u32]| [self.oid.as_slice(), suffix].concat();
+        // hundredths of a second, wrapped
+        let uptime = self.started.elapsed().as_millis() / 10 % (1 << 32);
+        let uptime = i64::try_from(uptime).unwrap_or_default();
+        sequence(
+            TAG_TRAP_V2,
+            &[
+                &integer(TAG_INTEGER, request_id),
+                &integer(TAG_INTEGER, 0),
+                &integer(TAG_INTEGER, 0),
+                &sequence(
+                    TAG_SEQUENCE,
+                    &[
+                        &varbind(SYS_UP_TIME, &integer(TAG_TIME_TICKS, uptime)),
+                        &varbind(SNMP_TRAP_OID, &oid(&object(&[0, notification]))),
+                        &varbind(&object(&[1, 1]), &octets(name.as_bytes())),
+                        &varbind(
+                            &object(&[1, 2]),
+                            &integer(TAG_INTEGER, State::from(event.clone()) as i64),
+                        ),
+                        &varbind(&object(&[1, 3]), &octets(fault.as_bytes())),
+                    ],
+                ),
+            ],
+        )
+    }
+    fn v3_message(&self, user: &User, msg_id: i64, pdu: &[u8]) -> Vec<u8> {
+        let time = u32::try_from(self.started.elapsed().as_secs())
+            .unwrap_or(u32::MAX)
+            .min(MAX_ENGINE_VALUE);
+        let scoped_pdu = sequence(TAG_SEQUENCE, &[&octets(&user.engine_id), &octets(&[]), pdu]);
+        let mut flags = 0;
+        let (data, priv_params) = if let Some(ref key) = user.privacy {
+            flags |= FLAG_PRIV;
+            // RFC 3826: the IV is the engine boots, the engine time and the salt
+            let salt = self.salt.fetch_add(1, Ordering::Relaxed).to_be_bytes();
+            let mut iv = [0; 16];
+            iv[..4].copy_from_slice(&self.engine_boots.to_be_bytes());
+            iv[4..8].copy_from_slice(&time.to_be_bytes());
+            iv[8..].copy_from_slice(&salt);
+            let mut encrypted = scoped_pdu;
+            Encryptor::<Aes128>::new(key.into(), &iv.into()).encrypt(&mut encrypted);
+            (octets(&encrypted), salt.to_vec())
+        } else {
+            (scoped_pdu, Vec::new())
+        };
+        let mac_size = user.auth.as_ref().map_or(0, |(protocol, _)| {
+            flags |= FLAG_AUTH;
+            protocol.mac_size()
+        });
+        let priv_params = octets(&priv_params);
+        let security_params = sequence(
+            TAG_SEQUENCE,
+            &[
+                &octets(&user.engine_id),
+                &integer(TAG_INTEGER, i64::from(self.engine_boots)),
+                &integer(TAG_INTEGER, i64::from(time)),
+                &octets(user.name.as_bytes()),
+                &octets(&vec![0; mac_size]),
+                &priv_params,
+            ],
+        );
+        let mut message = sequence(
+            TAG_SEQUENCE,
+            &[
+                &integer(TAG_INTEGER, VERSION_3),
+                &sequence(
+                    TAG_SEQUENCE,
+                    &[
+                        &integer(TAG_INTEGER, msg_id),
+                        &integer(TAG_INTEGER, MAX_MESSAGE_SIZE),
+                        &octets(&[flags]),
+                        &integer(TAG_INTEGER, SECURITY_MODEL_USM),
+                    ],
+                ),
+                &octets(&security_params),
+                &data,
+            ],
+        );
+        if let Some((protocol, ref key)) = user.auth {
+            // the MAC is calculated with the zero-filled authentication parameters, which are
+            // followed by the privacy parameters and the data
+            let pos = message.len() - data.len() - priv_params.len() - mac_size;
+            let mac = protocol.mac(key, &message);
+            message[pos..pos + mac_size].copy_from_slice(&mac);
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write as _;
+
+    use super::*;
+
+    // RFC 3414 A.3.2
+    const ENGINE_ID: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+    const PASSWORD: &str = "maplesyrup";
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "{:02x}", b);
+            s
+        })
+    }
+
+    /// Splits the data into the tag, the content and the rest
+    fn read_tlv(data: &[u8]) -> (u8, &[u8], &[u8]) {
+        let (tag, len) = (data[0], data[1]);
+        let (len, start) = if len < 0x80 {
+            (usize::from(len), 2)
+        } else {
+            let n = usize::from(len & 0x7f);
+            let len = data[2..2 + n]
+                .iter()
+                .fold(0, |acc, b| acc << 8 | usize::from(*b));
+            (len, 2 + n)
+        };
+        (tag, &data[start..start + len], &data[start + len..])
+    }
+
+    fn read_all(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut items = Vec::new();
+        while !data.is_empty() {
+            let (tag, content, rest) = read_tlv(data);
+            items.push((tag, content));
+            data = rest;
+        }
+        items
+    }
+
+    fn receive(notifier: SnmpNotifier, socket: &UdpSocket, event: &StateEvent) -> Vec<u8> {
+        notifier.send("test", event).unwrap();
+        let mut buf = [0; 1500];
+        let len = socket.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn receiver() -> UdpSocket {
+        UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap()
+    }
+
+    #[test]
+    fn test_localize() {
+        assert_eq!(
+            hex(&AuthProtocol::Sha1.localize(PASSWORD, ENGINE_ID).unwrap()),
+            "6695febc9288e36282235fc7151f128497b38f3f"
+        );
+        assert_eq!(
+            hex(&AuthProtocol::Sha256.localize(PASSWORD, ENGINE_ID).unwrap()),
+            "8982e0e549e866db361a6b625d84cccc11162d453ee8ce3a6445c2d6776f0f8b"
+        );
+        assert!(AuthProtocol::Sha1.localize("short", ENGINE_ID).is_err());
+    }
+
+    #[test]
+    fn test_ber() {
+        assert_eq!(integer(TAG_INTEGER, 0), [0x02, 0x01, 0x00]);
+        assert_eq!(integer(TAG_INTEGER, 127), [0x02, 0x01, 0x7f]);
+        assert_eq!(integer(TAG_INTEGER, 128), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(TAG_INTEGER, -1), [0x02, 0x01, 0xff]);
+        assert_eq!(integer(TAG_INTEGER, -129), [0x02, 0x02, 0xff, 0x7f]);
+        assert_eq!(
+            oid(SYS_UP_TIME),
+            [0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00]
+        );
+        assert_eq!(
+            oid(&[1, 3, 6, 1, 4, 1, 8072]),
+            [0x06, 0x07, 0x2b, 0x06, 0x01, 0x04, 0x01, 0xbf, 0x08]
+        );
+        let long = octets(&[0; 200]);
+        assert_eq!(long[..3], [0x04, 0x81, 0xc8]);
+        assert_eq!(long.len(), 203);
+        let long = octets(&[0; 300]);
+        assert_eq!(long[..4], [0x04, 0x82, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn test_v2c() {
+        let socket = receiver();
+        let notifier = SnmpNotifier::v2c(socket.local_addr().unwrap(), "public").unwrap();
+        let message = receive(notifier, &socket, &StateEvent::Fault(FaultKind::Timeout));
+        let (tag, content, rest) = read_tlv(&message);
+        assert_eq!(tag, TAG_SEQUENCE);
+        assert!(rest.is_empty());
+        let items = read_all(content);
+        assert_eq!(items[0], (TAG_INTEGER, &[1][..]));
+        assert_eq!(items[1], (TAG_OCTET_STRING, &b"public"[..]));
+        let (tag, pdu) = items[2];
+        assert_eq!(tag, TAG_TRAP_V2);
+        let pdu = read_all(pdu);
+        assert_eq!(pdu[1], (TAG_INTEGER, &[0][..]));
+        assert_eq!(pdu[2], (TAG_INTEGER, &[0][..]));
+        let varbinds = read_all(pdu[3].1);
+        assert_eq!(varbinds.len(), 5);
+        let trap_oid = read_all(varbinds[1].1);
+        assert_eq!(trap_oid[0].1, &oid(SNMP_TRAP_OID)[2..]);
+        assert_eq!(trap_oid[1].1, &oid(&[DEFAULT_OID, &[0, 1]].concat())[2..]);
+        let fault = read_all(varbinds[4].1);
+        assert_eq!(fault[1], (TAG_OCTET_STRING, &b"Timeout"[..]));
+    }
+
+    #[test]
+    fn test_v3() {
+        let socket = receiver();
+        let notifier = SnmpNotifier::v3(socket.local_addr().unwrap(), "watchdog", ENGINE_ID)
+            .unwrap()
+            .with_auth(AuthProtocol::Sha1, PASSWORD)
+            .unwrap()
+            .with_privacy(PASSWORD)
+            .unwrap()
+            .with_engine_boots(7);
+        let message = receive(notifier, &socket, &StateEvent::Ok);
+        let items = read_all(read_tlv(&message).1);
+        assert_eq!(items[0], (TAG_INTEGER, &[3][..]));
+        let header = read_all(items[1].1);
+        assert_eq!(header[2], (TAG_OCTET_STRING, &[FLAG_AUTH | FLAG_PRIV][..]));
+        let security = read_all(read_tlv(items[2].1).1);
+        assert_eq!(security[0], (TAG_OCTET_STRING, ENGINE_ID));
+        assert_eq!(security[1], (TAG_INTEGER, &[7][..]));
+        assert_eq!(security[3], (TAG_OCTET_STRING, &b"watchdog"[..]));
+        // the MAC is verified with the zero-filled authentication parameters
+        let key = AuthProtocol::Sha1.localize(PASSWORD, ENGINE_ID).unwrap();
+        let mac = security[4].1;
+        assert_eq!(mac.len(), 12);
+        let pos = message.windows(mac.len()).position(|w| w == mac).unwrap();
+        let mut unsigned = message.clone();
+        unsigned[pos..pos + mac.len()].fill(0);
+        assert_eq!(AuthProtocol::Sha1.mac(&key, &unsigned), mac);
+        // RFC 3826: the IV is the engine boots, the engine time and the salt
+        let time = u32::try_from(
+            security[2]
+                .1
+                .iter()
+                .fold(0, |acc, b| acc << 8 | u64::from(*b)),
+        )
+        .unwrap();
+        let mut iv = [0; 16];
+        iv[..4].copy_from_slice(&7u32.to_be_bytes());
+        iv[4..8].copy_from_slice(&time.to_be_bytes());
+        iv[8..].copy_from_slice(security[5].1);
+        let (tag, encrypted) = items[3];
+        assert_eq!(tag, TAG_OCTET_STRING);
+        let mut scoped_pdu = encrypted.to_vec();
+        cfb_mode::Decryptor::<Aes128>::new(key[..16].into(), &iv.into()).decrypt(&mut scoped_pdu);
+        let scoped_pdu = read_all(read_tlv(&scoped_pdu).1);
+        assert_eq!(scoped_pdu[0], (TAG_OCTET_STRING, ENGINE_ID));
+        assert_eq!(scoped_pdu[2].0, TAG_TRAP_V2);
+    }
+
+    #[test]
+    fn test_engine_boots() {
+        let socket = receiver();
+        let notifier = SnmpNotifier::v3(socket.local_addr().unwrap(), "watchdog", ENGINE_ID)
+            .unwrap()
+            .with_engine_boots(u32::MAX);
+        let message = receive(notifier, &socket, &StateEvent::Ok);
+        let items = read_all(read_tlv(&message).1);
+        let security = read_all(read_tlv(items[2].1).1);
+        assert_eq!(security[1], (TAG_INTEGER, &[0x7f, 0xff, 0xff, 0xff][..]));
+        // noAuthNoPriv
+        assert!(security[4].1.is_empty());
+        assert!(security[5].1.is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+use core::time::Duration;
+
+use crate::{FaultKind, Range, State, StateEvent, WatchdogConfig};
+
+const FAULT_KINDS: usize = 7;
+
+/// Maximum number of histogram buckets
+pub const MAX_HISTOGRAM_BUCKETS: usize = 64;
+
+/// Maximum number of state events kept in the history
+pub const MAX_HISTORY: usize = 32;
+
+/// Inter-beat interval histogram
+///
+/// The bucket `i` counts intervals in the range `[i * width, (i + 1) * width)`, the last bucket
+/// also counts all longer intervals.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bucket_width: Duration,
+    len: usize,
+    buckets: [u64; MAX_HISTOGRAM_BUCKETS],
+}
+
+impl Histogram {
+    pub(crate) fn new(bucket_width: Duration, buckets: usize) -> Self {
+        Self {
+            bucket_width,
+            // rejected by the config validation, clamped for watchdogs created without it
+            len: buckets.clamp(1, MAX_HISTOGRAM_BUCKETS),
+            buckets: [0; MAX_HISTOGRAM_BUCKETS],
+        }
+    }
+    fn record(&mut self, interval: Duration) {
+        let n = if self.bucket_width.is_zero() {
+            self.len - 1
+        } else {
+            usize::try_from(interval.as_nanos() / self.bucket_width.as_nanos())
+                .unwrap_or(usize::MAX)
+                .min(self.len - 1)
+        };
+        self.buckets[n] += 1;
+    }
+    /// Get the bucket width
+    pub fn bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+    /// Get the bucket counters
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets[..self.len]
+    }
+}
+
+/// Watchdog statistics
+///
+/// Inter-beat intervals are measured between consecutive edges received by the watchdog I/O,
+/// intervals interrupted by a fault (and the warmup which follows it) are not counted.
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct WatchdogStats {
+    beats: u64,
+    lost: u64,
+    rejected: u64,
+    faults: [u64; FAULT_KINDS],
+    intervals: u64,
+    interval_sum_us: u64,
+    min_interval: Option<Duration>,
+    max_interval: Option<Duration>,
+    histogram: Option<Histogram>,
+    latencies: u64,
+    latency_sum_us: u64,
+    last_latency: Option<Duration>,
+    min_latency: Option<Duration>,
+    max_latency: Option<Duration>,
+}
+
+impl WatchdogStats {
+    pub(crate) fn new(histogram: Option<Histogram>) -> Self {
+        Self {
+            histogram,
+            ..Self::default()
+        }
+    }
+    pub(crate) fn record_beat(&mut self, interval: Option<Duration>, latency: Option<Duration>) {
+        self.beats += 1;
+        if let Some(latency) = latency {
+            self.latencies += 1;
+            self.latency_sum_us = self
+                .latency_sum_us
+                .saturating_add(u64::try_from(latency.as_micros()).unwrap_or(u64::MAX));
+            self.last_latency = Some(latency);
+            self.min_latency = Some(self.min_latency.map_or(latency, |v| v.min(latency)));
+            self.max_latency = Some(self.max_latency.map_or(latency, |v| v.max(latency)));
+        }
+        let Some(interval) = interval else {
+            return;
+        };
+        self.intervals += 1;
+        self.interval_sum_us = self
+            .interval_sum_us
+            .saturating_add(u64::try_from(interval.as_micros()).unwrap_or(u64::MAX));
+        self.min_interval = Some(self.min_interval.map_or(interval, |v| v.min(interval)));
+        self.max_interval = Some(self.max_interval.map_or(interval, |v| v.max(interval)));
+        if let Some(ref mut histogram) = self.histogram {
+            histogram.record(interval);
+        }
+    }
+    pub(crate) fn record_lost(&mut self, lost: u32) {
+        self.lost += u64::from(lost);
+    }
+    pub(crate) fn record_rejected(&mut self, rejected: u32) {
+        self.rejected += u64::from(rejected);
+    }
+    pub(crate) fn record_fault(&mut self, kind: FaultKind) {
+        self.faults[kind as usize] += 1;
+    }
+    /// Get the number of beats (edges) received
+    pub fn beats(&self) -> u64 {
+        self.beats
+    }
+    /// Get the number of beats lost (reported by sequenced protocols)
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+    /// Get the number of packets rejected by the I/O (e.g. failed authentication)
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+    /// Get the number of faults of the given kind
+    pub fn faults(&self, kind: FaultKind) -> u64 {
+        self.faults[kind as usize]
+    }
+    /// Get the total number of faults (including the initial one)
+    pub fn total_faults(&self) -> u64 {
+        self.faults.iter().sum()
+    }
+    /// Get the minimum inter-beat interval
+    pub fn min_interval(&self) -> Option<Duration> {
+        self.min_interval
+    }
+    /// Get the maximum inter-beat interval
+    pub fn max_interval(&self) -> Option<Duration> {
+        self.max_interval
+    }
+    /// Get the mean inter-beat interval
+    pub fn mean_interval(&self) -> Option<Duration> {
+        if self.intervals == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(self.interval_sum_us / self.intervals))
+    }
+    /// Get the inter-beat interval histogram (if enabled with
+    /// [`crate::WatchdogConfig::with_histogram`])
+    pub fn histogram(&self) -> Option<&Histogram> {
+        self.histogram.as_ref()
+    }
+    /// Get the one-way latency of the last beat (timestamped protocols only)
+    pub fn last_latency(&self) -> Option<Duration> {
+        self.last_latency
+    }
+    /// Get the minimum one-way latency
+    pub fn min_latency(&self) -> Option<Duration> {
+        self.min_latency
+    }
+    /// Get the maximum one-way latency
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.max_latency
+    }
+    /// Get the mean one-way latency
+    pub fn mean_latency(&self) -> Option<Duration> {
+        if self.latencies == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(self.latency_sum_us / self.latencies))
+    }
+}
+
+/// State event history entry
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    event: StateEvent,
+    time: Duration,
+    latency: Option<Duration>,
+}
+
+impl HistoryEntry {
+    /// Get the state event
+    pub fn event(&self) -> &StateEvent {
+        &self.event
+    }
+    /// Get the event time (the watchdog clock time, see [`crate::Clock`])
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+    /// Get the one-way latency of the last beat received before the event (timestamped
+    /// protocols only), helps to distinguish network delays from sender stalls
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+}
+
+/// State event history (the last events, oldest first)
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: [Option<HistoryEntry>; MAX_HISTORY],
+    capacity: usize,
+    // the next entry to write
+    pos: usize,
+}
+
+impl History {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: core::array::from_fn(|_| None),
+            // rejected by the config validation, clamped for watchdogs created without it
+            capacity: capacity.min(MAX_HISTORY),
+            pos: 0,
+        }
+    }
+    pub(crate) fn push(&mut self, event: StateEvent, latency: Option<Duration>, time: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries[self.pos] = Some(HistoryEntry {
+            event,
+            time,
+            latency,
+        });
+        self.pos = (self.pos + 1) % self.capacity;
+    }
+    /// Get the history capacity
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Get the number of events in the history
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+    /// Is the history empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Iterate over the events, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &HistoryEntry> {
+        let (newer, older) = self.entries[..self.capacity].split_at(self.pos);
+        older.iter().chain(newer).flatten()
+    }
+}
+
+/// Watchdog availability (time spent in OK and Fault states)
+///
+/// The time when the watchdog is paused is counted for the state it has been paused in, the time
+/// in Warning state is counted as OK.
+#[derive(Debug, Clone, Copy)]
+pub struct Availability {
+    time_ok: Duration,
+    time_fault: Duration,
+    last_transition: Duration,
+}
+
+impl Availability {
+    /// Get the cumulative time in OK state
+    pub fn time_ok(&self) -> Duration {
+        self.time_ok
+    }
+    /// Get the cumulative time in Fault state
+    pub fn time_fault(&self) -> Duration {
+        self.time_fault
+    }
+    /// Get the time of the last transition between OK (including Warning) and Fault states (the
+    /// watchdog clock time, see [`crate::Clock`])
+    pub fn last_transition(&self) -> Duration {
+        self.last_transition
+    }
+    /// Get the ratio of time in OK state (0.0 - 1.0)
+    pub fn ratio(&self) -> f64 {
+        let total = (self.time_ok + self.time_fault).as_secs_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.time_ok.as_secs_f64() / total
+    }
+}
+
+pub(crate) struct AvailabilityTracker {
+    state: State,
+    since: Duration,
+    time_ok: Duration,
+    time_fault: Duration,
+}
+
+impl AvailabilityTracker {
+    pub(crate) fn new(now: Duration) -> Self {
+        Self {
+            state: State::Fault,
+            since: now,
+            time_ok: Duration::ZERO,
+            time_fault: Duration::ZERO,
+        }
+    }
+    fn times(&self, now: Duration) -> (Duration, Duration) {
+        let current = now.saturating_sub(self.since);
+        match self.state {
+            State::Ok | State::Warning => (self.time_ok + current, self.time_fault),
+            State::Fault => (self.time_ok, self.time_fault + current),
+        }
+    }
+    pub(crate) fn transition(&mut self, state: State, now: Duration) {
+        // Warning is counted as OK, so OK <-> Warning is not a transition
+        if (state == State::Fault) == (self.state == State::Fault) {
+            self.state = state;
+            return;
+        }
+        (self.time_ok, self.time_fault) = self.times(now);
+        self.state = state;
+        self.since = now;
+    }
+    pub(crate) fn snapshot(&self, now: Duration) -> Availability {
+        let (time_ok, time_fault) = self.times(now);
+        Availability {
+            time_ok,
+            time_fault,
+            last_transition: self.since,
+        }
+    }
+}
+
+/// Watchdog status snapshot (see [`crate::Watchdog::status`]), e.g. to be exposed by a health
+/// endpoint. The times are relative to the moment the snapshot has been taken
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchdogStatus {
+    /// The watchdog name
+    #[cfg(feature = "std")]
+    pub name: Option<String>,
+    /// The current state
+    pub state: State,
+    /// Is the watchdog paused
+    pub paused: bool,
+    /// The time spent in the current state (Warning is counted as OK)
+    pub state_duration: Duration,
+    /// The kind of the last fault
+    pub last_fault: Option<FaultKind>,
+    /// The time elapsed since the last fault
+    pub last_fault_elapsed: Option<Duration>,
+    /// The number of beats received
+    pub beats: u64,
+    /// The number of beats lost
+    pub lost: u64,
+    /// The number of packets rejected by the I/O
+    pub rejected: u64,
+    /// The total number of faults (including the initial one)
+    pub faults: u64,
+    /// The mean inter-beat interval
+    pub mean_interval: Option<Duration>,
+    /// The maximum inter-beat interval
+    pub max_interval: Option<Duration>,
+    /// The mean one-way latency (timestamped protocols only)
+    pub mean_latency: Option<Duration>,
+    /// The ratio of time in OK state (0.0 - 1.0)
+    pub availability: f64,
+    /// The configured beat interval
+    pub interval: Duration,
+    /// The configured heartbeat range
+    pub range: Range,
+    /// The I/O timeout
+    pub io_timeout: Duration,
+}
+
+impl WatchdogStatus {
+    pub(crate) fn new(
+        state: State,
+        paused: bool,
+        config: &WatchdogConfig,
+        counters: &WatchdogStats,
+        last_fault: Option<(FaultKind, Duration)>,
+        availability: &Availability,
+        now: Duration,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            name: None,
+            state,
+            paused,
+            state_duration: now.saturating_sub(availability.last_transition()),
+            last_fault: last_fault.map(|(kind, _)| kind),
+            last_fault_elapsed: last_fault.map(|(_, time)| now.saturating_sub(time)),
+            beats: counters.beats(),
+            lost: counters.lost(),
+            rejected: counters.rejected(),
+            faults: counters.total_faults(),
+            mean_interval: counters.mean_interval(),
+            max_interval: counters.max_interval(),
+            mean_latency: counters.mean_latency(),
+            availability: availability.ratio(),
+            interval: config.interval(),
+            range: config.range().clone(),
+            io_timeout: config.io_timeout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_availability_warning() {
+        let mut tracker = AvailabilityTracker::new(secs(0));
+        tracker.transition(State::Ok, secs(1));
+        tracker.transition(State::Warning, secs(3));
+        tracker.transition(State::Ok, secs(4));
+        let availability = tracker.snapshot(secs(6));
+        assert_eq!(availability.last_transition(), secs(1));
+        assert_eq!(availability.time_ok(), secs(5));
+        assert_eq!(availability.time_fault(), secs(1));
+        tracker.transition(State::Warning, secs(7));
+        tracker.transition(State::Fault, secs(8));
+        let availability = tracker.snapshot(secs(10));
+        assert_eq!(availability.last_transition(), secs(8));
+        assert_eq!(availability.time_ok(), secs(7));
+        assert_eq!(availability.time_fault(), secs(3));
+    }
+}
@@ -0,0 +1,169 @@
+use core::time::Duration;
+use std::{
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rtsc::channel;
+use serde_json::{json, Value};
+
+use crate::{
+    io::http::HttpClient, logging, Condvar, Error, FaultKind, RawMutex, Result, RetryPolicy, State,
+    StateEvent, StateSource,
+};
+
+/// The maximum number of notifications waiting to be sent, newer ones are dropped when exceeded
+const QUEUE_SIZE: usize = 64;
+
+type BodyFn = Box<dyn Fn(&Notification) -> Value + Send>;
+
+/// A state transition to notify about
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// The watchdog name
+    pub name: String,
+    /// The state event
+    pub event: StateEvent,
+    /// The time of the transition
+    pub timestamp: SystemTime,
+}
+
+impl Notification {
+    /// The state after the transition
+    pub fn state(&self) -> State {
+        self.event.clone().into()
+    }
+    /// The fault kind, if the watchdog has switched to Fault state
+    pub fn fault(&self) -> Option<FaultKind> {
+        match self.event {
+            StateEvent::Fault(kind) => Some(kind),
+            _ => None,
+        }
+    }
+    /// The default request body:
+    ///
+    /// ```json
+    /// {"name":"plc1","state":"Fault","event":"Fault","fault":"Timeout","timestamp":1700000000.5}
+    /// ```
+    ///
+    /// The timestamp is UNIX time in seconds, the fault is null for other events
+    pub fn to_json(&self) -> Value {
+        let event = match self.event {
+            StateEvent::Fault(_) => "Fault",
+            StateEvent::Ok => "Ok",
+            StateEvent::Warning => "Warning",
+            StateEvent::Paused => "Paused",
+            StateEvent::Degraded { .. } => "Degraded",
+            StateEvent::HighLatency { .. } => "HighLatency",
+            StateEvent::Terminated => "Terminated",
+        };
+        json!({
+            "name": self.name,
+            "state": format!("{:?}", self.state()),
+            "event": event,
+            "fault": self.fault().map(|kind| format!("{:?}", kind)),
+            "timestamp": self
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+        })
+    }
+}
+
+/// HTTP webhook notifier
+///
+/// State transitions are sent as JSON POST requests, so faults can be delivered to chat/alerting
+/// services directly (e.g. Slack incoming webhooks, PagerDuty Events API) with a custom body (see
+/// [`Webhook::with_body`]). "https://" URLs require the `tls` feature and a TLS configuration
+/// (see [`Webhook::with_tls_config`]).
+///
+/// ```rust,ignore
+/// Webhook::create("https://hooks.slack.com/services/...", Duration::from_secs(5))?
+///     .with_tls_config(tls_config)
+///     .with_body(|n| json!({ "text": format!("{}: {:?}", n.name, n.event) }))
+///     .attach(&watchdog)?;
+/// ```
+pub struct Webhook {
+    client: HttpClient,
+    retry: RetryPolicy,
+    body: BodyFn,
+}
+
+impl Webhook {
+    /// Create a new notifier, the timeout is used for both connection and requests
+    pub fn create(url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            client: HttpClient::create_https(url, timeout)?,
+            retry: RetryPolicy::new(Duration::from_secs(1))
+                .with_max_delay(Duration::from_secs(60))
+                .with_max_retries(5),
+            body: Box::new(Notification::to_json),
+        })
+    }
+    /// Set the retry policy for failed requests (1 second doubled up to 1 minute, 5 retries by
+    /// default). If the retries are exceeded, the notification is dropped
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    /// Add a request header (e.g. `Authorization`)
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.client.add_header(name, value);
+        self
+    }
+    /// Set a custom request body (the default one is [`Notification::to_json`])
+    pub fn with_body(mut self, body: impl Fn(&Notification) -> Value + Send + 'static) -> Self {
+        self.body = Box::new(body);
+        self
+    }
+    /// Set the TLS configuration for "https://" URLs (required), e.g. built with
+    /// [`crate::io::tls::rustls`] and the root certificates the server is verified with
+    #[cfg(feature = "tls")]
+    pub fn with_tls_config(mut self, config: std::sync::Arc<rustls::ClientConfig>) -> Self {
+        self.client.set_tls_config(config);
+        self
+    }
+    /// Send a notification (a single attempt)
+    pub fn send(&self, notification: &Notification) -> Result<()> {
+        let body = serde_json::to_vec(&(self.body)(notification)).map_err(Error::failed)?;
+        self.client.post("application/json", &body)?;
+        Ok(())
+    }
+    /// Send the notifications on the state transitions of the watchdog (or any other state
+    /// source). The requests are sent in order from a background thread, failed ones are retried
+    /// according to the retry policy
+    pub fn attach<S: StateSource>(self, source: &S) -> Result<()> {
+        let (tx, rx) = channel::bounded::<Notification, RawMutex, Condvar>(QUEUE_SIZE);
+        let name = source.name().unwrap_or("watchdog").to_owned();
+        source.on_state_change(Box::new(move |event| {
+            if *event != StateEvent::Fault(FaultKind::Initial) {
+                // never blocks, the notification is dropped if the queue is full
+                let _ = tx.try_send(Notification {
+                    name: name.clone(),
+                    event: event.clone(),
+                    timestamp: SystemTime::now(),
+                });
+            }
+        }));
+        thread::Builder::new()
+            .name("watchdog-webhook".to_owned())
+            .spawn(move || {
+                while let Ok(notification) = rx.recv() {
+                    self.deliver(&notification);
+                }
+            })?;
+        Ok(())
+    }
+    fn deliver(&self, notification: &Notification) {
+        let mut retry = 0;
+        while let Err(e) = self.send(notification) {
+            retry += 1;
+            if self.retry.max_retries().is_some_and(|max| retry > max) {
+                logging::notification_failed("webhook", &e);
+                return;
+            }
+            thread::sleep(self.retry.backoff(retry));
+        }
+    }
+}